@@ -0,0 +1,498 @@
+use std::cell::RefCell;
+use std::collections::BTreeSet;
+use std::path::PathBuf;
+use std::rc::Rc;
+
+use macroquad::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::cosmetics::{reward_label, Slot};
+use crate::daily::{load_toml, save_toml};
+use crate::events::{GameEvent, Listener};
+use crate::scoring::ScoreEvent;
+use crate::theme::Palette;
+use crate::ui::{draw_label, label_width};
+use crate::FIXED_DT;
+
+// 成就：StatTracker 订阅游戏事件，统计本局的得分、星星、坚持的时间和复活题的作答，
+// 主循环只把计入个人最佳的对局（不是演示、没有机器人帮忙）的统计交给 Achievements。
+// 进度和已解锁的成就保存在 profiles/<档案名>/achievements.toml，部分成就解锁恐龙的颜色或帽子
+
+pub const ACHIEVEMENTS_FILE: &str = "achievements.toml";
+// 解锁提示显示的秒数
+const TOAST_DURATION: f32 = 3.0;
+// 一秒的步数
+const SECOND_TICKS: u32 = (1.0 / FIXED_DT + 0.5) as u32;
+
+// 一局中的统计
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Stat {
+    // 本局到目前为止的得分
+    Score(u32),
+    // 本局到目前为止收集的星星
+    Stars(u32),
+    // 开局或上次复活以来坚持的秒数
+    Survived(u32),
+    // 回答了一道复活题
+    Answer { correct: bool },
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Goal {
+    Score,
+    Stars,
+    Survived,
+    AnswerStreak,
+}
+
+pub struct Achievement {
+    pub id: &'static str,
+    // 中文和英文的名字、说明
+    name: (&'static str, &'static str),
+    description: (&'static str, &'static str),
+    goal: Goal,
+    target: u32,
+    // 解锁的外观
+    pub reward: Option<(Slot, &'static str)>,
+}
+
+impl Achievement {
+    pub fn name(&self, chinese: bool) -> &'static str {
+        if chinese {
+            self.name.0
+        } else {
+            self.name.1
+        }
+    }
+
+    fn description(&self, chinese: bool) -> &'static str {
+        if chinese {
+            self.description.0
+        } else {
+            self.description.1
+        }
+    }
+}
+
+pub const ACHIEVEMENTS: [Achievement; 6] = [
+    Achievement {
+        id: "first_hundred",
+        name: ("初露锋芒", "First hundred"),
+        description: ("一局得到 100 分", "Score 100 points in a run"),
+        goal: Goal::Score,
+        target: 100,
+        reward: None,
+    },
+    Achievement {
+        id: "high_scorer",
+        name: ("高分选手", "High scorer"),
+        description: ("一局得到 500 分", "Score 500 points in a run"),
+        goal: Goal::Score,
+        target: 500,
+        reward: Some((Slot::Color, "gold")),
+    },
+    Achievement {
+        id: "star_collector",
+        name: ("摘星者", "Star collector"),
+        description: ("一局收集 50 颗星星", "Collect 50 stars in a run"),
+        goal: Goal::Stars,
+        target: 50,
+        reward: Some((Slot::Hat, "crown")),
+    },
+    Achievement {
+        id: "second_chance",
+        name: ("再来一次", "Second chance"),
+        description: ("答对复活题", "Answer a revive question"),
+        goal: Goal::AnswerStreak,
+        target: 1,
+        reward: None,
+    },
+    Achievement {
+        id: "quiz_whiz",
+        name: ("数学小天才", "Quiz whiz"),
+        description: (
+            "连续答对 10 道复活题",
+            "Answer 10 revive questions in a row",
+        ),
+        goal: Goal::AnswerStreak,
+        target: 10,
+        reward: Some((Slot::Hat, "mortarboard")),
+    },
+    Achievement {
+        id: "marathon",
+        name: ("马拉松", "Marathon"),
+        description: ("不复活坚持 2 分钟", "Survive 2 minutes without a revive"),
+        goal: Goal::Survived,
+        target: 120,
+        reward: Some((Slot::Color, "midnight")),
+    },
+];
+
+// 保存在档案中的进度
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Progress {
+    // 已解锁的成就 id
+    pub unlocked: BTreeSet<String>,
+    // 各项统计历来的最好成绩
+    pub best_score: u32,
+    pub best_stars: u32,
+    pub best_survived: u32,
+    pub best_answer_streak: u32,
+    // 当前连续答对的题数，跨局累计，答错时清零
+    pub answer_streak: u32,
+}
+
+impl Progress {
+    fn value(&self, goal: Goal) -> u32 {
+        match goal {
+            Goal::Score => self.best_score,
+            Goal::Stars => self.best_stars,
+            Goal::Survived => self.best_survived,
+            Goal::AnswerStreak => self.best_answer_streak,
+        }
+    }
+}
+
+pub struct Achievements {
+    progress: Progress,
+    path: PathBuf,
+    // 有还没保存的进度
+    dirty: bool,
+    // 等待显示的解锁提示（ACHIEVEMENTS 的下标），第一个正在显示
+    toasts: Vec<usize>,
+    toast_age: f32,
+}
+
+impl Achievements {
+    pub fn load(path: PathBuf) -> Self {
+        Self {
+            progress: load_toml(&path),
+            path,
+            dirty: false,
+            toasts: Vec::new(),
+            toast_age: 0.0,
+        }
+    }
+
+    pub fn is_unlocked(&self, id: &str) -> bool {
+        self.progress.unlocked.contains(id)
+    }
+
+    // 记录一项统计，达到目标的成就立即解锁并保存
+    pub fn record(&mut self, stat: Stat) {
+        let progress = &mut self.progress;
+        let before = progress.clone();
+        match stat {
+            Stat::Score(score) => progress.best_score = progress.best_score.max(score),
+            Stat::Stars(stars) => progress.best_stars = progress.best_stars.max(stars),
+            Stat::Survived(seconds) => {
+                progress.best_survived = progress.best_survived.max(seconds);
+            }
+            Stat::Answer { correct: true } => {
+                progress.answer_streak += 1;
+                progress.best_answer_streak =
+                    progress.best_answer_streak.max(progress.answer_streak);
+            }
+            Stat::Answer { correct: false } => progress.answer_streak = 0,
+        }
+        self.dirty |= *progress != before;
+
+        let mut unlocked = false;
+        for (index, achievement) in ACHIEVEMENTS.iter().enumerate() {
+            if progress.value(achievement.goal) >= achievement.target
+                && progress.unlocked.insert(achievement.id.to_string())
+            {
+                self.toasts.push(index);
+                unlocked = true;
+            }
+        }
+        if unlocked {
+            self.dirty = true;
+            self.save();
+        }
+    }
+
+    // 一局结束时保存进度
+    pub fn save(&mut self) {
+        if self.dirty {
+            save_toml(&self.path, &self.progress);
+            self.dirty = false;
+        }
+    }
+
+    pub fn step_toasts(&mut self, dt: f32) {
+        if self.toasts.is_empty() {
+            return;
+        }
+        self.toast_age += dt;
+        if self.toast_age >= TOAST_DURATION {
+            self.toasts.remove(0);
+            self.toast_age = 0.0;
+        }
+    }
+
+    // 屏幕上方滑入的解锁提示
+    pub fn draw_toast(&self, palette: &Palette, font: Option<&Font>) {
+        let Some(&index) = self.toasts.first() else {
+            return;
+        };
+        let chinese = font.is_some();
+        let achievement = &ACHIEVEMENTS[index];
+        let title = if chinese {
+            format!("成就解锁：{}", achievement.name(true))
+        } else {
+            format!("Achievement unlocked: {}", achievement.name(false))
+        };
+        let detail = match achievement.reward {
+            Some((slot, id)) if chinese => format!("获得 {}", reward_label(slot, id, true)),
+            Some((slot, id)) => format!("Reward: {}", reward_label(slot, id, false)),
+            None => achievement.description(chinese).to_string(),
+        };
+
+        let w = label_width(font, &title, 22).max(label_width(font, &detail, 16)) + 40.0;
+        let h = 60.0;
+        // 前 0.3 秒从屏幕上方滑入，最后 0.3 秒滑出
+        let slide = (self.toast_age / 0.3)
+            .min((TOAST_DURATION - self.toast_age) / 0.3)
+            .clamp(0.0, 1.0);
+        let x = screen_width() / 2.0 - w / 2.0;
+        let y = -h + (h + 10.0) * slide;
+        draw_rectangle(x, y, w, h, palette.panel);
+        draw_rectangle_lines(x, y, w, h, 2.0, palette.panel_border);
+        draw_label(font, &title, x + 20.0, y + 26.0, 22, palette.title);
+        draw_label(
+            font,
+            &detail,
+            x + 20.0,
+            y + 48.0,
+            16,
+            palette.text_secondary,
+        );
+    }
+
+    // 成就一览：名字、说明、进度和奖励
+    pub fn draw_screen(&self, palette: &Palette, font: Option<&Font>) {
+        let chinese = font.is_some();
+        draw_rectangle(0.0, 0.0, screen_width(), screen_height(), palette.overlay);
+
+        let row_h = 56.0;
+        let panel_w = 560.0;
+        let panel_h = 110.0 + ACHIEVEMENTS.len() as f32 * row_h;
+        let panel_x = screen_width() / 2.0 - panel_w / 2.0;
+        let panel_y = screen_height() / 2.0 - panel_h / 2.0;
+        draw_rectangle(panel_x, panel_y, panel_w, panel_h, palette.panel);
+        draw_rectangle_lines(
+            panel_x,
+            panel_y,
+            panel_w,
+            panel_h,
+            3.0,
+            palette.panel_border,
+        );
+
+        let count = ACHIEVEMENTS
+            .iter()
+            .filter(|achievement| self.is_unlocked(achievement.id))
+            .count();
+        let title = if chinese {
+            format!("成就 {}/{}", count, ACHIEVEMENTS.len())
+        } else {
+            format!("Achievements {}/{}", count, ACHIEVEMENTS.len())
+        };
+        draw_label(
+            font,
+            &title,
+            screen_width() / 2.0 - label_width(font, &title, 28) / 2.0,
+            panel_y + 38.0,
+            28,
+            palette.title,
+        );
+
+        for (index, achievement) in ACHIEVEMENTS.iter().enumerate() {
+            let y = panel_y + 80.0 + index as f32 * row_h;
+            let unlocked = self.is_unlocked(achievement.id);
+            let value = self
+                .progress
+                .value(achievement.goal)
+                .min(achievement.target);
+            let name_color = if unlocked {
+                palette.success
+            } else {
+                palette.text
+            };
+            draw_label(
+                font,
+                achievement.name(chinese),
+                panel_x + 20.0,
+                y,
+                20,
+                name_color,
+            );
+            draw_label(
+                font,
+                achievement.description(chinese),
+                panel_x + 20.0,
+                y + 20.0,
+                16,
+                palette.text_secondary,
+            );
+
+            // 右侧为进度条和奖励
+            let bar_x = panel_x + panel_w - 200.0;
+            let fraction = value as f32 / achievement.target as f32;
+            draw_rectangle(bar_x, y - 12.0, 120.0, 10.0, palette.highlight);
+            draw_rectangle(bar_x, y - 12.0, 120.0 * fraction, 10.0, palette.success);
+            let amount = format!("{}/{}", value, achievement.target);
+            draw_label(font, &amount, bar_x + 128.0, y - 2.0, 16, palette.text);
+            if let Some((slot, id)) = achievement.reward {
+                let text = if chinese {
+                    format!("奖励 {}", reward_label(slot, id, true))
+                } else {
+                    format!("Reward: {}", reward_label(slot, id, false))
+                };
+                draw_label(font, &text, bar_x, y + 20.0, 16, palette.input);
+            }
+        }
+
+        let hint = if chinese {
+            "按 ESC 返回"
+        } else {
+            "Press ESC to go back"
+        };
+        draw_label(
+            font,
+            hint,
+            screen_width() / 2.0 - label_width(font, hint, 18) / 2.0,
+            panel_y + panel_h - 15.0,
+            18,
+            palette.text_secondary,
+        );
+    }
+}
+
+// 等待交给 Achievements 的统计，由 StatTracker 放入，主循环取出
+pub type StatQueue = Rc<RefCell<Vec<Stat>>>;
+
+// 订阅游戏事件，统计本局的得分、星星、坚持的时间和复活题的作答
+pub struct StatTracker {
+    queue: StatQueue,
+    score: u32,
+    stars: u32,
+    // 开局或上次复活以来的步数，撞到障碍物后停止计时
+    survived: u32,
+    running: bool,
+}
+
+impl StatTracker {
+    pub fn new(queue: &StatQueue) -> Self {
+        Self {
+            queue: queue.clone(),
+            score: 0,
+            stars: 0,
+            survived: 0,
+            running: true,
+        }
+    }
+}
+
+impl Listener for StatTracker {
+    fn on_event(&mut self, event: &GameEvent) {
+        let stat = match *event {
+            GameEvent::Score(ScoreEvent::Passed { points }) => {
+                self.score += points;
+                Stat::Score(self.score)
+            }
+            GameEvent::Score(ScoreEvent::Star { points, .. }) => {
+                self.score += points;
+                self.stars += 1;
+                self.queue.borrow_mut().push(Stat::Stars(self.stars));
+                Stat::Score(self.score)
+            }
+            GameEvent::Revived => {
+                self.survived = 0;
+                self.running = true;
+                Stat::Answer { correct: true }
+            }
+            GameEvent::Hit { .. } => {
+                self.running = false;
+                return;
+            }
+            GameEvent::WrongAnswer => Stat::Answer { correct: false },
+            GameEvent::Reset => {
+                self.score = 0;
+                self.stars = 0;
+                self.survived = 0;
+                self.running = true;
+                return;
+            }
+            _ => return,
+        };
+        self.queue.borrow_mut().push(stat);
+    }
+
+    fn step(&mut self, _dt: f32) {
+        if !self.running {
+            return;
+        }
+        self.survived += 1;
+        if self.survived.is_multiple_of(SECOND_TICKS) {
+            let seconds = self.survived / SECOND_TICKS;
+            self.queue.borrow_mut().push(Stat::Survived(seconds));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::defs::EntityDefs;
+    use crate::input::ActionState;
+    use crate::GameState;
+
+    #[test]
+    fn stats_from_a_run_unlock_achievements() {
+        let mut game = GameState::new(EntityDefs::default());
+        game.reseed(4);
+        let queue = StatQueue::default();
+        game.events.subscribe(Box::new(StatTracker::new(&queue)));
+        let idle = ActionState::default();
+        while !game.game_over {
+            game.update(&idle);
+        }
+
+        // 站着不动撞上第一个障碍物之前坚持的秒数
+        let path = std::env::temp_dir().join(format!(
+            "dino-achievements-test-{}.toml",
+            std::process::id()
+        ));
+        let mut achievements = Achievements::load(path.clone());
+        achievements.progress = Progress::default();
+        for stat in queue.borrow_mut().drain(..) {
+            achievements.record(stat);
+        }
+        let expected = game.ticks as u32 / SECOND_TICKS;
+        assert_eq!(achievements.progress.best_survived, expected);
+        assert!(!achievements.is_unlocked("first_hundred"));
+
+        // 连续答对 10 题才解锁，中途答错从头计算；最好成绩不会因为新的一局变差
+        for correct in [true; 9].into_iter().chain([false]).chain([true; 10]) {
+            achievements.record(Stat::Answer { correct });
+        }
+        achievements.record(Stat::Score(120));
+        achievements.record(Stat::Score(30));
+        assert_eq!(achievements.progress.best_score, 120);
+        assert!(achievements.is_unlocked("quiz_whiz"));
+        assert!(achievements.is_unlocked("second_chance"));
+        assert!(achievements.is_unlocked("first_hundred"));
+        assert!(!achievements.is_unlocked("high_scorer"));
+        assert_eq!(achievements.toasts.len(), 3);
+
+        // 解锁时已经保存，重新读取后进度不变
+        assert_eq!(
+            Achievements::load(path.clone()).progress,
+            achievements.progress
+        );
+        let _ = std::fs::remove_file(path);
+    }
+}
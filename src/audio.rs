@@ -0,0 +1,65 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use macroquad::audio::{load_sound_from_bytes, play_sound, PlaySoundParams, Sound};
+
+use crate::events::{GameEvent, Listener};
+use crate::scoring::ScoreEvent;
+use crate::sfx::{self, SoundEffect, SFX_TABLE};
+
+// 等待播放的音效，由 SoundCues 放入，主循环取出后交给 Audio 播放
+pub type SoundQueue = Rc<RefCell<Vec<SoundEffect>>>;
+
+// 订阅游戏事件，把需要发声的事件换成对应的音效
+pub struct SoundCues(pub SoundQueue);
+
+impl Listener for SoundCues {
+    fn on_event(&mut self, event: &GameEvent) {
+        let effect = match event {
+            GameEvent::Jumped => SoundEffect::Jump,
+            GameEvent::Score(ScoreEvent::Star { .. }) => SoundEffect::Star,
+            GameEvent::PowerUpCollected { .. } => SoundEffect::PowerUp,
+            GameEvent::HitAbsorbed => SoundEffect::ShieldBreak,
+            GameEvent::Hit { .. } => SoundEffect::Hit,
+            GameEvent::Revived => SoundEffect::Revive,
+            GameEvent::WrongAnswer => SoundEffect::WrongAnswer,
+            _ => return,
+        };
+        // 同一帧中的多个相同音效只播放一次
+        let mut queue = self.0.borrow_mut();
+        if !queue.contains(&effect) {
+            queue.push(effect);
+        }
+    }
+}
+
+// 音频层：启动时把合成好的 PCM 加载成 Sound，游戏中按音效类型播放
+pub struct Audio {
+    sounds: Vec<(SoundEffect, Sound)>,
+}
+
+impl Audio {
+    pub async fn load() -> Self {
+        let mut sounds = Vec::new();
+        for (effect, params) in SFX_TABLE.iter() {
+            let wav = sfx::encode_wav(&sfx::synthesize(params));
+            match load_sound_from_bytes(&wav).await {
+                Ok(sound) => sounds.push((*effect, sound)),
+                Err(err) => println!("Warning: failed to load sound {:?}: {}", effect, err),
+            }
+        }
+        Self { sounds }
+    }
+
+    pub fn play(&self, effect: SoundEffect) {
+        if let Some((_, sound)) = self.sounds.iter().find(|(e, _)| *e == effect) {
+            play_sound(
+                sound,
+                PlaySoundParams {
+                    looped: false,
+                    volume: 1.0,
+                },
+            );
+        }
+    }
+}
@@ -0,0 +1,65 @@
+use std::f32::consts::TAU;
+
+use macroquad::models::{draw_mesh, Mesh, Vertex};
+use macroquad::prelude::*;
+
+// 每个小圆用几边形近似，石子只有几个像素大，六边形已经足够
+const CIRCLE_SEGMENTS: u16 = 6;
+// macroquad 单次绘制最多 5000 个索引，超过后分批提交
+const MAX_INDICES: usize = 4800;
+
+// 把大量装饰小圆合并成一个网格一次性提交，代替逐个调用 draw_circle
+// 顶点和索引缓冲区在帧之间复用，不会每帧重新分配
+pub struct CircleBatch {
+    mesh: Mesh,
+}
+
+impl CircleBatch {
+    pub fn new() -> Self {
+        Self {
+            mesh: Mesh {
+                vertices: Vec::with_capacity(MAX_INDICES),
+                indices: Vec::with_capacity(MAX_INDICES),
+                texture: None,
+            },
+        }
+    }
+
+    pub fn circle(&mut self, x: f32, y: f32, radius: f32, color: Color) {
+        let indices_per_circle = CIRCLE_SEGMENTS as usize * 3;
+        if self.mesh.indices.len() + indices_per_circle > MAX_INDICES {
+            self.flush();
+        }
+
+        let center = self.mesh.vertices.len() as u16;
+        self.mesh
+            .vertices
+            .push(Vertex::new(x, y, 0.0, 0.0, 0.0, color));
+        for i in 0..CIRCLE_SEGMENTS {
+            let angle = i as f32 / CIRCLE_SEGMENTS as f32 * TAU;
+            self.mesh.vertices.push(Vertex::new(
+                x + radius * angle.cos(),
+                y + radius * angle.sin(),
+                0.0,
+                0.0,
+                0.0,
+                color,
+            ));
+        }
+        for i in 0..CIRCLE_SEGMENTS {
+            let next = (i + 1) % CIRCLE_SEGMENTS;
+            self.mesh
+                .indices
+                .extend_from_slice(&[center, center + 1 + i, center + 1 + next]);
+        }
+    }
+
+    // 提交已累积的网格并清空缓冲区
+    pub fn flush(&mut self) {
+        if !self.mesh.indices.is_empty() {
+            draw_mesh(&self.mesh);
+        }
+        self.mesh.vertices.clear();
+        self.mesh.indices.clear();
+    }
+}
@@ -0,0 +1,98 @@
+use std::time::Duration;
+
+use macroquad::prelude::*;
+
+use crate::theme::Palette;
+use crate::ui::draw_label;
+
+// 基准测试模式：统计每帧 update 和 draw 的 CPU 耗时
+pub struct Bench {
+    target_frames: u32,
+    frames: u32,
+    update_total: Duration,
+    draw_total: Duration,
+    update_max: Duration,
+    draw_max: Duration,
+    last_update: Duration,
+    last_draw: Duration,
+}
+
+impl Bench {
+    pub fn new(target_frames: u32) -> Self {
+        Self {
+            target_frames,
+            frames: 0,
+            update_total: Duration::ZERO,
+            draw_total: Duration::ZERO,
+            update_max: Duration::ZERO,
+            draw_max: Duration::ZERO,
+            last_update: Duration::ZERO,
+            last_draw: Duration::ZERO,
+        }
+    }
+
+    pub fn record(&mut self, update: Duration, draw: Duration) {
+        self.frames += 1;
+        self.update_total += update;
+        self.draw_total += draw;
+        self.update_max = self.update_max.max(update);
+        self.draw_max = self.draw_max.max(draw);
+        self.last_update = update;
+        self.last_draw = draw;
+    }
+
+    pub fn finished(&self) -> bool {
+        self.frames >= self.target_frames
+    }
+
+    fn average(total: Duration, frames: u32) -> f64 {
+        if frames == 0 {
+            0.0
+        } else {
+            total.as_secs_f64() * 1000.0 / frames as f64
+        }
+    }
+
+    pub fn draw_overlay(&self, entity_count: usize, palette: &Palette, font: Option<&Font>) {
+        let lines = [
+            format!("frame {}/{}", self.frames, self.target_frames),
+            format!("entities {}", entity_count),
+            format!(
+                "update {:.3} ms (avg {:.3}, max {:.3})",
+                self.last_update.as_secs_f64() * 1000.0,
+                Self::average(self.update_total, self.frames),
+                self.update_max.as_secs_f64() * 1000.0
+            ),
+            format!(
+                "draw   {:.3} ms (avg {:.3}, max {:.3})",
+                self.last_draw.as_secs_f64() * 1000.0,
+                Self::average(self.draw_total, self.frames),
+                self.draw_max.as_secs_f64() * 1000.0
+            ),
+        ];
+        for (i, line) in lines.iter().enumerate() {
+            draw_label(
+                font,
+                line,
+                screen_width() - 330.0,
+                30.0 + i as f32 * 20.0,
+                18,
+                palette.text_secondary,
+            );
+        }
+    }
+
+    pub fn report(&self) {
+        println!("Benchmark: {} frames", self.frames);
+        println!(
+            "  update: avg {:.3} ms, max {:.3} ms",
+            Self::average(self.update_total, self.frames),
+            self.update_max.as_secs_f64() * 1000.0
+        );
+        println!(
+            "  draw:   avg {:.3} ms, max {:.3} ms",
+            Self::average(self.draw_total, self.frames),
+            self.draw_max.as_secs_f64() * 1000.0
+        );
+    }
+}
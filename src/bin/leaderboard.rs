@@ -0,0 +1,12 @@
+use std::env;
+
+use chrome_dino_game::{serve, DEFAULT_SERVER_ADDRESS};
+
+// 排行榜服务器：不打开窗口，验证并保存客户端上传的成绩。
+// 用法：leaderboard [监听地址]，默认监听 0.0.0.0:7879
+fn main() {
+    let address = env::args()
+        .nth(1)
+        .unwrap_or_else(|| DEFAULT_SERVER_ADDRESS.to_string());
+    serve(&address);
+}
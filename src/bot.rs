@@ -0,0 +1,246 @@
+use std::collections::VecDeque;
+
+use macroquad::prelude::*;
+
+use crate::dino::{Dino, DinoInput};
+use crate::fairness::{self, Obstacle};
+use crate::theme::Palette;
+use crate::{
+    DINO_DUCK_HEIGHT, DINO_HEIGHT, DINO_WIDTH, DINO_X, FIXED_DT, GAME_SPEED, GROUND_Y, JUMP_FORCE,
+    VIEW_WIDTH,
+};
+
+// 自动游戏的机器人：每一步读取游戏状态，决定恐龙的输入
+// 新的策略只需实现 Bot 并在 by_name 中登记
+pub trait Bot {
+    fn name(&self) -> &'static str;
+    fn decide(&mut self, observation: &Observation) -> DinoInput;
+    // 游戏画面上可选的可视化，例如神经网络的激活值
+    fn draw(&self, _palette: &Palette, _font: Option<&Font>) {}
+}
+
+pub const BOT_NAMES: [&str; 2] = ["reflex", "planner"];
+
+pub fn by_name(name: &str) -> Option<Box<dyn Bot>> {
+    match name {
+        "reflex" => Some(Box::new(ReflexBot)),
+        "planner" => Some(Box::new(PlannerBot::default())),
+        _ => None,
+    }
+}
+
+// 机器人看到的游戏状态
+pub struct Observation {
+    pub dino: Dino,
+    // 本局已推进的步数和当前游戏速度
+    pub tick: u64,
+    pub speed: f32,
+    // 场上所有伤害性实体
+    pub obstacles: Vec<Obstacle>,
+    // 各生成器的计时器，变小说明刚生成过新实体
+    pub spawn_timers: Vec<f32>,
+}
+
+// 恐龙前方最近的障碍物，距离和高度都以像素为单位
+#[derive(Clone, Copy, Debug)]
+pub struct Nearest {
+    // 障碍物左边缘到恐龙右边缘的距离
+    pub distance: f32,
+    pub width: f32,
+    // 障碍物底部离地面的高度
+    pub elevation: f32,
+}
+
+impl Observation {
+    // 还没有完全越过恐龙的最近障碍物
+    pub fn nearest(&self) -> Option<Nearest> {
+        self.obstacles
+            .iter()
+            .filter(|obstacle| obstacle.rect.right() >= DINO_X)
+            .min_by(|a, b| a.rect.x.total_cmp(&b.rect.x))
+            .map(|obstacle| Nearest {
+                distance: obstacle.rect.x - (DINO_X + DINO_WIDTH),
+                width: obstacle.rect.w,
+                elevation: GROUND_Y - obstacle.rect.bottom(),
+            })
+    }
+
+    // 归一化的特征向量：恐龙离地高度、竖直速度、是否在空中、是否下蹲、当前速度，
+    // 以及前方最近 count 个障碍物的距离、离地高度、宽、高，不足的用“很远处的空障碍物”补齐
+    pub fn features(&self, count: usize) -> Vec<f32> {
+        let dino = &self.dino;
+        let mut features = vec![
+            (GROUND_Y - dino.rect().bottom()) / 100.0,
+            dino.velocity / -JUMP_FORCE,
+            dino.is_jumping as u8 as f32,
+            dino.is_ducking as u8 as f32,
+            self.speed / GAME_SPEED,
+        ];
+        let mut ahead: Vec<_> = self
+            .obstacles
+            .iter()
+            .map(|obstacle| obstacle.rect)
+            .filter(|rect| rect.right() >= DINO_X)
+            .collect();
+        ahead.sort_by(|a, b| a.x.total_cmp(&b.x));
+        for i in 0..count {
+            match ahead.get(i) {
+                Some(rect) => features.extend([
+                    (rect.x - DINO_X) / VIEW_WIDTH,
+                    (GROUND_Y - rect.bottom()) / 100.0,
+                    rect.w / 100.0,
+                    rect.h / 100.0,
+                ]),
+                None => features.extend([1.0, 0.0, 0.0, 0.0]),
+            }
+        }
+        features
+    }
+
+    // 从当前这一步开始，规划躲过场上所有障碍物的操作
+    pub fn plan(&self) -> Option<Vec<DinoInput>> {
+        fairness::plan(self.dino, &self.obstacles, self.speed, FIXED_DT, self.tick)
+    }
+}
+
+// 条件反射式的机器人：只看最近的障碍物。低处的障碍物在恰当距离起跳越过，
+// 悬在半空的鸟下蹲钻过，更高的鸟不用理会；越过障碍物后加速下落
+pub struct ReflexBot;
+
+impl Bot for ReflexBot {
+    fn name(&self) -> &'static str {
+        "reflex"
+    }
+
+    fn decide(&mut self, observation: &Observation) -> DinoInput {
+        let dino = &observation.dino;
+        let ahead = observation
+            .nearest()
+            .filter(|nearest| nearest.elevation < DINO_HEIGHT);
+        let Some(nearest) = ahead else {
+            // 前方没有威胁，空中时尽快落地
+            return DinoInput {
+                jump: false,
+                duck: dino.is_jumping,
+            };
+        };
+
+        if nearest.elevation >= DINO_DUCK_HEIGHT {
+            return DinoInput {
+                jump: false,
+                duck: nearest.distance < observation.speed * 0.3,
+            };
+        }
+
+        // 跳跃约 1 秒，在空中的中点正好越过障碍物的中心
+        let reach = DINO_WIDTH + nearest.width;
+        DinoInput {
+            jump: nearest.distance <= observation.speed * 0.5 - reach / 2.0,
+            duck: false,
+        }
+    }
+}
+
+// 完美机器人：按可通过性检查找到的操作序列行动，有新障碍物出现、
+// 或恐龙的状态与计划不符（例如道具改变了跳跃）时重新规划
+#[derive(Default)]
+pub struct PlannerBot {
+    plan: VecDeque<DinoInput>,
+    spawn_timers: Vec<f32>,
+    // 按计划执行上一步输入后恐龙应有的状态
+    expected: Option<Dino>,
+}
+
+impl PlannerBot {
+    // 找不到可行方案时返回 None
+    pub fn next_input(&mut self, observation: &Observation) -> Option<DinoInput> {
+        // 新实体只在生成器计时器归零时出现
+        let spawned = observation
+            .spawn_timers
+            .iter()
+            .zip(&self.spawn_timers)
+            .any(|(now, before)| now < before);
+        self.spawn_timers.clone_from(&observation.spawn_timers);
+        let diverged = self
+            .expected
+            .is_some_and(|expected| expected != observation.dino);
+        if self.plan.is_empty() || spawned || diverged {
+            let Some(plan) = observation.plan() else {
+                self.plan.clear();
+                self.expected = None;
+                return None;
+            };
+            self.plan = plan.into();
+        }
+        let input = self.plan.pop_front().unwrap_or_default();
+        let mut expected = observation.dino;
+        expected.step(FIXED_DT, input);
+        self.expected = Some(expected);
+        Some(input)
+    }
+}
+
+impl Bot for PlannerBot {
+    fn name(&self) -> &'static str {
+        "planner"
+    }
+
+    fn decide(&mut self, observation: &Observation) -> DinoInput {
+        self.next_input(observation).unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 恐龙站在地面上，前方 distance 处有一个离地 elevation 的障碍物
+    fn facing(distance: f32, elevation: f32, size: Vec2) -> Observation {
+        let x = DINO_X + DINO_WIDTH + distance;
+        let rect = Rect::new(x, GROUND_Y - elevation - size.y, size.x, size.y);
+        Observation {
+            dino: Dino::new(),
+            tick: 0,
+            speed: GAME_SPEED,
+            obstacles: vec![Obstacle { rect, vel_x: 0.0 }],
+            spawn_timers: vec![1.0],
+        }
+    }
+
+    #[test]
+    fn reflex_bot_jumps_cacti_ducks_birds_and_ignores_high_ones() {
+        let cactus = vec2(20.0, 40.0);
+        let bird = vec2(40.0, 20.0);
+        let mut bot = ReflexBot;
+        assert_eq!(
+            bot.decide(&facing(400.0, 0.0, cactus)),
+            DinoInput::default()
+        );
+        assert!(bot.decide(&facing(40.0, 0.0, cactus)).jump);
+
+        let low_bird = bot.decide(&facing(40.0, DINO_DUCK_HEIGHT + 2.0, bird));
+        assert!(low_bird.duck && !low_bird.jump);
+        let high_bird = facing(40.0, DINO_HEIGHT + 10.0, bird);
+        assert_eq!(bot.decide(&high_bird), DinoInput::default());
+    }
+
+    #[test]
+    fn planner_bot_clears_obstacles_and_gives_up_on_impossible_ones() {
+        let mut observation = facing(300.0, 0.0, vec2(20.0, 40.0));
+        let mut bot = PlannerBot::default();
+        for _ in 0..120 {
+            let input = bot.next_input(&observation).expect("cactus can be cleared");
+            observation.dino.step(FIXED_DT, input);
+            for obstacle in &mut observation.obstacles {
+                obstacle.advance(FIXED_DT, observation.speed);
+                assert!(!observation.dino.rect().overlaps(&obstacle.rect));
+            }
+            observation.tick += 1;
+        }
+        assert!(observation.dino.rect().x > observation.obstacles[0].rect.right());
+
+        // 贴在面前的高墙无论如何都躲不过
+        let wall = facing(1.0, 0.0, vec2(200.0, 400.0));
+        assert_eq!(PlannerBot::default().next_input(&wall), None);
+    }
+}
@@ -0,0 +1,93 @@
+use macroquad::prelude::*;
+
+// 摄像机：把游戏世界中的一块区域画到整个屏幕或屏幕的一部分上，并叠加屏幕震动。
+// 震动只影响画面，不改变游戏逻辑；强度由设置决定，开启“减少动态效果”时为 0
+
+// 震动量为 1 时的最大偏移像素
+const MAX_SHAKE: f32 = 12.0;
+// 每秒衰减的震动量
+const SHAKE_DECAY: f32 = 2.0;
+
+pub struct Camera {
+    // 当前的震动量（0 到 1），偏移随它的平方增大，衰减时显得更自然
+    trauma: f32,
+    time: f32,
+    // 设置中的震动强度
+    pub intensity: f32,
+}
+
+impl Default for Camera {
+    fn default() -> Self {
+        Self {
+            trauma: 0.0,
+            time: 0.0,
+            intensity: 1.0,
+        }
+    }
+}
+
+impl Camera {
+    pub fn shake(&mut self, amount: f32) {
+        self.trauma = (self.trauma + amount).min(1.0);
+    }
+
+    pub fn clear(&mut self) {
+        self.trauma = 0.0;
+    }
+
+    pub fn step(&mut self, dt: f32) {
+        self.trauma = (self.trauma - SHAKE_DECAY * dt).max(0.0);
+        self.time += dt;
+    }
+
+    // 当前的震动偏移，两个方向用不同频率的正弦错开
+    pub fn offset(&self) -> Vec2 {
+        let amount = self.trauma * self.trauma * MAX_SHAKE * self.intensity;
+        vec2((self.time * 47.0).sin(), (self.time * 59.0 + 1.3).sin()) * amount
+    }
+
+    // 之后的绘制把世界坐标中的 view 区域画到 viewport（像素，原点在左下角；None 为整个屏幕）上，
+    // 画完后调用 set_default_camera 恢复屏幕坐标
+    pub fn set(&self, view: Rect, viewport: Option<(i32, i32, i32, i32)>) {
+        apply(view.center() + self.offset(), view, viewport);
+    }
+
+    // 同 set，但不震动，用于画在同一区域中的界面文字
+    pub fn steady(view: Rect, viewport: Option<(i32, i32, i32, i32)>) {
+        apply(view.center(), view, viewport);
+    }
+}
+
+fn apply(target: Vec2, view: Rect, viewport: Option<(i32, i32, i32, i32)>) {
+    set_camera(&Camera2D {
+        target,
+        zoom: vec2(2.0 / view.w, 2.0 / view.h),
+        viewport,
+        ..Default::default()
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shake_decays_and_scales_with_intensity() {
+        let mut camera = Camera::default();
+        camera.shake(0.5);
+        camera.shake(0.8);
+        camera.step(0.01);
+        let full = camera.offset().length();
+        assert!(full > 0.0 && full <= MAX_SHAKE * 2f32.sqrt());
+
+        // 强度为 0（减少动态效果）时不偏移
+        camera.intensity = 0.0;
+        assert_eq!(camera.offset(), Vec2::ZERO);
+        camera.intensity = 1.0;
+
+        for _ in 0..60 {
+            camera.step(1.0 / 60.0);
+        }
+        assert_eq!(camera.offset(), Vec2::ZERO);
+    }
+}
@@ -0,0 +1,131 @@
+use std::env;
+
+use crate::bot::BOT_NAMES;
+
+const DEFAULT_BENCH_FRAMES: u32 = 1800;
+const DEFAULT_SIMULATE_RUNS: u32 = 100;
+const DEFAULT_SIMULATE_SECONDS: f32 = 120.0;
+const DEFAULT_TRAIN_GENERATIONS: u32 = 100;
+const DEFAULT_POPULATION: usize = 64;
+const DEFAULT_TRAIN_SEEDS: u64 = 16;
+pub const DEFAULT_GENOME_PATH: &str = "genome.toml";
+const DEFAULT_HOST_ADDRESS: &str = "0.0.0.0:7878";
+
+// 命令行参数
+pub struct Options {
+    // --bench [帧数]：基准测试模式，运行指定帧数后输出每帧耗时统计并退出
+    pub bench_frames: Option<u32>,
+    // --simulate [局数]：不打开窗口，用机器人连续跑若干局后输出统计并退出
+    pub simulate_runs: Option<u32>,
+    // --bot <名称>：模拟使用的机器人
+    pub bot: String,
+    // --seconds <秒数>：模拟和训练时每局的最长时间
+    pub simulate_seconds: f32,
+    // --env：作为强化学习环境运行，在标准输入输出上收发 JSON Lines
+    pub env: bool,
+    // --train [代数]：不打开窗口，用遗传算法训练神经网络机器人
+    pub train_generations: Option<u32>,
+    // --population <数量>、--seeds <局数>：训练的种群大小和每个基因组每代跑的局数
+    pub population: usize,
+    pub train_seeds: u64,
+    // --genome <路径>：训练时保存最优基因组的文件；其他模式下加载它，由神经网络机器人游戏
+    pub genome: Option<String>,
+    // --profile <名字>：本次使用的玩家档案，覆盖设置中的档案
+    pub profile: Option<String>,
+    // --ghost <路径>：与指定的录像文件（例如别的档案的个人最佳）赛跑
+    pub ghost: Option<String>,
+    // --host [地址]：作为局域网对战的主机监听，默认 0.0.0.0:7878
+    pub host: Option<String>,
+    // --join <地址>：加入局域网对战，地址不带端口时使用 7878
+    pub join: Option<String>,
+}
+
+impl Options {
+    pub fn parse() -> Self {
+        let mut options = Options {
+            bench_frames: None,
+            simulate_runs: None,
+            bot: "reflex".to_string(),
+            simulate_seconds: DEFAULT_SIMULATE_SECONDS,
+            env: false,
+            train_generations: None,
+            population: DEFAULT_POPULATION,
+            train_seeds: DEFAULT_TRAIN_SEEDS,
+            genome: None,
+            profile: None,
+            ghost: None,
+            host: None,
+            join: None,
+        };
+
+        let mut args = env::args().skip(1).peekable();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--bench" => {
+                    let frames = args.next_if(|value| value.parse::<u32>().is_ok());
+                    options.bench_frames = Some(
+                        frames
+                            .and_then(|value| value.parse().ok())
+                            .unwrap_or(DEFAULT_BENCH_FRAMES),
+                    );
+                }
+                "--simulate" => {
+                    let runs = args.next_if(|value| value.parse::<u32>().is_ok());
+                    options.simulate_runs = Some(
+                        runs.and_then(|value| value.parse().ok())
+                            .unwrap_or(DEFAULT_SIMULATE_RUNS),
+                    );
+                }
+                "--bot" => match args.next() {
+                    Some(name) if BOT_NAMES.contains(&name.as_str()) => options.bot = name,
+                    _ => eprintln!("Warning: --bot expects one of: {}", BOT_NAMES.join(", ")),
+                },
+                "--env" => options.env = true,
+                "--train" => {
+                    let generations = args.next_if(|value| value.parse::<u32>().is_ok());
+                    options.train_generations = Some(
+                        generations
+                            .and_then(|value| value.parse().ok())
+                            .unwrap_or(DEFAULT_TRAIN_GENERATIONS),
+                    );
+                }
+                "--population" => match args.next().and_then(|value| value.parse().ok()) {
+                    Some(population) if population > 0 => options.population = population,
+                    _ => eprintln!("Warning: --population expects a positive number"),
+                },
+                "--seeds" => match args.next().and_then(|value| value.parse().ok()) {
+                    Some(seeds) if seeds > 0 => options.train_seeds = seeds,
+                    _ => eprintln!("Warning: --seeds expects a positive number"),
+                },
+                "--genome" => match args.next() {
+                    Some(path) => options.genome = Some(path),
+                    None => eprintln!("Warning: --genome expects a file path"),
+                },
+                "--profile" => match args.next() {
+                    Some(name) => options.profile = Some(name),
+                    None => eprintln!("Warning: --profile expects a name"),
+                },
+                "--ghost" => match args.next() {
+                    Some(path) => options.ghost = Some(path),
+                    None => eprintln!("Warning: --ghost expects a file path"),
+                },
+                "--host" => {
+                    let address = args.next_if(|value| !value.starts_with("--"));
+                    options.host =
+                        Some(address.unwrap_or_else(|| DEFAULT_HOST_ADDRESS.to_string()));
+                }
+                "--join" => match args.next() {
+                    Some(address) => options.join = Some(address),
+                    None => eprintln!("Warning: --join expects a host address"),
+                },
+                "--seconds" => match args.next().and_then(|value| value.parse::<f32>().ok()) {
+                    Some(seconds) if seconds > 0.0 => options.simulate_seconds = seconds,
+                    _ => eprintln!("Warning: --seconds expects a positive number"),
+                },
+                _ => eprintln!("Warning: unknown argument '{}'", arg),
+            }
+        }
+
+        options
+    }
+}
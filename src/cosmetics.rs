@@ -0,0 +1,582 @@
+use std::collections::VecDeque;
+use std::path::Path;
+
+use macroquad::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::achievements::{Achievement, Achievements, ACHIEVEMENTS};
+use crate::daily::load_toml;
+use crate::dino::{Dino, DinoInput};
+use crate::theme::Palette;
+use crate::ui::{draw_label, label_width};
+use crate::{lane_ground, DINO_X, FIXED_DT, GAME_SPEED};
+
+// 恐龙外观：身体颜色、配饰（帽子、围巾、眼镜）和拖尾。只影响绘制，碰撞盒不变。
+// 选择保存在 profiles/<档案名>/cosmetics.toml，部分外观需要先解锁对应的成就
+
+pub const COSMETICS_FILE: &str = "cosmetics.toml";
+// 拖尾记录的步数
+const TRAIL_LENGTH: usize = 24;
+// 预览中的恐龙每隔多少步起跳一次
+const PREVIEW_JUMP_TICKS: u32 = 90;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Slot {
+    Color,
+    Hat,
+    Scarf,
+    Glasses,
+    Trail,
+}
+
+const SLOTS: [Slot; 5] = [
+    Slot::Color,
+    Slot::Hat,
+    Slot::Scarf,
+    Slot::Glasses,
+    Slot::Trail,
+];
+
+// 一件外观，id 为空表示不佩戴（身体颜色为主题颜色）
+pub struct Item {
+    pub id: &'static str,
+    // 中文和英文的名字
+    name: (&'static str, &'static str),
+    color: Color,
+}
+
+const fn item(id: &'static str, name: (&'static str, &'static str), rgb: [u8; 3]) -> Item {
+    Item {
+        id,
+        name,
+        color: Color::new(
+            rgb[0] as f32 / 255.0,
+            rgb[1] as f32 / 255.0,
+            rgb[2] as f32 / 255.0,
+            1.0,
+        ),
+    }
+}
+
+const COLORS: [Item; 6] = [
+    item("", ("主题颜色", "Theme color"), [0, 0, 0]),
+    item("red", ("火红", "Red"), [210, 70, 60]),
+    item("blue", ("天蓝", "Blue"), [70, 140, 220]),
+    item("purple", ("葡萄紫", "Purple"), [140, 90, 190]),
+    item("gold", ("金色", "Gold"), [225, 175, 40]),
+    item("midnight", ("午夜蓝", "Midnight"), [40, 50, 110]),
+];
+
+const HATS: [Item; 5] = [
+    item("", ("无", "None"), [0, 0, 0]),
+    item("cap", ("棒球帽", "Cap"), [200, 60, 60]),
+    item("party", ("派对帽", "Party hat"), [230, 110, 180]),
+    item("crown", ("王冠", "Crown"), [240, 190, 40]),
+    item("mortarboard", ("学士帽", "Mortarboard"), [40, 40, 50]),
+];
+
+const SCARVES: [Item; 3] = [
+    item("", ("无", "None"), [0, 0, 0]),
+    item("red", ("红围巾", "Red scarf"), [200, 50, 50]),
+    item("striped", ("条纹围巾", "Striped scarf"), [60, 110, 200]),
+];
+
+const GLASSES: [Item; 3] = [
+    item("", ("无", "None"), [0, 0, 0]),
+    item("round", ("圆眼镜", "Round glasses"), [60, 60, 60]),
+    item("sunglasses", ("墨镜", "Sunglasses"), [20, 20, 25]),
+];
+
+const TRAILS: [Item; 4] = [
+    item("", ("无", "None"), [0, 0, 0]),
+    item("shadow", ("残影", "Afterimage"), [0, 0, 0]),
+    item("rainbow", ("彩虹", "Rainbow"), [0, 0, 0]),
+    item("sparkle", ("星光", "Sparkle"), [255, 220, 90]),
+];
+
+impl Slot {
+    fn items(self) -> &'static [Item] {
+        match self {
+            Slot::Color => &COLORS,
+            Slot::Hat => &HATS,
+            Slot::Scarf => &SCARVES,
+            Slot::Glasses => &GLASSES,
+            Slot::Trail => &TRAILS,
+        }
+    }
+
+    fn label(self, chinese: bool) -> &'static str {
+        match (self, chinese) {
+            (Slot::Color, true) => "颜色",
+            (Slot::Color, false) => "Color",
+            (Slot::Hat, true) => "帽子",
+            (Slot::Hat, false) => "Hat",
+            (Slot::Scarf, true) => "围巾",
+            (Slot::Scarf, false) => "Scarf",
+            (Slot::Glasses, true) => "眼镜",
+            (Slot::Glasses, false) => "Glasses",
+            (Slot::Trail, true) => "拖尾",
+            (Slot::Trail, false) => "Trail",
+        }
+    }
+
+    fn find(self, id: &str) -> Option<&'static Item> {
+        self.items().iter().find(|item| item.id == id)
+    }
+}
+
+impl Item {
+    fn name(&self, chinese: bool) -> &'static str {
+        if chinese {
+            self.name.0
+        } else {
+            self.name.1
+        }
+    }
+}
+
+// 成就奖励的说明，例如 "帽子：王冠"
+pub fn reward_label(slot: Slot, id: &str, chinese: bool) -> String {
+    let name = slot.find(id).map_or(id, |item| item.name(chinese));
+    if chinese {
+        format!("{}：{}", slot.label(true), name)
+    } else {
+        format!("{}: {}", slot.label(false), name)
+    }
+}
+
+// 解锁这件外观需要的成就，不需要解锁时返回 None
+fn required_achievement(slot: Slot, id: &str) -> Option<&'static Achievement> {
+    ACHIEVEMENTS
+        .iter()
+        .find(|achievement| achievement.reward == Some((slot, id)))
+}
+
+fn is_unlocked(slot: Slot, id: &str, achievements: &Achievements) -> bool {
+    required_achievement(slot, id)
+        .is_none_or(|achievement| achievements.is_unlocked(achievement.id))
+}
+
+// 玩家选择的外观，保存在档案中
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Look {
+    pub color: String,
+    pub hat: String,
+    pub scarf: String,
+    pub glasses: String,
+    pub trail: String,
+}
+
+impl Look {
+    // 读取档案中的外观，去掉未知的和还没有解锁的
+    pub fn load(path: &Path, achievements: &Achievements) -> Self {
+        load_toml::<Look>(path).unlocked(achievements)
+    }
+
+    fn get(&self, slot: Slot) -> &str {
+        match slot {
+            Slot::Color => &self.color,
+            Slot::Hat => &self.hat,
+            Slot::Scarf => &self.scarf,
+            Slot::Glasses => &self.glasses,
+            Slot::Trail => &self.trail,
+        }
+    }
+
+    fn set(&mut self, slot: Slot, id: &str) {
+        let value = match slot {
+            Slot::Color => &mut self.color,
+            Slot::Hat => &mut self.hat,
+            Slot::Scarf => &mut self.scarf,
+            Slot::Glasses => &mut self.glasses,
+            Slot::Trail => &mut self.trail,
+        };
+        *value = id.to_string();
+    }
+
+    // 未知或还没有解锁的外观换成默认值
+    pub fn unlocked(mut self, achievements: &Achievements) -> Self {
+        for slot in SLOTS {
+            let id = self.get(slot);
+            if slot.find(id).is_none() || !is_unlocked(slot, id, achievements) {
+                self.set(slot, "");
+            }
+        }
+        self
+    }
+
+    fn item(&self, slot: Slot) -> Option<&'static Item> {
+        slot.find(self.get(slot)).filter(|item| !item.id.is_empty())
+    }
+
+    // 身体颜色，没有选择时使用主题颜色；外观的颜色随主题和昼夜调整
+    pub fn body_color(&self, palette: &Palette) -> Color {
+        self.item(Slot::Color)
+            .map_or(palette.dino, |item| palette.adapt(item.color))
+    }
+
+    // 在恐龙的头部和身体上画出配饰，alpha 为恐龙的不透明度
+    pub fn draw_accessories(&self, head: Rect, body: Rect, palette: &Palette, alpha: f32) {
+        let faded = |color: Color| Color {
+            a: color.a * alpha,
+            ..palette.adapt(color)
+        };
+        let (eye_x, eye_y) = (head.x + 13.0, head.y + 6.0);
+
+        if let Some(scarf) = self.item(Slot::Scarf) {
+            let color = faded(scarf.color);
+            draw_rectangle(body.x - 2.0, head.y + head.h, body.w + 4.0, 5.0, color);
+            draw_rectangle(body.x - 7.0, head.y + head.h + 2.0, 7.0, 10.0, color);
+            if scarf.id == "striped" {
+                for i in 0..3 {
+                    let x = body.x + i as f32 * 9.0;
+                    draw_rectangle(x, head.y + head.h, 4.0, 5.0, faded(WHITE));
+                }
+            }
+        }
+
+        if let Some(glasses) = self.item(Slot::Glasses) {
+            let color = faded(glasses.color);
+            draw_line(
+                head.x + 2.0,
+                eye_y - 1.0,
+                eye_x - 4.5,
+                eye_y - 1.0,
+                1.5,
+                color,
+            );
+            if glasses.id == "sunglasses" {
+                draw_rectangle(eye_x - 5.0, eye_y - 3.5, 11.0, 6.0, color);
+            } else {
+                draw_circle_lines(eye_x, eye_y, 4.5, 1.5, color);
+            }
+        }
+
+        if let Some(hat) = self.item(Slot::Hat) {
+            let color = faded(hat.color);
+            let (x, y) = (head.x, head.y);
+            match hat.id {
+                "cap" => {
+                    draw_rectangle(x + 6.0, y - 7.0, 20.0, 7.0, color);
+                    draw_rectangle(x + 22.0, y - 2.0, 14.0, 3.0, color);
+                }
+                "party" => {
+                    draw_triangle(
+                        vec2(x + 8.0, y),
+                        vec2(x + 24.0, y),
+                        vec2(x + 16.0, y - 18.0),
+                        color,
+                    );
+                    draw_circle(x + 16.0, y - 18.0, 2.5, faded(WHITE));
+                }
+                "crown" => {
+                    draw_rectangle(x + 6.0, y - 6.0, 22.0, 6.0, color);
+                    for i in 0..3 {
+                        let left = x + 6.0 + i as f32 * 8.0;
+                        draw_triangle(
+                            vec2(left, y - 6.0),
+                            vec2(left + 6.0, y - 6.0),
+                            vec2(left + 3.0, y - 13.0),
+                            color,
+                        );
+                    }
+                }
+                "mortarboard" => {
+                    draw_rectangle(x + 9.0, y - 5.0, 16.0, 5.0, color);
+                    draw_rectangle(x + 1.0, y - 8.0, 32.0, 3.0, color);
+                    draw_line(x + 31.0, y - 7.0, x + 33.0, y + 4.0, 1.5, faded(GOLD));
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+// 拖尾：记下恐龙最近每一步的位置，随地面一起向左移动
+#[derive(Default)]
+pub struct Trail {
+    rects: VecDeque<Rect>,
+}
+
+impl Trail {
+    pub fn step(&mut self, body: Rect, scroll: f32) {
+        for rect in &mut self.rects {
+            rect.x -= scroll;
+        }
+        self.rects.push_front(body);
+        self.rects.truncate(TRAIL_LENGTH);
+    }
+
+    pub fn clear(&mut self) {
+        self.rects.clear();
+    }
+
+    pub fn draw(&self, look: &Look, palette: &Palette) {
+        let Some(trail) = look.item(Slot::Trail) else {
+            return;
+        };
+        let body_color = look.body_color(palette);
+        let count = self.rects.len() as f32;
+        // 跳过当前位置，越旧越淡
+        for (i, rect) in self.rects.iter().enumerate().skip(1) {
+            let fade = 1.0 - i as f32 / count;
+            match trail.id {
+                "shadow" if i % 4 == 0 => {
+                    let color = Color {
+                        a: body_color.a * 0.35 * fade,
+                        ..body_color
+                    };
+                    draw_rectangle(rect.x, rect.y, rect.w, rect.h, color);
+                }
+                "rainbow" => {
+                    const BANDS: [Color; 6] = [RED, ORANGE, YELLOW, GREEN, BLUE, VIOLET];
+                    let previous = self.rects[i - 1];
+                    for (band, color) in BANDS.iter().enumerate() {
+                        let dy = rect.h * 0.25 + band as f32 * 3.0;
+                        draw_line(
+                            previous.x,
+                            previous.y + dy,
+                            rect.x,
+                            rect.y + dy,
+                            3.0,
+                            Color {
+                                a: fade,
+                                ..palette.adapt(*color)
+                            },
+                        );
+                    }
+                }
+                "sparkle" if i % 3 == 0 => {
+                    // 按下标和时间错开闪烁
+                    let twinkle = ((get_time() * 8.0 + i as f64).sin() * 0.5 + 0.5) as f32;
+                    let offset = if i % 2 == 0 { 0.3 } else { 0.7 };
+                    let color = Color {
+                        a: fade * twinkle,
+                        ..palette.adapt(trail.color)
+                    };
+                    draw_circle(rect.x, rect.y + rect.h * offset, 2.5, color);
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+// 外观定制界面：左侧为奔跑中的恐龙预览，右侧选择每一项外观。
+// 锁定的外观也可以预览，但不会保存。和设置菜单一样使用固定的方向键和 ESC 操作
+pub struct CustomizeScreen {
+    look: Look,
+    selected: usize,
+    preview: Dino,
+    trail: Trail,
+    ticks: u32,
+    accumulator: f32,
+}
+
+impl CustomizeScreen {
+    pub fn new(look: &Look) -> Self {
+        Self {
+            look: look.clone(),
+            selected: 0,
+            preview: Dino::new(),
+            trail: Trail::default(),
+            ticks: 0,
+            accumulator: 0.0,
+        }
+    }
+
+    // 关闭时要保存的外观，锁定的部分换回默认值
+    pub fn look(&self, achievements: &Achievements) -> Look {
+        self.look.clone().unlocked(achievements)
+    }
+
+    // 返回 false 表示界面已关闭
+    pub fn update(&mut self, dt: f32) -> bool {
+        let count = SLOTS.len();
+        if is_key_pressed(KeyCode::Up) {
+            self.selected = (self.selected + count - 1) % count;
+        }
+        if is_key_pressed(KeyCode::Down) {
+            self.selected = (self.selected + 1) % count;
+        }
+        let step = if is_key_pressed(KeyCode::Left) {
+            Some(-1)
+        } else if is_key_pressed(KeyCode::Right) || is_key_pressed(KeyCode::Enter) {
+            Some(1)
+        } else {
+            None
+        };
+        if let Some(step) = step {
+            let slot = SLOTS[self.selected];
+            let items = slot.items();
+            let current = items
+                .iter()
+                .position(|item| item.id == self.look.get(slot))
+                .unwrap_or(0);
+            let next = (current as i32 + step).rem_euclid(items.len() as i32) as usize;
+            self.look.set(slot, items[next].id);
+        }
+
+        // 预览的恐龙按固定步长奔跑，每隔一段时间跳一下
+        self.accumulator += dt;
+        while self.accumulator >= FIXED_DT {
+            self.accumulator -= FIXED_DT;
+            self.ticks += 1;
+            let input = DinoInput {
+                jump: self.ticks.is_multiple_of(PREVIEW_JUMP_TICKS),
+                duck: false,
+            };
+            self.preview.step(FIXED_DT, input);
+            self.trail.step(self.preview.rect(), GAME_SPEED * FIXED_DT);
+        }
+
+        !(is_key_pressed(KeyCode::Escape) || is_key_pressed(KeyCode::Tab))
+    }
+
+    pub fn draw(&self, palette: &Palette, font: Option<&Font>, achievements: &Achievements) {
+        let chinese = font.is_some();
+        draw_rectangle(0.0, 0.0, screen_width(), screen_height(), palette.overlay);
+
+        // 预览框放在恐龙平时的位置，恐龙按游戏中的坐标绘制
+        let ground = lane_ground(0);
+        let (box_x, box_y, box_w, box_h) = (DINO_X - 90.0, ground - 150.0, 220.0, 190.0);
+        draw_rectangle(box_x, box_y, box_w, box_h, palette.background);
+        draw_rectangle_lines(box_x, box_y, box_w, box_h, 3.0, palette.panel_border);
+        draw_line(box_x, ground, box_x + box_w, ground, 2.0, palette.ground);
+        self.trail.draw(&self.look, palette);
+        let body_color = self.look.body_color(palette);
+        self.preview
+            .draw(&self.look, palette, body_color, palette.dino_detail, false);
+
+        let panel_x = box_x + box_w + 30.0;
+        let panel_w = 420.0;
+        let panel_h = 260.0;
+        let panel_y = ground - panel_h + 40.0;
+        draw_rectangle(panel_x, panel_y, panel_w, panel_h, palette.panel);
+        draw_rectangle_lines(
+            panel_x,
+            panel_y,
+            panel_w,
+            panel_h,
+            3.0,
+            palette.panel_border,
+        );
+        let title = if chinese { "外观" } else { "Customize" };
+        draw_label(
+            font,
+            title,
+            panel_x + panel_w / 2.0 - label_width(font, title, 28) / 2.0,
+            panel_y + 38.0,
+            28,
+            palette.title,
+        );
+
+        let row_h = 32.0;
+        for (index, &slot) in SLOTS.iter().enumerate() {
+            let y = panel_y + 80.0 + index as f32 * row_h;
+            if index == self.selected {
+                draw_rectangle(
+                    panel_x + 10.0,
+                    y - 22.0,
+                    panel_w - 20.0,
+                    row_h,
+                    palette.highlight,
+                );
+            }
+            let id = self.look.get(slot);
+            let name = slot.find(id).map_or(id, |item| item.name(chinese));
+            draw_label(
+                font,
+                slot.label(chinese),
+                panel_x + 20.0,
+                y,
+                20,
+                palette.text,
+            );
+            let value = format!("< {} >", name);
+            draw_label(font, &value, panel_x + 110.0, y, 20, palette.text_secondary);
+            // 还没解锁的外观注明需要的成就
+            if let Some(achievement) = required_achievement(slot, id)
+                .filter(|achievement| !achievements.is_unlocked(achievement.id))
+            {
+                let note = if chinese {
+                    format!("需要成就：{}", achievement.name(true))
+                } else {
+                    format!("Needs: {}", achievement.name(false))
+                };
+                let x = panel_x + 120.0 + label_width(font, &value, 20);
+                draw_label(font, &note, x, y, 16, palette.danger);
+            }
+        }
+
+        let hint = if chinese {
+            "↑↓ 选择  ←→ 切换  ESC 保存并返回"
+        } else {
+            "Up/Down select  Left/Right change  ESC save and back"
+        };
+        draw_label(
+            font,
+            hint,
+            panel_x + panel_w / 2.0 - label_width(font, hint, 18) / 2.0,
+            panel_y + panel_h - 15.0,
+            18,
+            palette.text_secondary,
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::achievements::Stat;
+
+    #[test]
+    fn locked_and_unknown_items_fall_back_to_defaults() {
+        let path =
+            std::env::temp_dir().join(format!("dino-cosmetics-test-{}.toml", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let mut achievements = Achievements::load(path.clone());
+        let look = Look {
+            color: "gold".to_string(),
+            hat: "crown".to_string(),
+            scarf: "striped".to_string(),
+            glasses: "monocle".to_string(),
+            trail: "rainbow".to_string(),
+        };
+        let expected = Look {
+            scarf: "striped".to_string(),
+            trail: "rainbow".to_string(),
+            ..Look::default()
+        };
+        assert_eq!(look.clone().unlocked(&achievements), expected);
+
+        // 一局收集 50 颗星星解锁王冠
+        achievements.record(Stat::Stars(50));
+        let unlocked = look.unlocked(&achievements);
+        assert_eq!(unlocked.hat, "crown");
+        assert_eq!(unlocked.color, "");
+        let _ = std::fs::remove_file(path);
+    }
+
+    #[test]
+    fn chosen_colors_follow_the_night_and_theme() {
+        let look = Look {
+            color: "midnight".to_string(),
+            ..Look::default()
+        };
+        let day = Palette::default();
+        let chosen = look.body_color(&day);
+        assert_eq!(look.body_color(&day.at_night(0.0)), chosen);
+
+        // 完全进入夜晚后和调色板中的其他颜色一样变成反色
+        let night = look.body_color(&day.at_night(1.0));
+        assert!((night.r - (1.0 - chosen.r)).abs() < 1e-5);
+        assert!((night.b - (1.0 - chosen.b)).abs() < 1e-5);
+
+        // 黑色背景的主题上同样使用反色，深色身体不会和背景混在一起
+        let themes = crate::theme::load_themes();
+        let dark = &crate::theme::find_theme(&themes, "high_contrast").palette;
+        assert_eq!(look.body_color(dark), night);
+    }
+}
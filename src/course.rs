@@ -0,0 +1,33 @@
+use crate::bot::PlannerBot;
+use crate::defs::EntityDefs;
+use crate::entity::Entity;
+use crate::input::ActionState;
+use crate::GameState;
+
+// 共用的赛道：每日挑战、双人赛跑和局域网对战中所有人要跑同一条赛道。
+// 障碍物的生成会参考恐龙的状态（组合的最低分数、公平性检查），每名玩家各自生成会随操作不同而分岔，
+// 因此赛道由一个不会失误的领跑者（无敌的规划机器人）按同一种子、定义和视野宽度生成，
+// 玩家按步数复制领跑者生成的实体，赛道与玩家自己的操作无关
+pub struct Course {
+    pacer: GameState,
+}
+
+impl Course {
+    pub fn new(defs: &EntityDefs, seed: u64, view_width: f32) -> Self {
+        let mut pacer = GameState::new(defs.clone());
+        pacer.view_width = view_width;
+        pacer.reseed(seed);
+        pacer.invincible = true;
+        pacer.bot = Some(Box::new(PlannerBot::default()));
+        Self { pacer }
+    }
+
+    // 第 tick 步生成的实体，领跑者按需向前推进；玩家按步数依次取用
+    pub fn spawns_at(&mut self, tick: u64) -> &[Entity] {
+        let idle = ActionState::default();
+        while self.pacer.ticks <= tick {
+            self.pacer.update(&idle);
+        }
+        &self.pacer.spawned
+    }
+}
@@ -0,0 +1,289 @@
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use macroquad::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::profile::Profile;
+use crate::theme::Palette;
+use crate::ui::{draw_label, label_width};
+
+// 每日挑战：种子（以及由种子决定的障碍物和数学题）只取决于当天的 UTC 日期，
+// 同一天所有人跑的是同一条赛道。成绩表按日期保存在 daily/ 目录下，所有档案共用；
+// 连续挑战的天数保存在各自档案中。全部在本地完成，不需要网络
+
+const DAILY_DIR: &str = "daily";
+const STREAK_FILE: &str = "daily_streak.toml";
+const DAILY_SEED_SALT: u64 = 0x0da1_1ee5_eed5_a175;
+// 每天的成绩表保留的名次数量
+const TABLE_SIZE: usize = 10;
+
+// 以 1970-01-01 起的天数表示的 UTC 日期
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Date {
+    pub days: i64,
+}
+
+impl Date {
+    pub fn today() -> Self {
+        let seconds = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |elapsed| elapsed.as_secs());
+        Self {
+            days: (seconds / 86_400) as i64,
+        }
+    }
+
+    pub fn previous(self) -> Self {
+        Self {
+            days: self.days - 1,
+        }
+    }
+
+    // 公历年月日（Howard Hinnant 的 civil_from_days 算法）
+    pub fn ymd(self) -> (i64, u32, u32) {
+        let z = self.days + 719_468;
+        let era = z.div_euclid(146_097);
+        let doe = z.rem_euclid(146_097);
+        let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+        let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+        let mp = (5 * doy + 2) / 153;
+        let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+        let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+        let year = yoe + era * 400 + i64::from(month <= 2);
+        (year, month, day)
+    }
+
+    // 当天的种子（splitmix64 混合），相邻日期的种子也完全不同
+    pub fn seed(self) -> u64 {
+        let mut z = (self.days as u64 ^ DAILY_SEED_SALT).wrapping_add(0x9e37_79b9_7f4a_7c15);
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+}
+
+impl fmt::Display for Date {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let (year, month, day) = self.ymd();
+        write!(f, "{:04}-{:02}-{:02}", year, month, day)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct DailyEntry {
+    pub player: String,
+    pub score: u32,
+}
+
+// 某一天的成绩表，按分数从高到低排列
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DailyTable {
+    pub entries: Vec<DailyEntry>,
+}
+
+impl DailyTable {
+    // 返回本次成绩的名次（从 1 开始），没有进入成绩表时返回 None
+    pub fn submit(&mut self, player: &str, score: u32) -> Option<usize> {
+        let rank = self.entries.partition_point(|entry| entry.score >= score);
+        if rank >= TABLE_SIZE {
+            return None;
+        }
+        self.entries.insert(
+            rank,
+            DailyEntry {
+                player: player.to_string(),
+                score,
+            },
+        );
+        self.entries.truncate(TABLE_SIZE);
+        Some(rank + 1)
+    }
+}
+
+// 连续参加每日挑战的天数
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Streak {
+    // 最近一次完成挑战的日期，例如 "2026-10-18"
+    pub last_date: String,
+    pub current: u32,
+    pub best: u32,
+}
+
+impl Streak {
+    pub fn record(&mut self, today: Date) {
+        if self.last_date == today.to_string() {
+            return;
+        }
+        self.current = if self.last_date == today.previous().to_string() {
+            self.current + 1
+        } else {
+            1
+        };
+        self.best = self.best.max(self.current);
+        self.last_date = today.to_string();
+    }
+}
+
+pub fn load_toml<T: Default + for<'de> Deserialize<'de>>(path: &Path) -> T {
+    let Ok(text) = fs::read_to_string(path) else {
+        return T::default();
+    };
+    toml::from_str(&text).unwrap_or_else(|err| {
+        println!("Warning: failed to parse {}: {}", path.display(), err);
+        T::default()
+    })
+}
+
+pub fn save_toml<T: Serialize>(path: &Path, value: &T) {
+    let result = path
+        .parent()
+        .map_or(Ok(()), fs::create_dir_all)
+        .map_err(|err| err.to_string())
+        .and_then(|_| toml::to_string_pretty(value).map_err(|err| err.to_string()))
+        .and_then(|text| fs::write(path, text).map_err(|err| err.to_string()));
+    if let Err(err) = result {
+        println!("Warning: failed to save {}: {}", path.display(), err);
+    }
+}
+
+// 正在进行的每日挑战
+pub struct DailyChallenge {
+    pub date: Date,
+    pub table: DailyTable,
+    pub streak: Streak,
+    streak_path: PathBuf,
+    // 本次挑战最近一局的名次
+    last_rank: Option<usize>,
+}
+
+impl DailyChallenge {
+    pub fn start(profile: &Profile) -> Self {
+        let date = Date::today();
+        let streak_path = profile.path(STREAK_FILE);
+        Self {
+            date,
+            table: load_toml(&Self::table_path(date)),
+            streak: load_toml(&streak_path),
+            streak_path,
+            last_rank: None,
+        }
+    }
+
+    fn table_path(date: Date) -> PathBuf {
+        PathBuf::from(DAILY_DIR).join(format!("{}.toml", date))
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.date.seed()
+    }
+
+    // 一局结束后记录成绩和连续天数
+    pub fn submit(&mut self, player: &str, score: u32) {
+        // 先重新读取成绩表，同一台电脑上的其他档案可能刚刚提交过
+        self.table = load_toml(&Self::table_path(self.date));
+        self.last_rank = self.table.submit(player, score);
+        save_toml(&Self::table_path(self.date), &self.table);
+        self.streak.record(self.date);
+        save_toml(&self.streak_path, &self.streak);
+    }
+
+    // 游戏结束画面上的当日成绩表和连续天数
+    pub fn draw_summary(&self, palette: &Palette, font: Option<&Font>, top: f32) {
+        let chinese = font.is_some();
+        let center_x = screen_width() / 2.0;
+        let title = if chinese {
+            format!(
+                "每日挑战 {}  连续 {} 天（最长 {} 天）",
+                self.date, self.streak.current, self.streak.best
+            )
+        } else {
+            format!(
+                "Daily {}  streak {} (best {})",
+                self.date, self.streak.current, self.streak.best
+            )
+        };
+        draw_label(
+            font,
+            &title,
+            center_x - label_width(font, &title, 20) / 2.0,
+            top,
+            20,
+            palette.title,
+        );
+        for (index, entry) in self.table.entries.iter().enumerate() {
+            let line = format!("{:>2}. {:<16} {:>6}", index + 1, entry.player, entry.score);
+            let color = if self.last_rank == Some(index + 1) {
+                palette.input
+            } else {
+                palette.text_secondary
+            };
+            draw_label(
+                font,
+                &line,
+                center_x - 110.0,
+                top + 24.0 + index as f32 * 18.0,
+                16,
+                color,
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    // 不读写成绩表和连续天数文件的每日挑战
+    pub fn challenge(date: Date) -> DailyChallenge {
+        DailyChallenge {
+            date,
+            table: DailyTable::default(),
+            streak: Streak::default(),
+            streak_path: PathBuf::new(),
+            last_rank: None,
+        }
+    }
+
+    #[test]
+    fn dates_convert_to_calendar_days() {
+        assert_eq!(Date { days: 0 }.to_string(), "1970-01-01");
+        assert_eq!(Date { days: 11_016 }.to_string(), "2000-02-29");
+        assert_eq!(Date { days: 20_744 }.to_string(), "2026-10-18");
+        assert_eq!(Date { days: -1 }.to_string(), "1969-12-31");
+        assert_ne!(Date { days: 1 }.seed(), Date { days: 2 }.seed());
+    }
+
+    #[test]
+    fn streak_counts_consecutive_days() {
+        let mut streak = Streak::default();
+        let day = Date { days: 20_000 };
+        streak.record(day);
+        streak.record(day);
+        assert_eq!(streak.current, 1);
+        streak.record(Date { days: 20_001 });
+        streak.record(Date { days: 20_002 });
+        assert_eq!((streak.current, streak.best), (3, 3));
+        streak.record(Date { days: 20_005 });
+        assert_eq!((streak.current, streak.best), (1, 3));
+    }
+
+    #[test]
+    fn table_keeps_top_scores_in_order() {
+        let mut table = DailyTable::default();
+        for score in 0..TABLE_SIZE as u32 {
+            table.submit("a", score * 10);
+        }
+        assert_eq!(table.submit("b", 0), None);
+        assert_eq!(table.submit("b", 55), Some(5));
+        assert_eq!(table.entries.len(), TABLE_SIZE);
+        assert!(table
+            .entries
+            .windows(2)
+            .all(|pair| pair[0].score >= pair[1].score));
+    }
+}
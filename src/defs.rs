@@ -0,0 +1,513 @@
+use std::collections::{BTreeMap, HashSet};
+use std::fs;
+use std::time::SystemTime;
+
+use ::rand::rngs::StdRng;
+use ::rand::Rng;
+use macroquad::prelude::*;
+use serde::Deserialize;
+
+use crate::entity::{Behaviour, Entity, Sprite};
+use crate::powerup::POWER_UP_NAMES;
+use crate::GROUND_Y;
+
+// 运行目录下的定义文件优先，不存在时使用编译进可执行文件的默认定义
+pub const DEFS_PATH: &str = "data/entities.toml";
+const BUILTIN_DEFS: &str = include_str!("../data/entities.toml");
+// 热重载时检查文件修改时间的间隔（秒）
+const RELOAD_CHECK_INTERVAL: f32 = 0.5;
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct DefsFile {
+    spawners: BTreeMap<String, SpawnerFile>,
+    #[serde(default)]
+    kinds: Vec<KindDef>,
+    #[serde(default)]
+    patterns: Vec<PatternFile>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SpawnerFile {
+    interval: f32,
+    chance: f64,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct PatternFile {
+    name: String,
+    spawner: String,
+    weight: u32,
+    #[serde(default)]
+    min_score: u32,
+    parts: Vec<PartFile>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct PartFile {
+    kind: String,
+    #[serde(default)]
+    x: f32,
+    #[serde(default)]
+    elevation: Option<f32>,
+}
+
+#[derive(Clone, Copy, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SpriteKind {
+    Cactus,
+    Bird,
+    Star,
+    PowerUp,
+}
+
+#[derive(Clone, Copy, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Effect {
+    // 碰到就游戏结束，安全越过后得分
+    GameOver,
+    // 被收集并得分
+    Score,
+    // 被收集并获得 power_up 指定的道具效果
+    PowerUp,
+}
+
+// 一种障碍物或道具
+#[derive(Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct KindDef {
+    pub name: String,
+    pub sprite: SpriteKind,
+    pub effect: Effect,
+    pub size: [f32; 2],
+    #[serde(default)]
+    pub hitbox: Option<[f32; 4]>,
+    pub elevation: f32,
+    #[serde(default)]
+    pub elevation_jitter: f32,
+    pub score: u32,
+    // 道具名，sprite 或 effect 为 power_up 时必须填写
+    #[serde(default)]
+    pub power_up: Option<String>,
+}
+
+impl KindDef {
+    // 道具名在登记表中的静态字符串，validate 保证已登记
+    fn power_up_name(&self) -> &'static str {
+        let name = self.power_up.as_deref().unwrap_or_default();
+        POWER_UP_NAMES
+            .into_iter()
+            .find(|known| *known == name)
+            .unwrap_or_default()
+    }
+
+    fn entity(&self, x: f32, y: f32) -> Entity {
+        let [width, height] = self.size;
+        let collider = match self.hitbox {
+            Some([hx, hy, hw, hh]) => Rect::new(hx, hy, hw, hh),
+            None => Rect::new(0.0, 0.0, width, height),
+        };
+        Entity {
+            pos: vec2(x, y),
+            vel: Vec2::ZERO,
+            size: vec2(width, height),
+            collider: Some(collider),
+            sprite: match self.sprite {
+                SpriteKind::Cactus => Sprite::Cactus,
+                SpriteKind::Bird => Sprite::Bird,
+                SpriteKind::Star => Sprite::Star,
+                SpriteKind::PowerUp => Sprite::PowerUp {
+                    name: self.power_up_name(),
+                },
+            },
+            behaviour: match self.effect {
+                Effect::GameOver => Behaviour::Damaging {
+                    pass_score: self.score,
+                },
+                Effect::Score => Behaviour::Collectible { score: self.score },
+                Effect::PowerUp => Behaviour::PowerUp {
+                    name: self.power_up_name(),
+                },
+            },
+            lane: 0,
+        }
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        if self.size.iter().any(|v| !positive(*v)) {
+            return Err(format!("size {:?} must be positive", self.size));
+        }
+        if let Some(hitbox) = self.hitbox {
+            if !(positive(hitbox[2]) && positive(hitbox[3])) {
+                return Err(format!("hitbox {:?} must have a positive size", hitbox));
+            }
+        }
+        if !non_negative(self.elevation_jitter) {
+            return Err("elevation_jitter must not be negative".to_string());
+        }
+        let uses_power_up =
+            matches!(self.sprite, SpriteKind::PowerUp) || matches!(self.effect, Effect::PowerUp);
+        match &self.power_up {
+            Some(name) if !POWER_UP_NAMES.contains(&name.as_str()) => {
+                return Err(format!(
+                    "unknown power_up '{}', expected one of: {}",
+                    name,
+                    POWER_UP_NAMES.join(", ")
+                ));
+            }
+            None if uses_power_up => return Err("power_up must be set".to_string()),
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+// NaN 也视为无效
+fn positive(value: f32) -> bool {
+    value > 0.0
+}
+
+fn non_negative(value: f32) -> bool {
+    value >= 0.0
+}
+
+// 组合中的一个实体：种类、相对组合起点的水平位置、可选的高度覆盖
+#[derive(Clone)]
+pub struct PartDef {
+    pub kind: KindDef,
+    pub x: f32,
+    pub elevation: Option<f32>,
+}
+
+// 一个组合（例如单个仙人掌、三连仙人掌、仙人掌加飞鸟），生成时作为整体检查能否通过
+#[derive(Clone)]
+pub struct PatternDef {
+    pub weight: u32,
+    pub min_score: u32,
+    pub parts: Vec<PartDef>,
+}
+
+impl PatternDef {
+    fn instantiate(&self, rng: &mut StdRng, x: f32) -> Vec<Entity> {
+        self.parts
+            .iter()
+            .map(|part| {
+                let kind = &part.kind;
+                let jitter = if kind.elevation_jitter > 0.0 {
+                    rng.gen_range(0.0..kind.elevation_jitter)
+                } else {
+                    0.0
+                };
+                let elevation = part.elevation.unwrap_or(kind.elevation) + jitter;
+                kind.entity(x + part.x, GROUND_Y - elevation - kind.size[1])
+            })
+            .collect()
+    }
+}
+
+#[derive(Clone)]
+pub struct SpawnerDef {
+    // 每隔 interval 秒检查一次，以 chance 概率生成
+    pub interval: f32,
+    pub chance: f64,
+    pub patterns: Vec<PatternDef>,
+}
+
+impl SpawnerDef {
+    // 按权重从已达到 min_score 的组合中选一个，在 x 处生成。
+    // 含有障碍物的组合需要通过 clearable 检查，不通过时按权重换一个，全部不通过则本次不生成
+    pub fn spawn(
+        &self,
+        rng: &mut StdRng,
+        score: u32,
+        x: f32,
+        mut clearable: impl FnMut(&[Entity]) -> bool,
+    ) -> Vec<Entity> {
+        if !rng.gen_bool(self.chance) {
+            return Vec::new();
+        }
+        let mut candidates: Vec<&PatternDef> = self
+            .patterns
+            .iter()
+            .filter(|pattern| score >= pattern.min_score)
+            .collect();
+        while !candidates.is_empty() {
+            let total: u32 = candidates.iter().map(|pattern| pattern.weight).sum();
+            let mut pick = rng.gen_range(0..total);
+            let index = candidates
+                .iter()
+                .position(|pattern| {
+                    if pick < pattern.weight {
+                        return true;
+                    }
+                    pick -= pattern.weight;
+                    false
+                })
+                .unwrap_or(0);
+            let pattern = candidates.remove(index);
+
+            let entities = pattern.instantiate(rng, x);
+            if !entities.iter().any(Entity::is_damaging) || clearable(&entities) {
+                return entities;
+            }
+        }
+        Vec::new()
+    }
+}
+
+#[derive(Clone)]
+pub struct EntityDefs {
+    pub spawners: Vec<SpawnerDef>,
+}
+
+impl Default for EntityDefs {
+    fn default() -> Self {
+        EntityDefs::parse(BUILTIN_DEFS).expect("built-in entity definitions are valid")
+    }
+}
+
+impl EntityDefs {
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let file: DefsFile = toml::from_str(text).map_err(|err| err.to_string())?;
+
+        for (name, spawner) in &file.spawners {
+            if !positive(spawner.interval) {
+                return Err(format!("spawner '{}': interval must be positive", name));
+            }
+            if !(0.0..=1.0).contains(&spawner.chance) {
+                return Err(format!(
+                    "spawner '{}': chance must be between 0 and 1",
+                    name
+                ));
+            }
+        }
+
+        let mut kinds = BTreeMap::new();
+        for kind in &file.kinds {
+            kind.validate()
+                .map_err(|err| format!("kind '{}': {}", kind.name, err))?;
+            if kinds.insert(kind.name.as_str(), kind).is_some() {
+                return Err(format!("kind '{}' is defined more than once", kind.name));
+            }
+        }
+
+        let mut names = HashSet::new();
+        let mut spawners: BTreeMap<&str, SpawnerDef> = file
+            .spawners
+            .iter()
+            .map(|(name, spawner)| {
+                let def = SpawnerDef {
+                    interval: spawner.interval,
+                    chance: spawner.chance,
+                    patterns: Vec::new(),
+                };
+                (name.as_str(), def)
+            })
+            .collect();
+        for pattern in &file.patterns {
+            let context = |err: String| format!("pattern '{}': {}", pattern.name, err);
+            if !names.insert(pattern.name.as_str()) {
+                return Err(context("defined more than once".to_string()));
+            }
+            let spawner = spawners
+                .get_mut(pattern.spawner.as_str())
+                .ok_or_else(|| context(format!("unknown spawner '{}'", pattern.spawner)))?;
+            if pattern.weight == 0 {
+                return Err(context("weight must be at least 1".to_string()));
+            }
+            if pattern.parts.is_empty() {
+                return Err(context("parts must not be empty".to_string()));
+            }
+            let parts = pattern
+                .parts
+                .iter()
+                .map(|part| {
+                    let kind = kinds
+                        .get(part.kind.as_str())
+                        .ok_or_else(|| context(format!("unknown kind '{}'", part.kind)))?;
+                    Ok(PartDef {
+                        kind: (*kind).clone(),
+                        x: part.x,
+                        elevation: part.elevation,
+                    })
+                })
+                .collect::<Result<Vec<_>, String>>()?;
+            spawner.patterns.push(PatternDef {
+                weight: pattern.weight,
+                min_score: pattern.min_score,
+                parts,
+            });
+        }
+
+        Ok(EntityDefs {
+            spawners: spawners.into_values().collect(),
+        })
+    }
+
+    // 启动时加载，文件有错误时输出错误并使用默认定义
+    pub fn load() -> Self {
+        match fs::read_to_string(DEFS_PATH) {
+            Ok(text) => EntityDefs::parse(&text).unwrap_or_else(|err| {
+                eprintln!("Error: invalid {}: {}", DEFS_PATH, err);
+                eprintln!("Using built-in entity definitions");
+                EntityDefs::default()
+            }),
+            Err(_) => EntityDefs::default(),
+        }
+    }
+}
+
+// 游戏运行中定期检查定义文件，修改后自动重新加载
+pub struct DefsWatcher {
+    modified: Option<SystemTime>,
+    check_timer: f32,
+}
+
+impl DefsWatcher {
+    pub fn new() -> Self {
+        Self {
+            modified: modified_time(),
+            check_timer: 0.0,
+        }
+    }
+
+    // 文件发生变化且内容有效时返回新的定义，无效时输出错误并保留当前定义
+    pub fn poll(&mut self, dt: f32) -> Option<EntityDefs> {
+        self.check_timer += dt;
+        if self.check_timer < RELOAD_CHECK_INTERVAL {
+            return None;
+        }
+        self.check_timer = 0.0;
+
+        let modified = modified_time();
+        if modified.is_none() || modified == self.modified {
+            return None;
+        }
+        self.modified = modified;
+
+        let text = fs::read_to_string(DEFS_PATH).ok()?;
+        match EntityDefs::parse(&text) {
+            Ok(defs) => {
+                println!("Reloaded {}", DEFS_PATH);
+                Some(defs)
+            }
+            Err(err) => {
+                println!(
+                    "Error: invalid {}: {} (keeping previous definitions)",
+                    DEFS_PATH, err
+                );
+                None
+            }
+        }
+    }
+}
+
+fn modified_time() -> Option<SystemTime> {
+    fs::metadata(DEFS_PATH)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn error(text: &str) -> String {
+        match EntityDefs::parse(text) {
+            Ok(_) => panic!("definitions should be rejected"),
+            Err(err) => err,
+        }
+    }
+
+    const SPAWNER: &str = "[spawners.obstacles]\ninterval = 2.5\nchance = 0.4\n";
+    const ROCK: &str = "[[kinds]]\nname = \"rock\"\nsprite = \"cactus\"\neffect = \"game_over\"\n\
+                        size = [10.0, 10.0]\nelevation = 0.0\nscore = 1\n";
+
+    #[test]
+    fn builtin_defs_are_valid() {
+        let defs = EntityDefs::default();
+        // 生成器按名字排序：obstacles、pickups、power_ups
+        assert_eq!(defs.spawners.len(), 3);
+        assert!(defs.spawners[0].patterns.len() > 1);
+        assert_eq!(defs.spawners[1].patterns[0].parts[0].kind.score, 5);
+        // 每种登记的道具都有对应的组合
+        for name in POWER_UP_NAMES {
+            assert!(defs.spawners[2]
+                .patterns
+                .iter()
+                .any(|pattern| pattern.parts[0].kind.power_up.as_deref() == Some(name)));
+        }
+    }
+
+    #[test]
+    fn unknown_power_up_is_rejected() {
+        let err = error(&format!(
+            "{}{}",
+            SPAWNER,
+            ROCK.replace(
+                "effect = \"game_over\"",
+                "effect = \"power_up\"\npower_up = \"wings\""
+            )
+        ));
+        assert!(err.contains("wings") && err.contains("shield"), "{}", err);
+        let err = error(&format!(
+            "{}{}",
+            SPAWNER,
+            ROCK.replace("effect = \"game_over\"", "effect = \"power_up\"")
+        ));
+        assert!(err.contains("power_up must be set"), "{}", err);
+    }
+
+    #[test]
+    fn unknown_spawner_is_named_in_error() {
+        let err = error(&format!(
+            "{}{}[[patterns]]\nname = \"rocks\"\nspawner = \"boulders\"\nweight = 1\n\
+             parts = [{{ kind = \"rock\" }}]\n",
+            SPAWNER, ROCK
+        ));
+        assert!(
+            err.contains("pattern 'rocks'") && err.contains("boulders"),
+            "{}",
+            err
+        );
+    }
+
+    #[test]
+    fn unknown_kind_is_named_in_error() {
+        let err = error(&format!(
+            "{}{}[[patterns]]\nname = \"rocks\"\nspawner = \"obstacles\"\nweight = 1\n\
+             parts = [{{ kind = \"rock\" }}, {{ kind = \"pebble\", x = 20.0 }}]\n",
+            SPAWNER, ROCK
+        ));
+        assert!(err.contains("pebble"), "{}", err);
+    }
+
+    #[test]
+    fn non_positive_size_is_rejected() {
+        let err = error(&format!(
+            "{}{}",
+            SPAWNER,
+            ROCK.replace("[10.0, 10.0]", "[0.0, 10.0]")
+        ));
+        assert!(
+            err.contains("kind 'rock'") && err.contains("size"),
+            "{}",
+            err
+        );
+    }
+
+    #[test]
+    fn unknown_field_is_rejected() {
+        let err = error("[spawners.obstacles]\ninterval = 2.5\nchance = 0.4\nspeed = 3\n");
+        assert!(err.contains("speed"), "{}", err);
+    }
+
+    #[test]
+    fn chance_out_of_range_is_rejected() {
+        let err = error("[spawners.obstacles]\ninterval = 2.5\nchance = 1.5\n");
+        assert!(err.contains("chance"), "{}", err);
+    }
+}
@@ -0,0 +1,187 @@
+use macroquad::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::cosmetics::Look;
+use crate::theme::Palette;
+use crate::{
+    lane_ground, DINO_DUCK_HEIGHT, DINO_DUCK_WIDTH, DINO_HEIGHT, DINO_WIDTH, DINO_X,
+    FAST_FALL_MULTIPLIER, GRAVITY, HIGH_JUMP_FORCE, JUMP_FORCE, LANE_SPACING,
+};
+
+// 恐龙在一步中的输入：是否按下跳跃、是否按住下蹲
+#[derive(Clone, Copy, Default, PartialEq, Debug)]
+pub struct DinoInput {
+    pub jump: bool,
+    pub duck: bool,
+}
+
+// 恐龙的物理状态，游戏和可通过性检查共用同一套运动规则
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+pub struct Dino {
+    pub y: f32,
+    pub velocity: f32,
+    pub is_jumping: bool,
+    pub is_ducking: bool,
+    // 多车道模式中所在的车道，0 为最上面的一条（GROUND_Y）
+    #[serde(default)]
+    pub lane: usize,
+    // 超级跳跃道具剩余的步数，期间起跳使用 HIGH_JUMP_FORCE
+    #[serde(default)]
+    pub high_jump_ticks: u32,
+}
+
+impl Dino {
+    pub fn new() -> Self {
+        Self {
+            y: lane_ground(0) - DINO_HEIGHT,
+            velocity: 0.0,
+            is_jumping: false,
+            is_ducking: false,
+            lane: 0,
+            high_jump_ticks: 0,
+        }
+    }
+
+    // 换到另一条车道，空中换道时保持离地高度
+    pub fn switch_lane(&mut self, lane: usize) {
+        self.y += (lane as f32 - self.lane as f32) * LANE_SPACING;
+        self.lane = lane;
+    }
+
+    // 换算到第一条车道上的同一状态，可通过性检查和机器人只按一条车道计算
+    pub fn in_first_lane(mut self) -> Self {
+        self.switch_lane(0);
+        self
+    }
+
+    // 推进一步，返回这一步是否起跳
+    pub fn step(&mut self, dt: f32, input: DinoInput) -> bool {
+        let jumped = input.jump && !self.is_jumping && !self.is_ducking;
+        if jumped {
+            self.velocity = if self.high_jump_ticks > 0 {
+                HIGH_JUMP_FORCE
+            } else {
+                JUMP_FORCE
+            };
+            self.is_jumping = true;
+        }
+        self.high_jump_ticks = self.high_jump_ticks.saturating_sub(1);
+
+        // 应用重力，空中按住下蹲会加速下落
+        let gravity = if self.is_jumping && input.duck {
+            GRAVITY * FAST_FALL_MULTIPLIER
+        } else {
+            GRAVITY
+        };
+        self.velocity += gravity * dt;
+        self.y += self.velocity * dt;
+
+        // 检查着地
+        let ground = lane_ground(self.lane);
+        if self.y >= ground - DINO_HEIGHT {
+            self.y = ground - DINO_HEIGHT;
+            self.velocity = 0.0;
+            self.is_jumping = false;
+        }
+
+        // 在地面上按住下蹲
+        self.is_ducking = !self.is_jumping && input.duck;
+        jumped
+    }
+
+    // 当前的碰撞盒，下蹲时更矮更宽
+    pub fn rect(&self) -> Rect {
+        if self.is_ducking {
+            Rect::new(
+                DINO_X,
+                lane_ground(self.lane) - DINO_DUCK_HEIGHT,
+                DINO_DUCK_WIDTH,
+                DINO_DUCK_HEIGHT,
+            )
+        } else {
+            Rect::new(DINO_X, self.y, DINO_WIDTH, DINO_HEIGHT)
+        }
+    }
+
+    // 绘制恐龙：下蹲时身体压低，头部前伸；dead 为撞到障碍物后的样子，眼睛画成叉、张着嘴、腿不再摆动。
+    // 配饰画在碰撞盒之外也不影响碰撞
+    pub fn draw(&self, look: &Look, palette: &Palette, color: Color, detail: Color, dead: bool) {
+        // 绘制恐龙身体
+        let body = self.rect();
+        let head_dx = body.w - DINO_WIDTH;
+        draw_rectangle(body.x, body.y, body.w, body.h, color);
+
+        // 绘制恐龙的头部（更宽一些）
+        let head = Rect::new(
+            DINO_X - 5.0 + head_dx,
+            body.y - 8.0,
+            DINO_WIDTH + 12.0,
+            15.0,
+        );
+        draw_rectangle(head.x, head.y, head.w, head.h, color);
+
+        // 绘制恐龙的眼睛和嘴巴
+        let (eye_x, eye_y) = (DINO_X + 8.0 + head_dx, body.y - 2.0);
+        if dead {
+            draw_line(
+                eye_x - 3.0,
+                eye_y - 3.0,
+                eye_x + 3.0,
+                eye_y + 3.0,
+                1.5,
+                detail,
+            );
+            draw_line(
+                eye_x - 3.0,
+                eye_y + 3.0,
+                eye_x + 3.0,
+                eye_y - 3.0,
+                1.5,
+                detail,
+            );
+            draw_rectangle(DINO_X + 18.0 + head_dx, body.y, 7.0, 5.0, detail);
+        } else {
+            draw_circle(eye_x, eye_y, 2.5, detail);
+            draw_line(
+                DINO_X + 18.0 + head_dx,
+                body.y + 2.0,
+                DINO_X + 25.0 + head_dx,
+                body.y + 2.0,
+                2.0,
+                detail,
+            );
+        }
+
+        // 绘制恐龙的尾巴
+        draw_rectangle(DINO_X - 5.0, body.y + body.h - 20.0, 8.0, 4.0, color);
+
+        // 绘制恐龙的腿（简单的线条）
+        if !self.is_jumping {
+            let leg_offset = if dead {
+                0.0
+            } else {
+                (get_time() * 10.0).sin() as f32 * 2.0
+            };
+            // 前腿
+            draw_line(
+                DINO_X + 8.0,
+                body.y + body.h,
+                DINO_X + 8.0,
+                body.y + body.h + 20.0 + leg_offset,
+                3.0,
+                detail,
+            );
+            // 后腿
+            draw_line(
+                DINO_X + 15.0,
+                body.y + body.h,
+                DINO_X + 15.0,
+                body.y + body.h + 20.0 - leg_offset,
+                3.0,
+                detail,
+            );
+        }
+
+        look.draw_accessories(head, body, palette, color.a);
+    }
+}
@@ -0,0 +1,370 @@
+use macroquad::prelude::*;
+
+use crate::batch::CircleBatch;
+use crate::pool::RingBuffer;
+use crate::powerup;
+use crate::theme::Palette;
+use crate::{GROUND_Y, GROUND_Y2};
+
+// 统一的游戏实体：仙人掌、星星、石子等都由同一组组件描述
+// 障碍物和道具由 data/entities.toml 中的定义生成（见 defs.rs），石子装饰由 Entity::stone 生成
+#[derive(Clone)]
+pub struct Entity {
+    pub pos: Vec2,
+    // 相对地面的速度，整体滚动由 World::integrate 统一施加
+    pub vel: Vec2,
+    // 外观尺寸，实体完全移出屏幕左侧（x <= -size.x）后被移除
+    pub size: Vec2,
+    // 碰撞盒（相对 pos），None 表示不参与碰撞
+    pub collider: Option<Rect>,
+    pub sprite: Sprite,
+    pub behaviour: Behaviour,
+    // 多车道模式中所在的车道，只与同一车道上的恐龙碰撞
+    pub lane: usize,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Sprite {
+    Cactus,
+    Bird,
+    Star,
+    // 道具图标，name 为道具名
+    PowerUp { name: &'static str },
+    // radius 为半径，tint 为调色板中的颜色序号
+    Stone { radius: f32, tint: usize },
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Behaviour {
+    // 碰到就游戏结束；安全越过（移出屏幕）后获得 pass_score 分
+    Damaging { pass_score: u32 },
+    // 碰到后被收集并获得 score 分
+    Collectible { score: u32 },
+    // 碰到后被收集并获得名为 name 的道具效果
+    PowerUp { name: &'static str },
+    // 纯装饰，不参与碰撞
+    Decoration,
+}
+
+// 绘制层次，按 LAYERS 的顺序从后往前画
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Layer {
+    Obstacles,
+    Pickups,
+    Decorations,
+}
+
+const LAYERS: [Layer; 3] = [Layer::Obstacles, Layer::Pickups, Layer::Decorations];
+
+impl Entity {
+    pub fn stone(x: f32, y: f32, radius: f32, tint: usize) -> Self {
+        Self {
+            pos: vec2(x, y),
+            vel: Vec2::ZERO,
+            // 石子按固定的 10 像素边界剔除
+            size: vec2(10.0, 10.0),
+            collider: None,
+            sprite: Sprite::Stone { radius, tint },
+            behaviour: Behaviour::Decoration,
+            lane: 0,
+        }
+    }
+
+    pub fn rect(&self) -> Option<Rect> {
+        self.collider.map(|rect| rect.offset(self.pos))
+    }
+
+    pub fn is_damaging(&self) -> bool {
+        matches!(self.behaviour, Behaviour::Damaging { .. })
+    }
+
+    fn layer(&self) -> Layer {
+        match self.behaviour {
+            Behaviour::Damaging { .. } => Layer::Obstacles,
+            Behaviour::Collectible { .. } | Behaviour::PowerUp { .. } => Layer::Pickups,
+            Behaviour::Decoration => Layer::Decorations,
+        }
+    }
+}
+
+// 一次碰撞检测的结果
+#[derive(Default)]
+pub struct Contacts {
+    // 收集到的星星：中心位置和分数
+    pub stars: Vec<(Vec2, u32)>,
+    pub power_ups: Vec<&'static str>,
+    pub hit: bool,
+}
+
+// 一次移除屏幕外实体的结果
+#[derive(Default)]
+pub struct Despawned {
+    // 安全越过的障碍物的得分
+    pub passed_score: u32,
+    // 没有收集就离开屏幕的星星数量
+    pub missed_stars: u32,
+}
+
+// 障碍物、星星和道具与装饰分开存放：宽屏上石子很多，装饰的缓冲区满了只会丢弃最旧的石子，
+// 不会挤掉恐龙还没有越过的障碍物或星星
+pub struct World {
+    entities: RingBuffer<Entity>,
+    decorations: RingBuffer<Entity>,
+}
+
+impl World {
+    pub fn with_capacity(capacity: usize, decorations: usize) -> Self {
+        Self {
+            entities: RingBuffer::with_capacity(capacity),
+            decorations: RingBuffer::with_capacity(decorations),
+        }
+    }
+
+    pub fn spawn(&mut self, entity: Entity) {
+        if let Behaviour::Decoration = entity.behaviour {
+            self.decorations.push(entity);
+        } else {
+            self.entities.push(entity);
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.entities.clear();
+        self.decorations.clear();
+    }
+
+    pub fn len(&self) -> usize {
+        self.entities.len() + self.decorations.len()
+    }
+
+    pub fn retain(&mut self, mut f: impl FnMut(&Entity) -> bool) {
+        self.entities.retain(&mut f);
+        self.decorations.retain(f);
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Entity> {
+        self.entities.iter().chain(self.decorations.iter())
+    }
+
+    // 按各自的速度移动所有实体，并随地面以 speed 向左滚动
+    pub fn integrate(&mut self, dt: f32, speed: f32) {
+        for entity in self.entities.iter_mut().chain(self.decorations.iter_mut()) {
+            entity.pos += entity.vel * dt;
+            entity.pos.x -= speed * dt;
+        }
+    }
+
+    // 移除移出屏幕左侧的实体，统计 lane 车道上越过的障碍物和漏掉的星星
+    pub fn despawn_offscreen(&mut self, lane: usize) -> Despawned {
+        let mut despawned = Despawned::default();
+        self.entities.retain(|entity| {
+            let keep = entity.pos.x > -entity.size.x;
+            if !keep && entity.lane == lane {
+                match entity.behaviour {
+                    Behaviour::Damaging { pass_score } => despawned.passed_score += pass_score,
+                    Behaviour::Collectible { .. } => despawned.missed_stars += 1,
+                    _ => {}
+                }
+            }
+            keep
+        });
+        self.decorations
+            .retain(|entity| entity.pos.x > -entity.size.x);
+        despawned
+    }
+
+    // 把 lane 车道上 radius 范围内的星星向 target 移动 distance 的距离
+    pub fn pull_collectibles(&mut self, target: Vec2, lane: usize, radius: f32, distance: f32) {
+        for entity in &mut self.entities {
+            if entity.lane != lane || !matches!(entity.behaviour, Behaviour::Collectible { .. }) {
+                continue;
+            }
+            let offset = target - (entity.pos + entity.size / 2.0);
+            let length = offset.length();
+            if length > 0.0 && length <= radius {
+                entity.pos += offset * (distance.min(length) / length);
+            }
+        }
+    }
+
+    // 先收集 lane 车道上与 target 重叠的星星和道具，再检测是否撞上同一车道的伤害性实体
+    pub fn collide(&mut self, target: Rect, lane: usize) -> Contacts {
+        let mut contacts = Contacts::default();
+        let touches = |entity: &Entity| {
+            entity.lane == lane && entity.rect().is_some_and(|rect| target.overlaps(&rect))
+        };
+        self.entities.retain(|entity| match entity.behaviour {
+            Behaviour::Collectible { score } if touches(entity) => {
+                contacts.stars.push((entity.pos + entity.size / 2.0, score));
+                false
+            }
+            Behaviour::PowerUp { name } if touches(entity) => {
+                contacts.power_ups.push(name);
+                false
+            }
+            _ => true,
+        });
+        contacts.hit = self
+            .entities
+            .iter()
+            .any(|entity| entity.is_damaging() && touches(entity));
+        contacts
+    }
+
+    // 按层次绘制所有实体：障碍物、道具、装饰
+    pub fn draw(&self, palette: &Palette, batch: &mut CircleBatch) {
+        for layer in LAYERS {
+            for entity in self.iter().filter(|entity| entity.layer() == layer) {
+                draw_entity(entity, palette, batch);
+            }
+            batch.flush();
+        }
+    }
+}
+
+fn draw_entity(entity: &Entity, palette: &Palette, batch: &mut CircleBatch) {
+    let Vec2 { x, y } = entity.pos;
+    match entity.sprite {
+        Sprite::Cactus => {
+            let (width, height) = (entity.size.x, entity.size.y);
+            draw_rectangle(x, y, width, height, palette.cactus);
+            // 仙人掌的刺
+            for i in 0..3 {
+                let spike_y = y + (i as f32 * 20.0) + 10.0;
+                draw_line(x - 5.0, spike_y, x, spike_y, 2.0, palette.cactus);
+                draw_line(
+                    x + width,
+                    spike_y,
+                    x + width + 5.0,
+                    spike_y,
+                    2.0,
+                    palette.cactus,
+                );
+            }
+        }
+        Sprite::Bird => {
+            let (width, height) = (entity.size.x, entity.size.y);
+            // 身体、头和喙
+            draw_rectangle(
+                x + 6.0,
+                y + height * 0.4,
+                width - 10.0,
+                height * 0.4,
+                palette.cactus,
+            );
+            draw_rectangle(x, y + height * 0.25, 8.0, 6.0, palette.cactus);
+            draw_triangle(
+                vec2(x, y + height * 0.3),
+                vec2(x - 6.0, y + height * 0.45),
+                vec2(x, y + height * 0.55),
+                palette.cactus,
+            );
+            // 翅膀随位置上下扇动
+            let wing_up = (x / 24.0).floor() as i32 % 2 == 0;
+            let wing_tip = if wing_up { y } else { y + height };
+            draw_triangle(
+                vec2(x + width * 0.35, y + height * 0.5),
+                vec2(x + width * 0.7, y + height * 0.5),
+                vec2(x + width * 0.45, wing_tip),
+                palette.cactus,
+            );
+        }
+        Sprite::Star => {
+            let radius = entity.size.x / 2.0;
+            draw_star(x + radius, y + radius, radius, palette);
+        }
+        Sprite::PowerUp { name } => {
+            let radius = entity.size.x / 2.0;
+            powerup::draw_pickup(name, x + radius, y + radius, radius, palette);
+        }
+        Sprite::Stone { radius, tint } => {
+            // 两条线中间的石子使用彩色，地面线附近的石子使用灰色
+            let (colors, highlight_color) = if y > GROUND_Y && y < GROUND_Y2 {
+                (&palette.stone_colors, palette.stone_highlight)
+            } else {
+                (&palette.pebble_colors, palette.pebble_highlight)
+            };
+            batch.circle(x, y, radius, colors[tint % colors.len()]);
+
+            // 添加高光效果让石子更立体
+            batch.circle(
+                x - radius * 0.3,
+                y - radius * 0.3,
+                radius * 0.3,
+                highlight_color,
+            );
+        }
+    }
+}
+
+fn draw_star(center_x: f32, center_y: f32, radius: f32, palette: &Palette) {
+    let points = 5;
+    let outer_radius = radius;
+    let inner_radius = radius * 0.4;
+
+    let mut vertices = Vec::new();
+
+    for i in 0..(points * 2) {
+        let angle = (i as f32 * std::f32::consts::PI) / points as f32;
+        let r = if i % 2 == 0 {
+            outer_radius
+        } else {
+            inner_radius
+        };
+        let x = center_x + r * angle.cos();
+        let y = center_y + r * angle.sin();
+        vertices.push(Vec2::new(x, y));
+    }
+
+    // 绘制五角星的填充
+    for i in 1..(vertices.len() - 1) {
+        draw_triangle(vertices[0], vertices[i], vertices[i + 1], palette.star);
+    }
+
+    // 绘制五角星的边框
+    for i in 0..vertices.len() {
+        let next = (i + 1) % vertices.len();
+        draw_line(
+            vertices[i].x,
+            vertices[i].y,
+            vertices[next].x,
+            vertices[next].y,
+            2.0,
+            palette.star_outline,
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decorations_never_evict_gameplay_entities() {
+        let mut world = World::with_capacity(4, 8);
+        let cactus = Entity {
+            pos: vec2(3000.0, GROUND_Y - 40.0),
+            vel: Vec2::ZERO,
+            size: vec2(20.0, 40.0),
+            collider: Some(Rect::new(0.0, 0.0, 20.0, 40.0)),
+            sprite: Sprite::Cactus,
+            behaviour: Behaviour::Damaging { pass_score: 10 },
+            lane: 0,
+        };
+        world.spawn(cactus);
+        // 宽屏上一路生成的石子远多于装饰的容量
+        for i in 0..100 {
+            world.spawn(Entity::stone(3000.0 + i as f32, GROUND_Y + 5.0, 2.0, 0));
+        }
+        assert_eq!(world.len(), 1 + 8);
+        assert_eq!(
+            world.iter().filter(|entity| entity.is_damaging()).count(),
+            1
+        );
+
+        // 障碍物移出屏幕时照常计分
+        world.integrate(1.0, 3200.0);
+        assert_eq!(world.despawn_offscreen(0).passed_score, 10);
+        assert_eq!(world.len(), 0);
+    }
+}
@@ -0,0 +1,419 @@
+use std::io::{self, BufRead, Write};
+
+use macroquad::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::defs::EntityDefs;
+use crate::dino::DinoInput;
+use crate::entity::Behaviour;
+use crate::input::ActionState;
+use crate::{GameState, DINO_X, FIXED_DT, GAME_SPEED, GROUND_Y, VIEW_WIDTH};
+
+// 强化学习环境：在无窗口的游戏核心上提供 reset / step 接口，
+// 并通过标准输入输出上的 JSON Lines 协议供外部训练程序（例如 Python）驱动
+
+// 观测向量中恐龙本身的特征数、每个障碍物的特征数（见 bot::Observation::features）和最近星星的特征数
+const DINO_FEATURES: usize = 5;
+const OBSTACLE_FEATURES: usize = 4;
+const STAR_FEATURES: usize = 2;
+// 画面观测覆盖地面以上这么高的区域，足够容纳跳跃的最高点和高飞的鸟
+const FRAME_HEIGHT: f32 = 160.0;
+// 画面观测中各类物体的灰度
+const FRAME_DINO: f32 = 0.25;
+const FRAME_STAR: f32 = 0.5;
+const FRAME_OBSTACLE: f32 = 1.0;
+// 观测配置的上限，防止外部程序让环境分配过大的观测向量
+const MAX_OBSTACLES: usize = 64;
+const MAX_FRAME_SIDE: usize = 1024;
+
+// 离散动作：0 不操作，1 跳跃，2 下蹲
+pub const ACTION_COUNT: u8 = 3;
+
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+pub enum ObservationConfig {
+    // 恐龙状态、前方 obstacles 个障碍物和最近一颗星星，数值大致归一化到 0~1
+    Vector { obstacles: usize },
+    // 把碰撞盒光栅化成 width x height 的灰度画面，按行从上到下排列
+    Framebuffer { width: usize, height: usize },
+}
+
+impl Default for ObservationConfig {
+    fn default() -> Self {
+        ObservationConfig::Vector { obstacles: 3 }
+    }
+}
+
+impl ObservationConfig {
+    // 观测向量的长度；配置超出上限时返回错误
+    pub fn size(&self) -> Result<usize, String> {
+        match *self {
+            ObservationConfig::Vector { obstacles } => {
+                if obstacles > MAX_OBSTACLES {
+                    return Err(format!("obstacles must be at most {}", MAX_OBSTACLES));
+                }
+                obstacles
+                    .checked_mul(OBSTACLE_FEATURES)
+                    .and_then(|size| size.checked_add(DINO_FEATURES + STAR_FEATURES))
+                    .ok_or_else(|| "observation is too large".to_string())
+            }
+            ObservationConfig::Framebuffer { width, height } => {
+                if width == 0 || height == 0 {
+                    return Err("framebuffer width and height must be positive".to_string());
+                }
+                if width > MAX_FRAME_SIDE || height > MAX_FRAME_SIDE {
+                    return Err(format!(
+                        "framebuffer width and height must be at most {}",
+                        MAX_FRAME_SIDE
+                    ));
+                }
+                width
+                    .checked_mul(height)
+                    .ok_or_else(|| "observation is too large".to_string())
+            }
+        }
+    }
+}
+
+// 奖励塑形：每跑过 100 像素、每收集一颗星星、死亡时各得到多少奖励
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RewardConfig {
+    pub distance: f32,
+    pub star: f32,
+    pub death: f32,
+}
+
+impl Default for RewardConfig {
+    fn default() -> Self {
+        Self {
+            distance: 0.1,
+            star: 1.0,
+            death: -10.0,
+        }
+    }
+}
+
+// 一次 step 的结果
+#[derive(Serialize)]
+pub struct Step {
+    pub observation: Vec<f32>,
+    pub reward: f32,
+    pub done: bool,
+    pub info: Info,
+}
+
+#[derive(Serialize)]
+pub struct Info {
+    pub score: u32,
+    pub tick: u64,
+    pub stars: u32,
+}
+
+pub struct Env {
+    game: GameState,
+    defs: EntityDefs,
+    pub observation: ObservationConfig,
+    pub reward: RewardConfig,
+    // 每个动作持续的步数，跳跃只在第一步按下
+    pub frame_skip: u32,
+}
+
+impl Env {
+    pub fn new(defs: EntityDefs) -> Self {
+        let mut env = Self {
+            game: GameState::new(defs.clone()),
+            defs,
+            observation: ObservationConfig::default(),
+            reward: RewardConfig::default(),
+            frame_skip: 1,
+        };
+        env.reset(0);
+        env
+    }
+
+    // 用指定的种子开始新的一局，同一种子和动作序列的结果完全相同
+    pub fn reset(&mut self, seed: u64) -> Vec<f32> {
+        self.game = GameState::new(self.defs.clone());
+        self.game.reseed(seed);
+        self.observe()
+    }
+
+    pub fn done(&self) -> bool {
+        self.game.game_over
+    }
+
+    pub fn step(&mut self, action: u8) -> Step {
+        let mut reward = 0.0;
+        for i in 0..self.frame_skip {
+            if self.game.game_over {
+                break;
+            }
+            let actions = ActionState::from(DinoInput {
+                jump: action == 1 && i == 0,
+                duck: action == 2,
+            });
+            let stars = self.game.stars;
+            self.game.update(&actions);
+
+            reward += (self.game.stars - stars) as f32 * self.reward.star;
+            if self.game.game_over {
+                reward += self.reward.death;
+            } else {
+                reward += GAME_SPEED * FIXED_DT / 100.0 * self.reward.distance;
+            }
+        }
+        Step {
+            observation: self.observe(),
+            reward,
+            done: self.game.game_over,
+            info: Info {
+                score: self.game.score,
+                tick: self.game.ticks,
+                stars: self.game.stars,
+            },
+        }
+    }
+
+    pub fn observe(&self) -> Vec<f32> {
+        match self.observation {
+            ObservationConfig::Vector { obstacles } => self.observe_vector(obstacles),
+            ObservationConfig::Framebuffer { width, height } => {
+                self.observe_framebuffer(width, height)
+            }
+        }
+    }
+
+    fn observe_vector(&self, count: usize) -> Vec<f32> {
+        let mut features = self.game.observe().features(count);
+
+        let star = self
+            .game
+            .world
+            .iter()
+            .filter(|entity| matches!(entity.behaviour, Behaviour::Collectible { .. }))
+            .filter_map(|entity| entity.rect())
+            .filter(|rect| rect.right() >= DINO_X)
+            .min_by(|a, b| a.x.total_cmp(&b.x));
+        match star {
+            Some(rect) => features.extend([
+                (rect.x - DINO_X) / VIEW_WIDTH,
+                (GROUND_Y - rect.bottom()) / 100.0,
+            ]),
+            None => features.extend([1.0, 0.0]),
+        }
+        features
+    }
+
+    fn observe_framebuffer(&self, width: usize, height: usize) -> Vec<f32> {
+        let mut frame = vec![0.0_f32; width * height];
+        let cell_w = VIEW_WIDTH / width as f32;
+        let cell_h = FRAME_HEIGHT / height as f32;
+        let top = GROUND_Y - FRAME_HEIGHT;
+        let mut fill = |rect: Rect, value: f32| {
+            let x0 = ((rect.x / cell_w).floor().max(0.0)) as usize;
+            let x1 = ((rect.right() / cell_w).ceil().max(0.0) as usize).min(width);
+            let y0 = (((rect.y - top) / cell_h).floor().max(0.0)) as usize;
+            let y1 = (((rect.bottom() - top) / cell_h).ceil().max(0.0) as usize).min(height);
+            for y in y0..y1 {
+                for x in x0..x1 {
+                    let cell = &mut frame[y * width + x];
+                    *cell = cell.max(value);
+                }
+            }
+        };
+
+        for entity in self.game.world.iter() {
+            let value = match entity.behaviour {
+                Behaviour::Damaging { .. } => FRAME_OBSTACLE,
+                Behaviour::Collectible { .. } | Behaviour::PowerUp { .. } => FRAME_STAR,
+                Behaviour::Decoration => continue,
+            };
+            if let Some(rect) = entity.rect() {
+                fill(rect, value);
+            }
+        }
+        fill(self.game.dino.rect(), FRAME_DINO);
+        frame
+    }
+}
+
+// 训练程序发来的一行请求
+#[derive(Deserialize)]
+#[serde(tag = "cmd", rename_all = "snake_case", deny_unknown_fields)]
+enum Request {
+    Configure {
+        observation: Option<ObservationConfig>,
+        reward: Option<RewardConfig>,
+        frame_skip: Option<u32>,
+    },
+    Reset {
+        #[serde(default)]
+        seed: u64,
+    },
+    Step {
+        action: u8,
+    },
+    Close,
+}
+
+// 对一行请求的回复
+#[derive(Serialize)]
+#[serde(untagged)]
+enum Response {
+    Configured {
+        observation_size: usize,
+        actions: u8,
+    },
+    Reset {
+        observation: Vec<f32>,
+    },
+    Step(Step),
+    Closed {
+        closed: bool,
+    },
+    Error {
+        error: String,
+    },
+}
+
+impl Env {
+    fn handle(&mut self, request: Request) -> Response {
+        match request {
+            Request::Configure {
+                observation,
+                reward,
+                frame_skip,
+            } => {
+                let observation = observation.unwrap_or(self.observation);
+                let observation_size = match observation.size() {
+                    Ok(size) => size,
+                    Err(error) => return Response::Error { error },
+                };
+                if frame_skip == Some(0) {
+                    return Response::Error {
+                        error: "frame_skip must be at least 1".to_string(),
+                    };
+                }
+                self.observation = observation;
+                self.reward = reward.unwrap_or(self.reward);
+                self.frame_skip = frame_skip.unwrap_or(self.frame_skip);
+                Response::Configured {
+                    observation_size,
+                    actions: ACTION_COUNT,
+                }
+            }
+            Request::Reset { seed } => Response::Reset {
+                observation: self.reset(seed),
+            },
+            Request::Step { action } if action >= ACTION_COUNT => Response::Error {
+                error: format!("action must be below {}", ACTION_COUNT),
+            },
+            Request::Step { .. } if self.done() => Response::Error {
+                error: "episode is over, send reset first".to_string(),
+            },
+            Request::Step { action } => Response::Step(self.step(action)),
+            Request::Close => Response::Closed { closed: true },
+        }
+    }
+}
+
+// --env：从标准输入逐行读取请求，每个请求向标准输出写一行回复，直到 close 或输入结束
+pub fn serve() {
+    let mut env = Env::new(EntityDefs::load());
+    let mut stdout = io::stdout().lock();
+    for line in io::stdin().lock().lines() {
+        let Ok(line) = line else {
+            break;
+        };
+        if line.trim().is_empty() {
+            continue;
+        }
+        let (response, close) = match serde_json::from_str::<Request>(&line) {
+            Ok(request) => {
+                let close = matches!(request, Request::Close);
+                (env.handle(request), close)
+            }
+            Err(err) => (
+                Response::Error {
+                    error: err.to_string(),
+                },
+                false,
+            ),
+        };
+        let written = serde_json::to_writer(&mut stdout, &response)
+            .map_err(io::Error::from)
+            .and_then(|_| writeln!(stdout))
+            .and_then(|_| stdout.flush());
+        if written.is_err() || close {
+            break;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(env: &mut Env, seed: u64, actions: &[u8]) -> Vec<f32> {
+        env.reset(seed);
+        let mut rewards = Vec::new();
+        for &action in actions {
+            let step = env.step(action);
+            assert_eq!(step.observation.len(), env.observation.size().unwrap());
+            rewards.push(step.reward);
+            if step.done {
+                break;
+            }
+        }
+        rewards
+    }
+
+    #[test]
+    fn same_seed_and_actions_give_same_rewards() {
+        let actions: Vec<u8> = (0..600).map(|i| (i % 7 % 3) as u8).collect();
+        let mut env = Env::new(EntityDefs::default());
+        let first = run(&mut env, 7, &actions);
+        let second = run(&mut env, 7, &actions);
+        assert_eq!(first, second);
+    }
+
+    #[test]
+    fn idle_agent_dies_with_death_penalty() {
+        let mut env = Env::new(EntityDefs::default());
+        env.observation = ObservationConfig::Framebuffer {
+            width: 40,
+            height: 8,
+        };
+        let rewards = run(&mut env, 1, &[0; 6000]);
+        assert!(env.done());
+        assert_eq!(*rewards.last().unwrap(), env.reward.death);
+    }
+
+    #[test]
+    fn protocol_rejects_bad_requests() {
+        let mut env = Env::new(EntityDefs::default());
+        for line in [
+            r#"{"cmd": "step", "action": 3}"#,
+            r#"{"cmd": "configure", "frame_skip": 0}"#,
+            r#"{"cmd": "configure", "observation": {"type": "framebuffer", "width": 0, "height": 4}}"#,
+            r#"{"cmd": "configure", "observation": {"type": "framebuffer", "width": 100000, "height": 100000}}"#,
+            r#"{"cmd": "configure", "observation": {"type": "framebuffer", "width": 18446744073709551615, "height": 2}}"#,
+            r#"{"cmd": "configure", "observation": {"type": "vector", "obstacles": 4611686018427387904}}"#,
+        ] {
+            let request = serde_json::from_str(line).unwrap();
+            assert!(
+                matches!(env.handle(request), Response::Error { .. }),
+                "{}",
+                line
+            );
+        }
+        assert!(serde_json::from_str::<Request>(r#"{"cmd": "jump"}"#).is_err());
+        // 被拒绝的配置不会改变当前的观测
+        assert_eq!(env.observation, ObservationConfig::default());
+        assert!(matches!(
+            env.handle(Request::Close),
+            Response::Closed { closed: true }
+        ));
+    }
+}
@@ -0,0 +1,110 @@
+use macroquad::prelude::*;
+
+use crate::scoring::ScoreEvent;
+use crate::theme::Palette;
+
+// 游戏事件：游戏逻辑每一步把发生的事情（起跳、得分、碰撞、复活等）放进队列，
+// 一步结束时按顺序发给所有订阅者。音效、得分飘字等都是订阅者，启动时注册，
+// 增加新的反馈只需实现 Listener，不需要修改游戏逻辑。没有订阅者时（回放、训练、测试）事件直接丢弃
+#[derive(Clone, Debug, PartialEq)]
+pub enum GameEvent {
+    Jumped,
+    // 落地，pos 为恐龙脚下的位置
+    Landed { pos: Vec2 },
+    // 越过障碍物、收集星星和连击中断
+    Score(ScoreEvent),
+    PowerUpCollected { name: &'static str },
+    // 道具到期、被用掉或被清除
+    PowerUpEnded { name: &'static str },
+    // 护盾挡下了一次碰撞
+    HitAbsorbed,
+    // 撞到障碍物，游戏结束，pos 为撞击的位置
+    Hit { pos: Vec2 },
+    Revived,
+    WrongAnswer,
+    // 开始新的一局
+    Reset,
+}
+
+pub trait Listener {
+    fn on_event(&mut self, event: &GameEvent);
+    // 游戏逻辑每推进一步调用一次（暂停时除外，游戏结束后仍然调用），用于随固定步长播放的动画
+    fn step(&mut self, _dt: f32) {}
+    // 在游戏世界中绘制，例如得分飘字
+    fn draw(&self, _palette: &Palette, _font: Option<&Font>) {}
+}
+
+#[derive(Default)]
+pub struct EventBus {
+    queue: Vec<GameEvent>,
+    listeners: Vec<Box<dyn Listener>>,
+}
+
+impl EventBus {
+    pub fn subscribe(&mut self, listener: Box<dyn Listener>) {
+        self.listeners.push(listener);
+    }
+
+    pub fn emit(&mut self, event: GameEvent) {
+        if !self.listeners.is_empty() {
+            self.queue.push(event);
+        }
+    }
+
+    // 把队列中的事件按顺序发给每个订阅者
+    pub fn dispatch(&mut self) {
+        for event in self.queue.drain(..) {
+            for listener in &mut self.listeners {
+                listener.on_event(&event);
+            }
+        }
+    }
+
+    pub fn step(&mut self, dt: f32) {
+        for listener in &mut self.listeners {
+            listener.step(dt);
+        }
+    }
+
+    pub fn draw(&self, palette: &Palette, font: Option<&Font>) {
+        for listener in &self.listeners {
+            listener.draw(palette, font);
+        }
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    use super::*;
+
+    // 记录收到的所有事件，供其他模块的测试检查
+    #[derive(Clone, Default)]
+    pub struct EventLog(pub Rc<RefCell<Vec<GameEvent>>>);
+
+    impl Listener for EventLog {
+        fn on_event(&mut self, event: &GameEvent) {
+            self.0.borrow_mut().push(event.clone());
+        }
+    }
+
+    #[test]
+    fn events_reach_every_listener_in_order() {
+        let mut bus = EventBus::default();
+        // 没有订阅者时不积累事件
+        bus.emit(GameEvent::Jumped);
+        let (first, second) = (EventLog::default(), EventLog::default());
+        bus.subscribe(Box::new(first.clone()));
+        bus.subscribe(Box::new(second.clone()));
+        let landed = GameEvent::Landed { pos: Vec2::ZERO };
+        bus.emit(GameEvent::Jumped);
+        bus.emit(landed.clone());
+        bus.dispatch();
+        bus.dispatch();
+        for log in [first, second] {
+            assert_eq!(*log.0.borrow(), [GameEvent::Jumped, landed.clone()]);
+        }
+    }
+}
@@ -0,0 +1,201 @@
+use std::collections::HashSet;
+
+use macroquad::prelude::*;
+
+use crate::dino::{Dino, DinoInput};
+use crate::entity::Entity;
+use crate::DINO_X;
+
+// 可通过性检查：在固定步长下枚举恐龙的操作（跑、下蹲、在某一步起跳、空中某一步开始加速下落），
+// 判断一组障碍物是否存在一条不会碰撞的操作序列。恐龙使用与游戏完全相同的物理规则，
+// 障碍物四周放大 MARGIN 像素吸收浮点误差，找到的方案在实际游戏中一定可行
+const MARGIN: f32 = 1.0;
+// 空中只在绝对步数为该值整数倍时才允许开始加速下落，控制搜索规模
+const FAST_FALL_STEP: u64 = 4;
+// 最多向前搜索的步数，超过后视为可以通过
+const MAX_HORIZON: usize = 60 * 20;
+
+// 一个需要躲避的障碍物：当前的碰撞盒和相对地面的水平速度
+#[derive(Clone, Copy)]
+pub struct Obstacle {
+    pub rect: Rect,
+    pub vel_x: f32,
+}
+
+impl Obstacle {
+    pub fn from_entity(entity: &Entity) -> Option<Self> {
+        if !entity.is_damaging() {
+            return None;
+        }
+        Some(Self {
+            rect: entity.rect()?,
+            vel_x: entity.vel.x,
+        })
+    }
+
+    // 与 World::integrate 相同的运算顺序
+    pub fn advance(&mut self, dt: f32, speed: f32) {
+        self.rect.x += self.vel_x * dt;
+        self.rect.x -= speed * dt;
+    }
+}
+
+struct Search {
+    dt: f32,
+    // 第 k 步结束时每个障碍物的碰撞盒（已放大 MARGIN）
+    frames: Vec<Vec<Rect>>,
+    start_tick: u64,
+    // 已知无法通过的地面状态：(步数, 是否处于下蹲)
+    dead: HashSet<(usize, bool)>,
+}
+
+impl Search {
+    fn horizon(&self) -> usize {
+        self.frames.len()
+    }
+
+    fn hits(&self, k: usize, dino: &Dino) -> bool {
+        let rect = dino.rect();
+        self.frames[k]
+            .iter()
+            .any(|obstacle| rect.overlaps(obstacle))
+    }
+
+    // 从第 k 步开始、恐龙处于 dino 状态时寻找可行操作，结果按倒序写入 plan
+    fn search(&mut self, k: usize, dino: Dino, plan: &mut Vec<DinoInput>) -> bool {
+        if k >= self.horizon() {
+            return true;
+        }
+        if dino.is_jumping {
+            return self.search_air(k, dino, plan);
+        }
+        if self.dead.contains(&(k, dino.is_ducking)) {
+            return false;
+        }
+
+        // 优先继续奔跑，其次下蹲，最后起跳
+        for input in [
+            DinoInput::default(),
+            DinoInput {
+                jump: false,
+                duck: true,
+            },
+        ] {
+            let mut next = dino;
+            next.step(self.dt, input);
+            if !self.hits(k, &next) && self.search(k + 1, next, plan) {
+                plan.push(input);
+                return true;
+            }
+        }
+        if !dino.is_ducking {
+            let input = DinoInput {
+                jump: true,
+                duck: false,
+            };
+            let mut next = dino;
+            next.step(self.dt, input);
+            if !self.hits(k, &next) && self.search_air(k + 1, next, plan) {
+                plan.push(input);
+                return true;
+            }
+        }
+
+        self.dead.insert((k, dino.is_ducking));
+        false
+    }
+
+    // 空中：在允许的步数开始加速下落（之后一直按住下蹲直到着地），或者一直不加速
+    fn search_air(&mut self, k: usize, dino: Dino, plan: &mut Vec<DinoInput>) -> bool {
+        if !dino.is_jumping {
+            return self.search(k, dino, plan);
+        }
+        let mut fall_starts = vec![None];
+        let mut state = dino;
+        let mut step = k;
+        while state.is_jumping && step < self.horizon() {
+            if step == k || (self.start_tick + step as u64).is_multiple_of(FAST_FALL_STEP) {
+                fall_starts.push(Some(step));
+            }
+            state.step(self.dt, DinoInput::default());
+            step += 1;
+        }
+
+        // 越晚开始加速下落越接近自然落地，先尝试自然落地
+        fall_starts.sort_by_key(|start| std::cmp::Reverse(start.unwrap_or(usize::MAX)));
+        for fall_start in fall_starts {
+            let mut state = dino;
+            let mut inputs = Vec::new();
+            let mut step = k;
+            let mut cleared = true;
+            while state.is_jumping && step < self.horizon() {
+                let input = DinoInput {
+                    jump: false,
+                    duck: fall_start.is_some_and(|start| step >= start),
+                };
+                state.step(self.dt, input);
+                inputs.push(input);
+                if self.hits(step, &state) {
+                    cleared = false;
+                    break;
+                }
+                step += 1;
+            }
+            if cleared && self.search(step, state, plan) {
+                plan.extend(inputs.into_iter().rev());
+                return true;
+            }
+        }
+        false
+    }
+}
+
+// 为恐龙寻找一条躲过所有障碍物的操作序列。speed 为游戏速度，
+// start_tick 为第 0 步对应的绝对步数。找不到时返回 None
+pub fn plan(
+    dino: Dino,
+    obstacles: &[Obstacle],
+    speed: f32,
+    dt: f32,
+    start_tick: u64,
+) -> Option<Vec<DinoInput>> {
+    // 预先计算每一步的障碍物位置，直到所有障碍物都移到恐龙身后
+    let mut moving = obstacles.to_vec();
+    let mut frames = Vec::new();
+    while moving
+        .iter()
+        .any(|obstacle| obstacle.rect.right() + MARGIN >= DINO_X)
+        && frames.len() < MAX_HORIZON
+    {
+        for obstacle in &mut moving {
+            obstacle.advance(dt, speed);
+        }
+        frames.push(
+            moving
+                .iter()
+                .map(|obstacle| {
+                    Rect::new(
+                        obstacle.rect.x - MARGIN,
+                        obstacle.rect.y - MARGIN,
+                        obstacle.rect.w + MARGIN * 2.0,
+                        obstacle.rect.h + MARGIN * 2.0,
+                    )
+                })
+                .collect(),
+        );
+    }
+
+    let mut search = Search {
+        dt,
+        frames,
+        start_tick,
+        dead: HashSet::new(),
+    };
+    let mut plan = Vec::new();
+    if search.search(0, dino, &mut plan) {
+        plan.reverse();
+        Some(plan)
+    } else {
+        None
+    }
+}
@@ -0,0 +1,150 @@
+// 手柄支持：启用 gamepad 特性时通过 gilrs 读取手柄，否则所有查询都返回未按下
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PadButton {
+    South,
+    East,
+    West,
+    North,
+    DPadUp,
+    DPadDown,
+    DPadLeft,
+    DPadRight,
+    Start,
+    Select,
+    LeftTrigger,
+    RightTrigger,
+}
+
+const ALL_BUTTONS: [PadButton; 12] = [
+    PadButton::South,
+    PadButton::East,
+    PadButton::West,
+    PadButton::North,
+    PadButton::DPadUp,
+    PadButton::DPadDown,
+    PadButton::DPadLeft,
+    PadButton::DPadRight,
+    PadButton::Start,
+    PadButton::Select,
+    PadButton::LeftTrigger,
+    PadButton::RightTrigger,
+];
+
+impl PadButton {
+    pub fn name(self) -> String {
+        format!("{:?}", self)
+    }
+
+    pub fn from_name(name: &str) -> Option<PadButton> {
+        ALL_BUTTONS.iter().copied().find(|b| b.name() == name)
+    }
+}
+
+#[cfg(feature = "gamepad")]
+pub struct Gamepads {
+    gilrs: Option<gilrs::Gilrs>,
+    pressed: Vec<PadButton>,
+    down: Vec<PadButton>,
+}
+
+#[cfg(feature = "gamepad")]
+impl Gamepads {
+    pub fn new() -> Self {
+        let gilrs = match gilrs::Gilrs::new() {
+            Ok(gilrs) => Some(gilrs),
+            Err(err) => {
+                println!("Warning: gamepad support unavailable: {}", err);
+                None
+            }
+        };
+        Self {
+            gilrs,
+            pressed: Vec::new(),
+            down: Vec::new(),
+        }
+    }
+
+    pub fn update(&mut self) {
+        self.pressed.clear();
+        let Some(gilrs) = &mut self.gilrs else {
+            return;
+        };
+
+        while let Some(event) = gilrs.next_event() {
+            if let gilrs::EventType::ButtonPressed(button, _) = event.event {
+                if let Some(button) = convert(button) {
+                    self.pressed.push(button);
+                }
+            }
+        }
+
+        self.down.clear();
+        for (_, gamepad) in gilrs.gamepads() {
+            for button in ALL_BUTTONS {
+                if gamepad.is_pressed(to_gilrs(button)) && !self.down.contains(&button) {
+                    self.down.push(button);
+                }
+            }
+        }
+    }
+
+    pub fn is_pressed(&self, button: PadButton) -> bool {
+        self.pressed.contains(&button)
+    }
+
+    pub fn is_down(&self, button: PadButton) -> bool {
+        self.down.contains(&button)
+    }
+
+    pub fn first_pressed(&self) -> Option<PadButton> {
+        self.pressed.first().copied()
+    }
+}
+
+#[cfg(feature = "gamepad")]
+fn to_gilrs(button: PadButton) -> gilrs::Button {
+    use gilrs::Button;
+    match button {
+        PadButton::South => Button::South,
+        PadButton::East => Button::East,
+        PadButton::West => Button::West,
+        PadButton::North => Button::North,
+        PadButton::DPadUp => Button::DPadUp,
+        PadButton::DPadDown => Button::DPadDown,
+        PadButton::DPadLeft => Button::DPadLeft,
+        PadButton::DPadRight => Button::DPadRight,
+        PadButton::Start => Button::Start,
+        PadButton::Select => Button::Select,
+        PadButton::LeftTrigger => Button::LeftTrigger,
+        PadButton::RightTrigger => Button::RightTrigger,
+    }
+}
+
+#[cfg(feature = "gamepad")]
+fn convert(button: gilrs::Button) -> Option<PadButton> {
+    ALL_BUTTONS.iter().copied().find(|b| to_gilrs(*b) == button)
+}
+
+#[cfg(not(feature = "gamepad"))]
+pub struct Gamepads;
+
+#[cfg(not(feature = "gamepad"))]
+impl Gamepads {
+    pub fn new() -> Self {
+        Gamepads
+    }
+
+    pub fn update(&mut self) {}
+
+    pub fn is_pressed(&self, _button: PadButton) -> bool {
+        false
+    }
+
+    pub fn is_down(&self, _button: PadButton) -> bool {
+        false
+    }
+
+    pub fn first_pressed(&self) -> Option<PadButton> {
+        None
+    }
+}
@@ -0,0 +1,483 @@
+use std::fs;
+use std::path::Path;
+
+use macroquad::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::cosmetics::Look;
+use crate::defs::EntityDefs;
+use crate::dino::{Dino, DinoInput};
+use crate::input::{Action, ActionState};
+use crate::theme::Palette;
+use crate::{GameState, MAX_LANES};
+
+// 幽灵赛跑：游戏逻辑是确定性的，同一种子、同样的每步输入和复活时机会得到完全相同的一局，
+// 因此一局录像只需要记录这三项（多车道模式还有换道）。回放时在后台用另一个 GameState 同步推进
+
+// 生成规则或计分规则改变后同样的输入会得到不同的一局，需要增加版本号，旧录像不再回放
+const GHOST_VERSION: u32 = 4;
+// 幽灵恐龙的不透明度
+const GHOST_ALPHA: f32 = 0.35;
+// 录像最多的步数（两小时），分享来的录像可能写着极大的游程，解码前先检查
+const MAX_GHOST_TICKS: u64 = 60 * 60 * 60 * 2;
+
+// 本局的操作记录，inputs[k] 为第 k 步的输入
+#[derive(Default)]
+pub struct Recording {
+    pub inputs: Vec<DinoInput>,
+    // 答对数学题复活时的步数
+    pub revives: Vec<u64>,
+    // 多车道模式中的换道
+    pub lane_changes: Vec<LaneChange>,
+    // 有机器人操作过，不计入个人最佳
+    pub assisted: bool,
+}
+
+// 第 tick 步换到了第 lane 条车道
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct LaneChange {
+    pub tick: u64,
+    pub lane: usize,
+}
+
+// 录像文件，可以在不同档案之间复制分享
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct GhostFile {
+    pub version: u32,
+    pub player: String,
+    #[serde(with = "seed_bits")]
+    pub seed: u64,
+    pub score: u32,
+    pub ticks: u64,
+    // 录制时的视野宽度，新实体在这里生成
+    pub view_width: f32,
+    // 游程编码的输入，例如 "120-1j30d"：120 步不操作、1 步跳跃、30 步下蹲（b 表示跳跃加下蹲）
+    pub inputs: String,
+    pub revives: Vec<u64>,
+    // 车道数，经典模式为 1，文件中省略
+    #[serde(default = "classic_lanes", skip_serializing_if = "is_classic")]
+    pub lanes: usize,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub lane_changes: Vec<LaneChange>,
+    // 赛道由领跑者生成（每日挑战等共用的赛道），自己生成赛道的对局在文件中省略
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub shared_course: bool,
+}
+
+fn classic_lanes() -> usize {
+    1
+}
+
+fn is_classic(lanes: &usize) -> bool {
+    *lanes == 1
+}
+
+// TOML 的整数只到 i64::MAX，种子按位存成 i64，更大的种子写成负数；读取时两种写法都接受
+mod seed_bits {
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Seed {
+        Unsigned(u64),
+        Signed(i64),
+    }
+
+    pub fn serialize<S: Serializer>(seed: &u64, serializer: S) -> Result<S::Ok, S::Error> {
+        (*seed as i64).serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u64, D::Error> {
+        Ok(match Seed::deserialize(deserializer)? {
+            Seed::Unsigned(seed) => seed,
+            Seed::Signed(seed) => seed as u64,
+        })
+    }
+}
+
+impl GhostFile {
+    pub fn new(player: &str, game: &GameState) -> Self {
+        Self {
+            version: GHOST_VERSION,
+            player: player.to_string(),
+            seed: game.seed,
+            score: game.score,
+            ticks: game.ticks,
+            view_width: game.view_width,
+            inputs: encode_inputs(&game.recording.inputs),
+            revives: game.recording.revives.clone(),
+            lanes: game.lanes,
+            lane_changes: game.recording.lane_changes.clone(),
+            shared_course: game.course.is_some(),
+        }
+    }
+
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let text = fs::read_to_string(path).map_err(|err| err.to_string())?;
+        let file: GhostFile = toml::from_str(&text).map_err(|err| err.to_string())?;
+        file.check()?;
+        Ok(file)
+    }
+
+    // 检查版本、长度和输入编码，从文件或网络读到的录像都要先检查
+    pub fn check(&self) -> Result<(), String> {
+        if self.version != GHOST_VERSION {
+            return Err(format!("unsupported ghost version {}", self.version));
+        }
+        if !(1..=MAX_LANES).contains(&self.lanes) {
+            return Err(format!("unsupported lane count {}", self.lanes));
+        }
+        if self.ticks > MAX_GHOST_TICKS {
+            return Err("run is too long".to_string());
+        }
+        if count_inputs(&self.inputs)? != self.ticks {
+            return Err("replay length does not match the run".to_string());
+        }
+        decode_inputs(&self.inputs).map(|_| ())
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        let text = toml::to_string(self).map_err(|err| err.to_string())?;
+        fs::write(path, text).map_err(|err| err.to_string())
+    }
+}
+
+fn input_code(input: DinoInput) -> char {
+    match (input.jump, input.duck) {
+        (false, false) => '-',
+        (true, false) => 'j',
+        (false, true) => 'd',
+        (true, true) => 'b',
+    }
+}
+
+pub fn encode_inputs(inputs: &[DinoInput]) -> String {
+    let mut text = String::new();
+    let mut rest = inputs;
+    while let Some(&first) = rest.first() {
+        let run = rest.iter().take_while(|&&input| input == first).count();
+        text.push_str(&format!("{}{}", run, input_code(first)));
+        rest = &rest[run..];
+    }
+    text
+}
+
+pub fn decode_inputs(text: &str) -> Result<Vec<DinoInput>, String> {
+    let mut inputs = Vec::new();
+    let mut count = String::new();
+    for c in text.chars() {
+        if c.is_ascii_digit() {
+            count.push(c);
+            continue;
+        }
+        let input = match c {
+            '-' => DinoInput::default(),
+            'j' => DinoInput {
+                jump: true,
+                duck: false,
+            },
+            'd' => DinoInput {
+                jump: false,
+                duck: true,
+            },
+            'b' => DinoInput {
+                jump: true,
+                duck: true,
+            },
+            _ => return Err(format!("invalid input code '{}'", c)),
+        };
+        let run: usize = count
+            .parse()
+            .map_err(|_| format!("missing count before '{}'", c))?;
+        inputs.extend(std::iter::repeat_n(input, run));
+        count.clear();
+    }
+    if !count.is_empty() {
+        return Err("inputs end with a dangling count".to_string());
+    }
+    Ok(inputs)
+}
+
+// 不展开游程编码，只统计输入的总步数，用来在解码前拒绝过长的录像
+fn count_inputs(text: &str) -> Result<u64, String> {
+    let mut total = 0u64;
+    for run in text.split(|c: char| !c.is_ascii_digit()) {
+        if let Ok(count) = run.parse::<u64>() {
+            total = total
+                .checked_add(count)
+                .ok_or_else(|| "too many inputs".to_string())?;
+        } else if !run.is_empty() {
+            return Err("too many inputs".to_string());
+        }
+    }
+    Ok(total)
+}
+
+// 正在回放的幽灵
+pub struct Ghost {
+    pub file: GhostFile,
+    inputs: Vec<DinoInput>,
+    game: Box<GameState>,
+    // 下一个要回放的换道
+    next_lane_change: usize,
+    finished: bool,
+}
+
+impl Ghost {
+    pub fn new(file: GhostFile, defs: &EntityDefs) -> Result<Self, String> {
+        let inputs = decode_inputs(&file.inputs)?;
+        let mut game = Box::new(GameState::new(defs.clone()));
+        game.view_width = file.view_width;
+        game.set_lanes(file.lanes);
+        game.reseed(file.seed);
+        if file.shared_course {
+            game.share_course();
+        }
+        Ok(Self {
+            file,
+            inputs,
+            game,
+            next_lane_change: 0,
+            finished: false,
+        })
+    }
+
+    pub fn score(&self) -> u32 {
+        self.game.score
+    }
+
+    pub fn finished(&self) -> bool {
+        self.finished
+    }
+
+    // 录像的结局，只有排行榜服务器验证成绩时用到（src/server.rs），游戏程序中不编译服务器
+    #[allow(dead_code)]
+    pub fn ticks(&self) -> u64 {
+        self.game.ticks
+    }
+
+    #[allow(dead_code)]
+    pub fn game_over(&self) -> bool {
+        self.game.game_over
+    }
+
+    pub fn dino(&self) -> &Dino {
+        &self.game.dino
+    }
+
+    // 推进到与玩家相同的步数
+    pub fn advance_to(&mut self, tick: u64) {
+        while !self.finished && self.game.ticks < tick {
+            self.step();
+        }
+    }
+
+    fn step(&mut self) {
+        if self.game.game_over {
+            if self.file.revives.contains(&self.game.ticks) {
+                self.game.revive();
+            } else {
+                self.finished = true;
+                return;
+            }
+        }
+        let Some(&input) = self.inputs.get(self.game.ticks as usize) else {
+            self.finished = true;
+            return;
+        };
+        let mut actions = ActionState::from(input);
+        let change = self.file.lane_changes.get(self.next_lane_change);
+        if let Some(change) = change.filter(|change| change.tick == self.game.ticks) {
+            let lane = self.game.dino.lane;
+            if change.lane < lane {
+                actions.press(Action::LaneUp);
+            } else if change.lane > lane {
+                actions.press(Action::LaneDown);
+            }
+            self.next_lane_change += 1;
+        }
+        self.game.update(&actions);
+    }
+
+    pub fn draw(&self, palette: &Palette) {
+        draw_faded(self.dino(), palette);
+    }
+}
+
+// 半透明地画出一只恐龙，幽灵和局域网对战中的其他玩家共用
+pub fn draw_faded(dino: &Dino, palette: &Palette) {
+    let faded = |color: Color| Color {
+        a: color.a * GHOST_ALPHA,
+        ..color
+    };
+    dino.draw(
+        &Look::default(),
+        palette,
+        faded(palette.dino),
+        faded(palette.dino_detail),
+        false,
+    );
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use crate::bot::ReflexBot;
+
+    // 每隔 45 步起跳一次的输入：很快就会撞上障碍物，用来录制带有失误和复活的一局
+    pub fn scripted_jumps(tick: u64) -> ActionState {
+        ActionState::from(DinoInput {
+            jump: tick.is_multiple_of(45),
+            duck: false,
+        })
+    }
+
+    #[test]
+    fn inputs_round_trip_through_run_length_encoding() {
+        let jump = DinoInput {
+            jump: true,
+            duck: false,
+        };
+        let duck = DinoInput {
+            jump: false,
+            duck: true,
+        };
+        let mut inputs = vec![DinoInput::default(); 120];
+        inputs.push(jump);
+        inputs.extend([duck; 30]);
+        inputs.push(DinoInput::default());
+        let text = encode_inputs(&inputs);
+        assert_eq!(text, "120-1j30d1-");
+        assert_eq!(decode_inputs(&text).unwrap(), inputs);
+        assert!(decode_inputs("12x").is_err());
+        assert!(decode_inputs("j").is_err());
+        assert!(decode_inputs("3-4").is_err());
+    }
+
+    #[test]
+    fn check_rejects_huge_runs_before_decoding() {
+        let mut game = GameState::new(EntityDefs::default());
+        for _ in 0..10 {
+            game.update(&ActionState::default());
+        }
+        let file = GhostFile::new("tester", &game);
+        assert_eq!(file.check(), Ok(()));
+        let huge = GhostFile {
+            inputs: "99999999999-".to_string(),
+            ..file.clone()
+        };
+        assert!(huge.check().is_err());
+        let huge = GhostFile {
+            ticks: 99999999999,
+            ..huge
+        };
+        assert!(huge.check().is_err());
+        let short = GhostFile {
+            inputs: "9-".to_string(),
+            ..file
+        };
+        assert!(short.check().is_err());
+    }
+
+    #[test]
+    fn ghost_replays_recorded_run_exactly() {
+        // 中途复活一次，覆盖复活的回放
+        let mut game = GameState::new(EntityDefs::default());
+        game.reseed(5);
+        let mut revived = false;
+        while game.ticks < 60 * 90 {
+            game.update(&scripted_jumps(game.ticks));
+            if game.game_over {
+                if revived {
+                    break;
+                }
+                game.revive();
+                revived = true;
+            }
+        }
+        assert!(revived);
+
+        let file = GhostFile::new("tester", &game);
+        let mut ghost = Ghost::new(file, &EntityDefs::default()).unwrap();
+        ghost.advance_to(game.ticks);
+        assert_eq!(ghost.score(), game.score);
+        assert_eq!(ghost.dino(), &game.dino);
+        assert_eq!(ghost.game.game_over, game.game_over);
+    }
+
+    #[test]
+    fn lane_changes_replay_through_the_file() {
+        // 双车道模式中每 5 秒换一次车道，录像经过 TOML 往返后仍能完全重现
+        let mut game = GameState::new(EntityDefs::default());
+        game.set_lanes(2);
+        game.reseed(9);
+        game.bot = Some(Box::new(ReflexBot));
+        while game.ticks < 60 * 60 && !game.game_over {
+            let mut actions = ActionState::default();
+            if game.ticks % 300 == 299 {
+                actions.press(if game.dino.lane == 0 {
+                    Action::LaneDown
+                } else {
+                    Action::LaneUp
+                });
+            }
+            game.update(&actions);
+        }
+        assert!(game.recording.lane_changes.len() >= 2);
+
+        let file = GhostFile::new("tester", &game);
+        let text = toml::to_string(&file).unwrap();
+        let file: GhostFile = toml::from_str(&text).unwrap();
+        assert_eq!(file.lanes, 2);
+        let mut ghost = Ghost::new(file, &EntityDefs::default()).unwrap();
+        ghost.advance_to(game.ticks);
+        assert_eq!(ghost.score(), game.score);
+        assert_eq!(ghost.dino(), &game.dino);
+    }
+
+    #[test]
+    fn shared_course_runs_replay_through_the_file() {
+        // 每日挑战的赛道由领跑者生成，录像记下这一点，回放时同样使用领跑者的赛道
+        let mut game = GameState::new(EntityDefs::default());
+        game.start_daily(crate::daily::tests::challenge(crate::daily::Date {
+            days: 20_000,
+        }));
+        while !game.game_over {
+            game.update(&scripted_jumps(game.ticks));
+        }
+
+        let file = GhostFile::new("tester", &game);
+        let text = toml::to_string(&file).unwrap();
+        let file: GhostFile = toml::from_str(&text).unwrap();
+        assert!(file.shared_course);
+        let mut ghost = Ghost::new(file, &EntityDefs::default()).unwrap();
+        ghost.advance_to(game.ticks);
+        assert_eq!(ghost.score(), game.score);
+        assert_eq!(ghost.dino(), &game.dino);
+        assert!(ghost.game_over());
+    }
+
+    #[test]
+    fn every_seed_survives_toml_and_json() {
+        let mut game = GameState::new(EntityDefs::default());
+        for seed in [0, 42, i64::MAX as u64, i64::MAX as u64 + 1, u64::MAX] {
+            game.reseed(seed);
+            let file = GhostFile::new("tester", &game);
+            let text = toml::to_string(&file).unwrap();
+            assert_eq!(toml::from_str::<GhostFile>(&text).unwrap().seed, seed);
+            let text = serde_json::to_string(&file).unwrap();
+            assert_eq!(serde_json::from_str::<GhostFile>(&text).unwrap().seed, seed);
+        }
+        // 其他程序按无符号整数写出的大种子也能读取
+        let text = serde_json::to_string(&GhostFile::new("tester", &game))
+            .unwrap()
+            .replace("\"seed\":-1,", &format!("\"seed\":{},", u64::MAX));
+        assert!(text.contains(&u64::MAX.to_string()));
+        assert_eq!(
+            serde_json::from_str::<GhostFile>(&text).unwrap().seed,
+            u64::MAX
+        );
+    }
+}
@@ -0,0 +1,593 @@
+use std::collections::{BTreeMap, HashSet};
+
+use macroquad::prelude::*;
+
+use crate::dino::DinoInput;
+use crate::gamepad::{Gamepads, PadButton};
+
+// 游戏中的逻辑动作，按键和手柄按钮都先映射成动作再交给游戏逻辑
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Action {
+    Jump,
+    Duck,
+    Pause,
+    Confirm,
+    Cancel,
+    Restart,
+    Backspace,
+    Autoplay,
+    Daily,
+    Versus,
+    Upload,
+    Achievements,
+    Customize,
+    LaneUp,
+    LaneDown,
+    Digit(u8),
+}
+
+impl Action {
+    pub fn all() -> Vec<Action> {
+        let mut actions = vec![
+            Action::Jump,
+            Action::Duck,
+            Action::Pause,
+            Action::Confirm,
+            Action::Cancel,
+            Action::Restart,
+            Action::Backspace,
+            Action::Autoplay,
+            Action::Daily,
+            Action::Versus,
+            Action::Upload,
+            Action::Achievements,
+            Action::Customize,
+            Action::LaneUp,
+            Action::LaneDown,
+        ];
+        actions.extend((0..10).map(Action::Digit));
+        actions
+    }
+
+    // 设置文件中使用的名字
+    pub fn name(self) -> String {
+        match self {
+            Action::Jump => "jump".to_string(),
+            Action::Duck => "duck".to_string(),
+            Action::Pause => "pause".to_string(),
+            Action::Confirm => "confirm".to_string(),
+            Action::Cancel => "cancel".to_string(),
+            Action::Restart => "restart".to_string(),
+            Action::Backspace => "backspace".to_string(),
+            Action::Autoplay => "autoplay".to_string(),
+            Action::Daily => "daily".to_string(),
+            Action::Versus => "versus".to_string(),
+            Action::Upload => "upload".to_string(),
+            Action::Achievements => "achievements".to_string(),
+            Action::Customize => "customize".to_string(),
+            Action::LaneUp => "lane_up".to_string(),
+            Action::LaneDown => "lane_down".to_string(),
+            Action::Digit(d) => format!("digit_{}", d),
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Action> {
+        Action::all()
+            .into_iter()
+            .find(|action| action.name() == name)
+    }
+
+    // 设置菜单中显示的名字
+    pub fn label(self, chinese: bool) -> String {
+        let (zh, en) = match self {
+            Action::Jump => ("跳跃", "Jump"),
+            Action::Duck => ("下蹲", "Duck"),
+            Action::Pause => ("暂停", "Pause"),
+            Action::Confirm => ("确认", "Confirm"),
+            Action::Cancel => ("取消", "Cancel"),
+            Action::Restart => ("重新开始", "Restart"),
+            Action::Backspace => ("退格", "Backspace"),
+            Action::Autoplay => ("自动游戏", "Autoplay"),
+            Action::Daily => ("每日挑战", "Daily challenge"),
+            Action::Versus => ("双人赛跑", "Two-player race"),
+            Action::Upload => ("上传成绩", "Submit score"),
+            Action::Achievements => ("成就", "Achievements"),
+            Action::Customize => ("外观", "Customize"),
+            Action::LaneUp => ("换到上方车道", "Lane up"),
+            Action::LaneDown => ("换到下方车道", "Lane down"),
+            Action::Digit(d) => {
+                return if chinese {
+                    format!("数字 {}", d)
+                } else {
+                    format!("Digit {}", d)
+                };
+            }
+        };
+        if chinese { zh } else { en }.to_string()
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Binding {
+    Key(KeyCode),
+    Pad(PadButton),
+}
+
+// ESC 在重新绑定时用于取消，不能被捕获，但默认绑定和设置文件中仍然可以使用
+const RESERVED_KEYS: &[KeyCode] = &[KeyCode::Escape];
+
+// 可以绑定的键盘按键
+const BINDABLE_KEYS: &[KeyCode] = &[
+    KeyCode::Space,
+    KeyCode::Apostrophe,
+    KeyCode::Comma,
+    KeyCode::Minus,
+    KeyCode::Period,
+    KeyCode::Slash,
+    KeyCode::Key0,
+    KeyCode::Key1,
+    KeyCode::Key2,
+    KeyCode::Key3,
+    KeyCode::Key4,
+    KeyCode::Key5,
+    KeyCode::Key6,
+    KeyCode::Key7,
+    KeyCode::Key8,
+    KeyCode::Key9,
+    KeyCode::Semicolon,
+    KeyCode::Equal,
+    KeyCode::A,
+    KeyCode::B,
+    KeyCode::C,
+    KeyCode::D,
+    KeyCode::E,
+    KeyCode::F,
+    KeyCode::G,
+    KeyCode::H,
+    KeyCode::I,
+    KeyCode::J,
+    KeyCode::K,
+    KeyCode::L,
+    KeyCode::M,
+    KeyCode::N,
+    KeyCode::O,
+    KeyCode::P,
+    KeyCode::Q,
+    KeyCode::R,
+    KeyCode::S,
+    KeyCode::T,
+    KeyCode::U,
+    KeyCode::V,
+    KeyCode::W,
+    KeyCode::X,
+    KeyCode::Y,
+    KeyCode::Z,
+    KeyCode::LeftBracket,
+    KeyCode::Backslash,
+    KeyCode::RightBracket,
+    KeyCode::GraveAccent,
+    KeyCode::Enter,
+    KeyCode::Backspace,
+    KeyCode::Insert,
+    KeyCode::Delete,
+    KeyCode::Right,
+    KeyCode::Left,
+    KeyCode::Down,
+    KeyCode::Up,
+    KeyCode::PageUp,
+    KeyCode::PageDown,
+    KeyCode::Home,
+    KeyCode::End,
+    KeyCode::F1,
+    KeyCode::F2,
+    KeyCode::F3,
+    KeyCode::F4,
+    KeyCode::F5,
+    KeyCode::F6,
+    KeyCode::F7,
+    KeyCode::F8,
+    KeyCode::F9,
+    KeyCode::F10,
+    KeyCode::F11,
+    KeyCode::F12,
+    KeyCode::Kp0,
+    KeyCode::Kp1,
+    KeyCode::Kp2,
+    KeyCode::Kp3,
+    KeyCode::Kp4,
+    KeyCode::Kp5,
+    KeyCode::Kp6,
+    KeyCode::Kp7,
+    KeyCode::Kp8,
+    KeyCode::Kp9,
+    KeyCode::KpEnter,
+    KeyCode::LeftShift,
+    KeyCode::RightShift,
+];
+
+const TOP_ROW_DIGITS: [KeyCode; 10] = [
+    KeyCode::Key0,
+    KeyCode::Key1,
+    KeyCode::Key2,
+    KeyCode::Key3,
+    KeyCode::Key4,
+    KeyCode::Key5,
+    KeyCode::Key6,
+    KeyCode::Key7,
+    KeyCode::Key8,
+    KeyCode::Key9,
+];
+
+const NUMPAD_DIGITS: [KeyCode; 10] = [
+    KeyCode::Kp0,
+    KeyCode::Kp1,
+    KeyCode::Kp2,
+    KeyCode::Kp3,
+    KeyCode::Kp4,
+    KeyCode::Kp5,
+    KeyCode::Kp6,
+    KeyCode::Kp7,
+    KeyCode::Kp8,
+    KeyCode::Kp9,
+];
+
+impl Binding {
+    // 键盘按键直接使用 KeyCode 的名字，手柄按钮加上 "Pad." 前缀
+    pub fn name(self) -> String {
+        match self {
+            Binding::Key(key) => format!("{:?}", key),
+            Binding::Pad(button) => format!("Pad.{}", button.name()),
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Binding> {
+        if let Some(button) = name.strip_prefix("Pad.") {
+            return PadButton::from_name(button).map(Binding::Pad);
+        }
+        BINDABLE_KEYS
+            .iter()
+            .chain(RESERVED_KEYS)
+            .find(|key| format!("{:?}", key) == name)
+            .map(|key| Binding::Key(*key))
+    }
+}
+
+#[derive(Clone)]
+pub struct Bindings {
+    map: BTreeMap<Action, Vec<Binding>>,
+}
+
+impl Bindings {
+    pub fn defaults() -> Self {
+        let mut map = BTreeMap::new();
+        map.insert(
+            Action::Jump,
+            vec![
+                Binding::Key(KeyCode::Space),
+                Binding::Key(KeyCode::Up),
+                Binding::Key(KeyCode::W),
+                Binding::Pad(PadButton::South),
+                Binding::Pad(PadButton::DPadUp),
+            ],
+        );
+        map.insert(
+            Action::Duck,
+            vec![
+                Binding::Key(KeyCode::Down),
+                Binding::Key(KeyCode::S),
+                Binding::Pad(PadButton::DPadDown),
+            ],
+        );
+        map.insert(
+            Action::Pause,
+            vec![Binding::Key(KeyCode::P), Binding::Pad(PadButton::Start)],
+        );
+        map.insert(
+            Action::Confirm,
+            vec![
+                Binding::Key(KeyCode::Enter),
+                Binding::Key(KeyCode::KpEnter),
+                Binding::Pad(PadButton::South),
+            ],
+        );
+        map.insert(
+            Action::Cancel,
+            vec![Binding::Key(KeyCode::Escape), Binding::Pad(PadButton::East)],
+        );
+        map.insert(
+            Action::Restart,
+            vec![Binding::Key(KeyCode::R), Binding::Pad(PadButton::Select)],
+        );
+        map.insert(
+            Action::Backspace,
+            vec![
+                Binding::Key(KeyCode::Backspace),
+                Binding::Pad(PadButton::West),
+            ],
+        );
+        map.insert(
+            Action::Autoplay,
+            vec![Binding::Key(KeyCode::A), Binding::Pad(PadButton::North)],
+        );
+        map.insert(Action::Daily, vec![Binding::Key(KeyCode::D)]);
+        map.insert(Action::Versus, vec![Binding::Key(KeyCode::V)]);
+        map.insert(Action::Upload, vec![Binding::Key(KeyCode::U)]);
+        map.insert(Action::Achievements, vec![Binding::Key(KeyCode::H)]);
+        map.insert(Action::Customize, vec![Binding::Key(KeyCode::C)]);
+        map.insert(
+            Action::LaneUp,
+            vec![
+                Binding::Key(KeyCode::Up),
+                Binding::Pad(PadButton::LeftTrigger),
+            ],
+        );
+        map.insert(
+            Action::LaneDown,
+            vec![
+                Binding::Key(KeyCode::Down),
+                Binding::Pad(PadButton::RightTrigger),
+            ],
+        );
+        for d in 0..10 {
+            map.insert(
+                Action::Digit(d),
+                vec![
+                    Binding::Key(TOP_ROW_DIGITS[d as usize]),
+                    Binding::Key(NUMPAD_DIGITS[d as usize]),
+                ],
+            );
+        }
+        Self { map }
+    }
+
+    // 双人赛跑时每名玩家各自的按键：玩家 1 用键盘左侧和主键盘数字，玩家 2 用方向键和小键盘
+    pub fn versus(player: usize) -> Self {
+        let (jump, duck, digits, confirm, backspace, cancel) = if player == 0 {
+            (
+                vec![KeyCode::W],
+                vec![KeyCode::S],
+                TOP_ROW_DIGITS,
+                vec![KeyCode::E],
+                vec![KeyCode::Q],
+                vec![KeyCode::X],
+            )
+        } else {
+            (
+                vec![KeyCode::Up],
+                vec![KeyCode::Down],
+                NUMPAD_DIGITS,
+                vec![KeyCode::KpEnter, KeyCode::Enter],
+                vec![KeyCode::Backspace],
+                vec![KeyCode::RightShift],
+            )
+        };
+        let keys = |list: Vec<KeyCode>| list.into_iter().map(Binding::Key).collect();
+        let mut map = BTreeMap::new();
+        map.insert(Action::Jump, keys(jump));
+        map.insert(Action::Duck, keys(duck));
+        map.insert(Action::Confirm, keys(confirm));
+        map.insert(Action::Backspace, keys(backspace));
+        map.insert(Action::Cancel, keys(cancel));
+        for d in 0..10 {
+            map.insert(Action::Digit(d), vec![Binding::Key(digits[d as usize])]);
+        }
+        Self { map }
+    }
+
+    // 多车道模式使用的绑定：换道的按键（默认 ↑ ↓）不再同时触发跳跃和下蹲等其他动作
+    pub fn for_lanes(&self) -> Self {
+        let lane_keys: Vec<Binding> = [Action::LaneUp, Action::LaneDown]
+            .iter()
+            .flat_map(|action| self.get(*action).to_vec())
+            .collect();
+        let mut map = self.map.clone();
+        for (action, list) in &mut map {
+            if !matches!(action, Action::LaneUp | Action::LaneDown) {
+                list.retain(|binding| !lane_keys.contains(binding));
+            }
+        }
+        Self { map }
+    }
+
+    // 从设置文件读取绑定，文件里没有的动作使用默认绑定
+    pub fn from_config(config: &BTreeMap<String, Vec<String>>) -> Self {
+        let mut bindings = Self::defaults();
+        for (action_name, binding_names) in config {
+            let Some(action) = Action::from_name(action_name) else {
+                println!("Warning: unknown action '{}' in settings", action_name);
+                continue;
+            };
+            let mut list = Vec::new();
+            for binding_name in binding_names {
+                match Binding::from_name(binding_name) {
+                    Some(binding) => list.push(binding),
+                    None => println!(
+                        "Warning: unknown key '{}' bound to '{}' in settings",
+                        binding_name, action_name
+                    ),
+                }
+            }
+            bindings.map.insert(action, list);
+        }
+        bindings
+    }
+
+    pub fn to_config(&self) -> BTreeMap<String, Vec<String>> {
+        self.map
+            .iter()
+            .map(|(action, list)| {
+                (
+                    action.name(),
+                    list.iter().map(|binding| binding.name()).collect(),
+                )
+            })
+            .collect()
+    }
+
+    pub fn get(&self, action: Action) -> &[Binding] {
+        self.map.get(&action).map(Vec::as_slice).unwrap_or(&[])
+    }
+
+    pub fn add(&mut self, action: Action, binding: Binding) {
+        let list = self.map.entry(action).or_default();
+        if !list.contains(&binding) {
+            list.push(binding);
+        }
+    }
+
+    pub fn clear(&mut self, action: Action) {
+        self.map.insert(action, Vec::new());
+    }
+}
+
+// 一帧内的动作状态：pressed 表示本帧刚按下，down 表示按住
+#[derive(Clone, Default)]
+pub struct ActionState {
+    pressed: HashSet<Action>,
+    down: HashSet<Action>,
+}
+
+impl ActionState {
+    pub fn pressed(&self, action: Action) -> bool {
+        self.pressed.contains(&action)
+    }
+
+    pub fn down(&self, action: Action) -> bool {
+        self.down.contains(&action)
+    }
+
+    // 供鼠标/触摸等非按键输入源注入动作
+    pub fn press(&mut self, action: Action) {
+        self.pressed.insert(action);
+        self.down.insert(action);
+    }
+
+    pub fn hold(&mut self, action: Action) {
+        self.down.insert(action);
+    }
+
+    // 合并另一组状态中的按下事件，用于把上一帧未处理的按键带到这一帧
+    pub fn merge_pressed(&mut self, other: &ActionState) {
+        self.pressed.extend(other.pressed.iter().copied());
+    }
+
+    pub fn clear_pressed(&mut self) {
+        self.pressed.clear();
+    }
+
+    // 本帧按下的数字，按从小到大的顺序
+    pub fn digits_pressed(&self) -> impl Iterator<Item = char> + '_ {
+        (0..10u8)
+            .filter(|d| self.pressed(Action::Digit(*d)))
+            .map(|d| (b'0' + d) as char)
+    }
+}
+
+// 机器人、回放等程序化输入：跳跃视为本步按下，下蹲视为按住
+impl From<DinoInput> for ActionState {
+    fn from(input: DinoInput) -> Self {
+        let mut actions = ActionState::default();
+        if input.jump {
+            actions.press(Action::Jump);
+        }
+        if input.duck {
+            actions.hold(Action::Duck);
+        }
+        actions
+    }
+}
+
+pub struct Controls {
+    pub bindings: Bindings,
+    gamepads: Gamepads,
+}
+
+impl Controls {
+    pub fn new(bindings: Bindings) -> Self {
+        Self {
+            bindings,
+            gamepads: Gamepads::new(),
+        }
+    }
+
+    // 每帧调用一次，读取手柄事件
+    pub fn update(&mut self) {
+        self.gamepads.update();
+    }
+
+    pub fn actions(&self) -> ActionState {
+        self.actions_for(&self.bindings)
+    }
+
+    // 按另一组绑定读取动作，用于双人赛跑中每名玩家各自的按键
+    pub fn actions_for(&self, bindings: &Bindings) -> ActionState {
+        let mut state = ActionState::default();
+        for (action, list) in &bindings.map {
+            for binding in list {
+                let (pressed, down) = match binding {
+                    Binding::Key(key) => (is_key_pressed(*key), is_key_down(*key)),
+                    Binding::Pad(button) => (
+                        self.gamepads.is_pressed(*button),
+                        self.gamepads.is_down(*button),
+                    ),
+                };
+                if pressed {
+                    state.pressed.insert(*action);
+                }
+                if down {
+                    state.down.insert(*action);
+                }
+            }
+        }
+        state
+    }
+
+    // 重新绑定时捕获本帧按下的按键或手柄按钮
+    pub fn captured_binding(&self) -> Option<Binding> {
+        if let Some(key) = get_last_key_pressed() {
+            if BINDABLE_KEYS.contains(&key) {
+                return Some(Binding::Key(key));
+            }
+        }
+        self.gamepads.first_pressed().map(Binding::Pad)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(entries: &[(&str, &[&str])]) -> BTreeMap<String, Vec<String>> {
+        entries
+            .iter()
+            .map(|(action, names)| {
+                let names = names.iter().map(|name| name.to_string()).collect();
+                (action.to_string(), names)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn bindings_round_trip_and_skip_unknown_names() {
+        let defaults = Bindings::defaults();
+        let saved = defaults.to_config();
+        assert_eq!(Bindings::from_config(&saved).to_config(), saved);
+        for binding in Action::all().iter().flat_map(|a| defaults.get(*a)) {
+            assert_eq!(Binding::from_name(&binding.name()), Some(*binding));
+        }
+
+        // 未知的按键和动作被忽略，文件里没有的动作保留默认绑定；ESC 不能捕获但仍能从文件读取
+        let bindings = Bindings::from_config(&config(&[
+            ("jump", &["K", "Hyperspace", "Pad.Nowhere"]),
+            ("cancel", &["Escape"]),
+            ("teleport", &["T"]),
+        ]));
+        assert_eq!(bindings.get(Action::Jump), [Binding::Key(KeyCode::K)]);
+        assert_eq!(
+            bindings.get(Action::Cancel),
+            [Binding::Key(KeyCode::Escape)]
+        );
+        assert_eq!(bindings.get(Action::Duck), defaults.get(Action::Duck));
+        assert!(!BINDABLE_KEYS.contains(&KeyCode::Escape));
+    }
+}
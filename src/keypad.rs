@@ -0,0 +1,143 @@
+use macroquad::prelude::*;
+
+use crate::input::Action;
+use crate::theme::Palette;
+use crate::ui::{draw_label, label_width};
+
+// 复活界面右侧的屏幕数字键盘，鼠标点击和触摸都可以使用
+pub const PANEL_WIDTH: f32 = 240.0;
+const QUIZ_WIDTH: f32 = 400.0;
+const BUTTON_W: f32 = 60.0;
+const BUTTON_H: f32 = 44.0;
+const GAP: f32 = 8.0;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum KeypadKey {
+    Digit(u8),
+    Backspace,
+    Submit,
+    Cancel,
+}
+
+impl KeypadKey {
+    pub fn action(self) -> Action {
+        match self {
+            KeypadKey::Digit(d) => Action::Digit(d),
+            KeypadKey::Backspace => Action::Backspace,
+            KeypadKey::Submit => Action::Confirm,
+            KeypadKey::Cancel => Action::Cancel,
+        }
+    }
+
+    fn label(self, chinese: bool) -> String {
+        match self {
+            KeypadKey::Digit(d) => d.to_string(),
+            KeypadKey::Backspace => if chinese { "退格" } else { "<-" }.to_string(),
+            KeypadKey::Submit => if chinese { "确定" } else { "OK" }.to_string(),
+            KeypadKey::Cancel => if chinese { "放弃复活" } else { "Cancel" }.to_string(),
+        }
+    }
+}
+
+// 按钮布局：1-9 三行，第四行为 退格 0 确定，最后一行为取消；screen 为屏幕大小
+fn layout(screen: Vec2) -> Vec<(KeypadKey, Rect)> {
+    let grid_w = BUTTON_W * 3.0 + GAP * 2.0;
+    let panel_left = screen.x / 2.0 + (QUIZ_WIDTH + PANEL_WIDTH) / 2.0 - PANEL_WIDTH;
+    let x0 = panel_left + (PANEL_WIDTH - grid_w) / 2.0;
+    let y0 = screen.y / 2.0 - 130.0;
+
+    let cell = |col: usize, row: usize| {
+        Rect::new(
+            x0 + col as f32 * (BUTTON_W + GAP),
+            y0 + row as f32 * (BUTTON_H + GAP),
+            BUTTON_W,
+            BUTTON_H,
+        )
+    };
+
+    let mut keys = Vec::new();
+    for d in 1..10u8 {
+        let index = (d - 1) as usize;
+        keys.push((KeypadKey::Digit(d), cell(index % 3, index / 3)));
+    }
+    keys.push((KeypadKey::Backspace, cell(0, 3)));
+    keys.push((KeypadKey::Digit(0), cell(1, 3)));
+    keys.push((KeypadKey::Submit, cell(2, 3)));
+    keys.push((
+        KeypadKey::Cancel,
+        Rect::new(x0, y0 + 4.0 * (BUTTON_H + GAP), grid_w, BUTTON_H),
+    ));
+    keys
+}
+
+pub fn hit(pos: Vec2, screen: Vec2) -> Option<KeypadKey> {
+    layout(screen)
+        .into_iter()
+        .find(|(_, rect)| rect.contains(pos))
+        .map(|(key, _)| key)
+}
+
+pub fn draw(font: Option<&Font>, palette: &Palette) {
+    let screen = vec2(screen_width(), screen_height());
+    let hovered = hit(Vec2::from(mouse_position()), screen);
+
+    for (key, rect) in layout(screen) {
+        let fill = if Some(key) == hovered {
+            palette.highlight
+        } else {
+            palette.button
+        };
+        draw_rectangle(rect.x, rect.y, rect.w, rect.h, fill);
+        draw_rectangle_lines(rect.x, rect.y, rect.w, rect.h, 2.0, palette.text_secondary);
+
+        let text = key.label(font.is_some());
+        let size = if matches!(key, KeypadKey::Digit(_)) {
+            30
+        } else {
+            20
+        };
+        let color = match key {
+            KeypadKey::Submit => palette.success,
+            KeypadKey::Cancel => palette.danger,
+            _ => palette.text,
+        };
+        draw_label(
+            font,
+            &text,
+            rect.x + rect.w / 2.0 - label_width(font, &text, size) / 2.0,
+            rect.y + rect.h / 2.0 + size as f32 / 3.0,
+            size,
+            color,
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn buttons_are_hit_at_their_centers_and_nowhere_else() {
+        let screen = vec2(1200.0, 700.0);
+        let keys = layout(screen);
+        assert_eq!(keys.len(), 13);
+        for (key, rect) in &keys {
+            assert_eq!(hit(rect.center(), screen), Some(*key));
+        }
+        // 按钮之间的空隙和题目区域都不算点中
+        let (_, one) = keys[0];
+        assert_eq!(
+            hit(vec2(one.right() + GAP / 2.0, one.center().y), screen),
+            None
+        );
+        assert_eq!(hit(screen / 2.0 - vec2(PANEL_WIDTH, 0.0), screen), None);
+
+        // 数字按小键盘的顺序排列：1 在左上，0 在退格和确定之间
+        let center = |key| keys.iter().find(|(k, _)| *k == key).unwrap().1.center();
+        assert!(center(KeypadKey::Digit(1)).y < center(KeypadKey::Digit(7)).y);
+        assert!(center(KeypadKey::Digit(1)).x < center(KeypadKey::Digit(3)).x);
+        assert!(center(KeypadKey::Backspace).x < center(KeypadKey::Digit(0)).x);
+        assert!(center(KeypadKey::Digit(0)).x < center(KeypadKey::Submit).x);
+        assert!(center(KeypadKey::Cancel).y > center(KeypadKey::Digit(0)).y);
+    }
+}
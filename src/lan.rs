@@ -0,0 +1,325 @@
+use macroquad::prelude::*;
+
+use crate::ghost::draw_faded;
+use crate::input::{Action, ActionState};
+use crate::net::{Session, Snapshot, SNAPSHOT_INTERVAL};
+use crate::theme::Palette;
+use crate::ui::{draw_label, label_width};
+use crate::GameState;
+
+// 局域网对战的游戏部分：大厅中由主机开始比赛，比赛中定期发送自己的状态快照，
+// 其他玩家的恐龙画成半透明的幽灵，右上角显示所有人的实时分数
+
+pub struct LanRace {
+    session: Session,
+    // 已经开始过比赛；false 表示还在大厅中等待主机开始
+    racing: bool,
+    // 最近一次发送的快照
+    last_sent: Option<Snapshot>,
+}
+
+// 一名玩家在记分板上的一行
+struct Standing {
+    name: String,
+    score: u32,
+    game_over: bool,
+    finished: bool,
+    local: bool,
+}
+
+impl LanRace {
+    pub fn new(session: Session) -> Self {
+        Self {
+            session,
+            racing: false,
+            last_sent: None,
+        }
+    }
+
+    // 每帧在推进游戏之前调用：处理网络消息和开始、离开的按键，返回 false 表示离开对战
+    pub fn update(&mut self, game: &mut GameState, actions: &mut ActionState) -> bool {
+        self.session.poll();
+        if let Some(error) = &self.session.error {
+            println!("Left the LAN race: {}", error);
+            return false;
+        }
+        if actions.pressed(Action::Cancel) && !game.showing_math_question {
+            return false;
+        }
+
+        // 主机在大厅中按跳跃或确认开始比赛，所有人都结束后按 R 或确认开始下一局
+        if self.session.host {
+            let start = if self.racing {
+                self.race_over(game)
+                    && (actions.pressed(Action::Restart) || actions.pressed(Action::Confirm))
+            } else {
+                actions.pressed(Action::Jump) || actions.pressed(Action::Confirm)
+            };
+            if start {
+                self.session.start_race(screen_width());
+            }
+        }
+        if let Some(start) = self.session.take_start() {
+            game.start_game();
+            game.view_width = start.view_width;
+            // 所有人跑领跑者按主机的种子和视野宽度生成的同一条赛道
+            game.reseed(start.seed);
+            game.share_course();
+            self.racing = true;
+            self.last_sent = None;
+            actions.clear_pressed();
+        }
+        true
+    }
+
+    // 每帧在推进游戏之后调用，每隔几步或状态变化时发送快照
+    pub fn send_state(&mut self, game: &GameState) {
+        let Some(id) = self.session.id.filter(|_| self.racing) else {
+            return;
+        };
+        let snapshot = Snapshot {
+            id,
+            race: self.session.race(),
+            tick: game.ticks,
+            dino: game.dino,
+            score: game.score,
+            game_over: game.game_over,
+            finished: finished(game),
+        };
+        let due = self.last_sent.as_ref().is_none_or(|last| {
+            snapshot.tick >= last.tick + SNAPSHOT_INTERVAL
+                || (last.game_over, last.finished) != (snapshot.game_over, snapshot.finished)
+        });
+        if due {
+            self.session.send_snapshot(snapshot.clone());
+            self.last_sent = Some(snapshot);
+        }
+    }
+
+    fn race_over(&self, game: &GameState) -> bool {
+        self.racing && finished(game) && self.session.others_finished()
+    }
+
+    // 自己和本局中其他玩家，按分数从高到低排列
+    fn standings(&self, game: &GameState) -> Vec<Standing> {
+        let mut standings = vec![Standing {
+            name: self.session.name.clone(),
+            score: game.score,
+            game_over: game.game_over,
+            finished: finished(game),
+            local: true,
+        }];
+        standings.extend(self.session.peers.values().filter_map(|peer| {
+            let snapshot = peer.snapshot.as_ref()?;
+            Some(Standing {
+                name: peer.name.clone(),
+                score: snapshot.score,
+                game_over: snapshot.game_over,
+                finished: snapshot.finished,
+                local: false,
+            })
+        }));
+        standings.sort_by_key(|standing| std::cmp::Reverse(standing.score));
+        standings
+    }
+
+    // 在游戏画面之上绘制：大厅、其他玩家的幽灵恐龙、记分板和结果
+    pub fn draw(&self, game: &GameState) {
+        let palette = game.current_palette();
+        let font = game.font.as_ref();
+        if !self.racing {
+            self.draw_lobby(&palette, font);
+            return;
+        }
+
+        // 答题界面打开时不画幽灵，避免盖住题目
+        if !game.showing_math_question {
+            for peer in self.session.peers.values() {
+                if let Some(snapshot) = &peer.snapshot {
+                    draw_faded(&snapshot.dino, &palette);
+                }
+            }
+        }
+
+        let chinese = font.is_some();
+        let standings = self.standings(game);
+        let right = screen_width() - 20.0;
+        for (row, standing) in standings.iter().enumerate() {
+            let status = match (standing.finished, standing.game_over, chinese) {
+                (true, _, true) => " 结束",
+                (true, _, false) => " out",
+                (false, true, true) => " 答题中",
+                (false, true, false) => " reviving",
+                _ => "",
+            };
+            let line = format!(
+                "{}. {} {}{}",
+                row + 1,
+                standing.name,
+                standing.score,
+                status
+            );
+            let color = if standing.local {
+                palette.input
+            } else {
+                palette.text_secondary
+            };
+            draw_label(
+                font,
+                &line,
+                right - label_width(font, &line, 20),
+                30.0 + row as f32 * 24.0,
+                20,
+                color,
+            );
+        }
+
+        if self.race_over(game) {
+            self.draw_results(&standings, &palette, font);
+        } else if finished(game) {
+            let waiting_text = if chinese {
+                "已结束，等待其他玩家…  ESC 离开"
+            } else {
+                "Finished - waiting for the others...  ESC to leave"
+            };
+            draw_label(
+                font,
+                waiting_text,
+                screen_width() / 2.0 - label_width(font, waiting_text, 24) / 2.0,
+                screen_height() / 2.0,
+                24,
+                palette.danger,
+            );
+        }
+    }
+
+    fn draw_lobby(&self, palette: &Palette, font: Option<&Font>) {
+        let chinese = font.is_some();
+        let (center_x, center_y) = (screen_width() / 2.0, screen_height() / 2.0);
+        let rows = self.session.peers.len() + 1;
+        let height = 130.0 + rows as f32 * 26.0;
+        let top = center_y - height / 2.0;
+        draw_rectangle(center_x - 220.0, top, 440.0, height, palette.panel);
+        draw_rectangle_lines(
+            center_x - 220.0,
+            top,
+            440.0,
+            height,
+            3.0,
+            palette.panel_border,
+        );
+
+        let title = match (self.session.host, chinese) {
+            (true, true) => format!("局域网大厅  主机 {}", self.session.address()),
+            (true, false) => format!("LAN lobby  hosting on {}", self.session.address()),
+            (false, true) => format!("局域网大厅  已连接 {}", self.session.address()),
+            (false, false) => format!("LAN lobby  joined {}", self.session.address()),
+        };
+        draw_label(
+            font,
+            &title,
+            center_x - label_width(font, &title, 24) / 2.0,
+            top + 40.0,
+            24,
+            palette.title,
+        );
+
+        let you = if chinese { "（你）" } else { " (you)" };
+        let mut names = vec![format!("{}{}", self.session.name, you)];
+        names.extend(self.session.peers.values().map(|peer| peer.name.clone()));
+        for (row, name) in names.iter().enumerate() {
+            draw_label(
+                font,
+                name,
+                center_x - 180.0,
+                top + 75.0 + row as f32 * 26.0,
+                22,
+                palette.text,
+            );
+        }
+
+        let hint = match (self.session.host, chinese) {
+            (true, true) => "按空格键或回车开始比赛，ESC 离开",
+            (true, false) => "SPACE or ENTER to start the race, ESC to leave",
+            (false, true) => "等待主机开始比赛，ESC 离开",
+            (false, false) => "Waiting for the host to start, ESC to leave",
+        };
+        draw_label(
+            font,
+            hint,
+            center_x - label_width(font, hint, 20) / 2.0,
+            top + height - 20.0,
+            20,
+            palette.text_secondary,
+        );
+    }
+
+    fn draw_results(&self, standings: &[Standing], palette: &Palette, font: Option<&Font>) {
+        let chinese = font.is_some();
+        let (center_x, center_y) = (screen_width() / 2.0, screen_height() / 2.0);
+        let height = 110.0 + standings.len() as f32 * 28.0;
+        let top = center_y - height / 2.0;
+        draw_rectangle(center_x - 200.0, top, 400.0, height, palette.panel);
+        draw_rectangle_lines(
+            center_x - 200.0,
+            top,
+            400.0,
+            height,
+            3.0,
+            palette.panel_border,
+        );
+
+        let title = match (standings.first(), chinese) {
+            (Some(winner), true) => format!("{} 获胜！", winner.name),
+            (Some(winner), false) => format!("{} wins!", winner.name),
+            (None, _) => String::new(),
+        };
+        draw_label(
+            font,
+            &title,
+            center_x - label_width(font, &title, 32) / 2.0,
+            top + 42.0,
+            32,
+            palette.title,
+        );
+        for (row, standing) in standings.iter().enumerate() {
+            let y = top + 78.0 + row as f32 * 28.0;
+            let color = if standing.local {
+                palette.input
+            } else {
+                palette.text
+            };
+            let rank = format!("{}. {}", row + 1, standing.name);
+            draw_label(font, &rank, center_x - 160.0, y, 22, color);
+            let score = standing.score.to_string();
+            draw_label(
+                font,
+                &score,
+                center_x + 160.0 - label_width(font, &score, 22),
+                y,
+                22,
+                color,
+            );
+        }
+
+        let hint = match (self.session.host, chinese) {
+            (true, true) => "按 R 开始下一局，ESC 离开",
+            (true, false) => "R for the next race, ESC to leave",
+            (false, true) => "等待主机开始下一局，ESC 离开",
+            (false, false) => "Waiting for the host's next race, ESC to leave",
+        };
+        draw_label(
+            font,
+            hint,
+            center_x - label_width(font, hint, 18) / 2.0,
+            top + height - 14.0,
+            18,
+            palette.text_secondary,
+        );
+    }
+}
+
+// 撞到障碍物且不再答题复活
+fn finished(game: &GameState) -> bool {
+    game.game_over && !game.showing_math_question
+}
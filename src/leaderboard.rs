@@ -0,0 +1,174 @@
+use std::io::{Read, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::sync::mpsc::{self, Receiver};
+use std::thread;
+use std::time::Duration;
+
+use macroquad::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::ghost::GhostFile;
+use crate::theme::Palette;
+use crate::ui::{draw_label, label_width};
+
+// 在线排行榜：服务器只接受附带录像的成绩，收到后在无窗口的 GameState 中重新模拟整局，
+// 分数和步数与录像一致才记入排行榜。协议是最简单的 HTTP/1.1 加 JSON，
+// 服务器和客户端都只用标准库的 TCP 实现，见 README。这里是游戏中的客户端，服务器在 src/server.rs
+
+pub const DEFAULT_LEADERBOARD_URL: &str = "http://127.0.0.1:7879";
+pub const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+const RESPONSE_TIMEOUT: Duration = Duration::from_secs(30);
+
+// POST /scores 的响应
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct Accepted {
+    pub rank: Option<usize>,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct Rejected {
+    pub error: String,
+}
+
+pub fn json<T: Serialize>(value: &T) -> String {
+    serde_json::to_string(value).expect("leaderboard responses serialize")
+}
+
+// 把 http://主机[:端口][/路径] 拆成连接地址和路径前缀
+fn parse_url(url: &str) -> Result<(String, String), String> {
+    let rest = url
+        .strip_prefix("http://")
+        .ok_or_else(|| format!("unsupported leaderboard URL '{}'", url))?;
+    let (host, path) = rest.split_once('/').unwrap_or((rest, ""));
+    let host = if host.contains(':') {
+        host.to_string()
+    } else {
+        format!("{}:80", host)
+    };
+    Ok((host, format!("/{}", path).trim_end_matches('/').to_string()))
+}
+
+// 把录像提交到 url 指定的排行榜，返回名次；会阻塞到服务器验证完成
+pub fn submit(url: &str, file: &GhostFile) -> Result<Option<usize>, String> {
+    let (host, path) = parse_url(url)?;
+    let address = host
+        .to_socket_addrs()
+        .map_err(|err| err.to_string())?
+        .next()
+        .ok_or_else(|| format!("cannot resolve {}", host))?;
+    let mut stream =
+        TcpStream::connect_timeout(&address, CONNECT_TIMEOUT).map_err(|err| err.to_string())?;
+    stream
+        .set_read_timeout(Some(RESPONSE_TIMEOUT))
+        .map_err(|err| err.to_string())?;
+
+    let body = json(file);
+    write!(
+        stream,
+        "POST {}/scores HTTP/1.1\r\nHost: {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        path,
+        host,
+        body.len(),
+        body
+    )
+    .map_err(|err| err.to_string())?;
+    let mut response = String::new();
+    stream
+        .read_to_string(&mut response)
+        .map_err(|err| err.to_string())?;
+
+    let (head, body) = response
+        .split_once("\r\n\r\n")
+        .ok_or("malformed response")?;
+    let status: u16 = head
+        .split_whitespace()
+        .nth(1)
+        .and_then(|status| status.parse().ok())
+        .ok_or("malformed response")?;
+    if status == 200 {
+        serde_json::from_str::<Accepted>(body)
+            .map(|accepted| accepted.rank)
+            .map_err(|err| err.to_string())
+    } else {
+        Err(serde_json::from_str::<Rejected>(body)
+            .map_or_else(|_| format!("HTTP {}", status), |rejected| rejected.error))
+    }
+}
+
+pub enum UploadStatus {
+    Sending,
+    Accepted(Option<usize>),
+    Failed(String),
+}
+
+// 游戏结束画面上正在进行或已经完成的上传，在后台线程中提交
+pub struct Upload {
+    receiver: Receiver<Result<Option<usize>, String>>,
+    pub status: UploadStatus,
+}
+
+impl Upload {
+    pub fn start(url: &str, file: GhostFile) -> Self {
+        let (sender, receiver) = mpsc::channel();
+        let url = url.to_string();
+        thread::spawn(move || {
+            let _ = sender.send(submit(&url, &file));
+        });
+        Self {
+            receiver,
+            status: UploadStatus::Sending,
+        }
+    }
+
+    pub fn poll(&mut self) {
+        if let Ok(result) = self.receiver.try_recv() {
+            self.status = match result {
+                Ok(rank) => UploadStatus::Accepted(rank),
+                Err(err) => UploadStatus::Failed(err),
+            };
+        }
+    }
+
+    // 失败后可以再试一次
+    pub fn failed(&self) -> bool {
+        matches!(self.status, UploadStatus::Failed(_))
+    }
+
+    pub fn draw(&self, palette: &Palette, font: Option<&Font>, y: f32) {
+        let chinese = font.is_some();
+        let (text, color) = match (&self.status, chinese) {
+            (UploadStatus::Sending, true) => ("正在上传成绩…".to_string(), palette.text_secondary),
+            (UploadStatus::Sending, false) => {
+                ("Submitting score...".to_string(), palette.text_secondary)
+            }
+            (UploadStatus::Accepted(Some(rank)), true) => (
+                format!("成绩已通过验证，排行榜第 {} 名", rank),
+                palette.success,
+            ),
+            (UploadStatus::Accepted(Some(rank)), false) => (
+                format!("Score verified - rank {} on the leaderboard", rank),
+                palette.success,
+            ),
+            (UploadStatus::Accepted(None), true) => (
+                "成绩已通过验证，没有刷新排行榜".to_string(),
+                palette.success,
+            ),
+            (UploadStatus::Accepted(None), false) => (
+                "Score verified - no new leaderboard entry".to_string(),
+                palette.success,
+            ),
+            (UploadStatus::Failed(err), true) => (format!("上传失败：{}", err), palette.danger),
+            (UploadStatus::Failed(err), false) => {
+                (format!("Submission failed: {}", err), palette.danger)
+            }
+        };
+        draw_label(
+            font,
+            &text,
+            screen_width() / 2.0 - label_width(font, &text, 20) / 2.0,
+            y,
+            20,
+            color,
+        );
+    }
+}
@@ -0,0 +1,9 @@
+// 排行榜服务器（src/bin/leaderboard.rs）使用的库。服务器要在无窗口的 GameState 中重新模拟录像，
+// 所以这里把游戏的源码原样编译一遍，但只公开服务器的入口；窗口和游戏循环在库中用不到
+#![allow(dead_code)]
+
+include!("main.rs");
+
+mod server;
+
+pub use server::{serve, DEFAULT_SERVER_ADDRESS};
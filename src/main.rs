@@ -1,36 +1,127 @@
+use std::cell::RefCell;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+use ::rand::rngs::StdRng;
+use ::rand::SeedableRng;
 use macroquad::prelude::*;
 
+mod achievements;
+mod audio;
+mod batch;
+mod bench;
+mod bot;
+mod camera;
+mod cli;
+mod cosmetics;
+mod course;
+mod daily;
+mod defs;
+mod dino;
+mod entity;
+mod env;
+mod events;
+mod fairness;
+mod gamepad;
+mod ghost;
+mod input;
+mod keypad;
+mod lan;
+mod leaderboard;
+mod menu;
+mod net;
+mod neuro;
+mod particles;
+mod pointer;
+mod pool;
+mod powerup;
+mod profile;
+mod scenery;
+mod scoring;
+mod settings;
+mod sfx;
+mod simulate;
+mod theme;
+mod train;
+mod ui;
+mod versus;
+
+// macroquad 的 prelude 中也有 camera 模块，库（src/lib.rs）include! 这个文件时必须写全路径
+use crate::camera::Camera;
+use achievements::{StatQueue, StatTracker};
+use audio::{Audio, SoundCues, SoundQueue};
+use batch::CircleBatch;
+use bench::Bench;
+use bot::{Bot, Observation, PlannerBot};
+use cli::{Options, DEFAULT_GENOME_PATH};
+use cosmetics::{CustomizeScreen, Look, Trail};
+use course::Course;
+use daily::DailyChallenge;
+use defs::{DefsWatcher, EntityDefs};
+use dino::{Dino, DinoInput};
+use entity::{Entity, World};
+use events::{EventBus, GameEvent};
+use fairness::Obstacle;
+use ghost::{Ghost, GhostFile, LaneChange, Recording};
+use input::{Action, ActionState, Bindings, Controls};
+use lan::LanRace;
+use leaderboard::Upload;
+use menu::SettingsMenu;
+use net::Session;
+use neuro::{Genome, NeuroBot};
+use particles::{ParticleToggle, Particles};
+use pointer::{Pointer, PointerMode};
+use powerup::Active;
+use profile::Profile;
+use scenery::Scenery;
+use scoring::{Popups, Scoring};
+use settings::Settings;
+use theme::{find_theme, load_themes, Palette};
+use train::TrainConfig;
+use ui::{draw_label, label_width};
+use versus::Versus;
+
+// 排行榜服务器程序（src/bin/leaderboard.rs）的入口
+
 const GRAVITY: f32 = 800.0;
 const JUMP_FORCE: f32 = -400.0;
+const HIGH_JUMP_FORCE: f32 = -500.0; // 超级跳跃道具生效时的起跳速度
 const GROUND_Y: f32 = 400.0;
 const GROUND_Y2: f32 = 450.0; // 第二条地面线，距离第一条50像素
+
+// 多车道模式：每条车道的地面依次向下相隔 LANE_SPACING，前两条就是两条地面线
+const MAX_LANES: usize = 3;
+const LANE_SPACING: f32 = GROUND_Y2 - GROUND_Y;
+
 const DINO_WIDTH: f32 = 25.0;
 const DINO_HEIGHT: f32 = 35.0;
+const DINO_DUCK_WIDTH: f32 = 40.0;
+const DINO_DUCK_HEIGHT: f32 = 20.0;
+const FAST_FALL_MULTIPLIER: f32 = 3.0; // 空中按下蹲时加速下落
 const DINO_X: f32 = 120.0;
-const CACTUS_WIDTH: f32 = 20.0;
-const CACTUS_HEIGHT: f32 = 60.0;
 const GAME_SPEED: f32 = 200.0;
-const STAR_SIZE: f32 = 15.0;
-
-#[derive(Clone)]
-struct Cactus {
-    x: f32,
-    y: f32,
-}
-
-#[derive(Clone)]
-struct Star {
-    x: f32,
-    y: f32,
-}
-
-#[derive(Clone)]
-struct Stone {
-    x: f32,
-    y: f32,
-    size: f32,
-    color: Color,
-}
+// 游戏逻辑以固定步长推进，保证同一种子和同样的输入得到完全相同的结果
+const FIXED_DT: f32 = 1.0 / 60.0;
+// 单帧最多推进的步数，避免卡顿后追赶过多
+const MAX_STEPS_PER_FRAME: u32 = 8;
+// 没有窗口时（测试、无界面模拟）使用的视野宽度
+const VIEW_WIDTH: f32 = 800.0;
+// 同时存在的障碍物、星星和道具以及装饰石子的数量上限，超过时丢弃同一类中最旧的
+const MAX_ENTITIES: usize = 256;
+const MAX_DECORATIONS: usize = 512;
+const NIGHT_SCORE_INTERVAL: u32 = 200; // 每 200 分在白天和夜晚之间切换
+const NIGHT_TRANSITION_TIME: f32 = 2.0; // 昼夜渐变所需的秒数
+
+// 数学题使用独立的随机数流，答题不影响障碍物的生成
+const QUIZ_SEED_SALT: u64 = 0x7175_697a_5eed_0001;
+
+// 撞到障碍物后的死亡动画（以步数计）：先定格 HIT_STOP_TICKS 步，随后数学题用 QUIZ_FADE_TICKS 步淡入，
+// 开始淡入后才接受答案；屏幕震动的大小为护盾挡下碰撞和真正撞上时的震动量
+const HIT_STOP_TICKS: u32 = 6;
+const QUIZ_DELAY_TICKS: u32 = 30;
+const QUIZ_FADE_TICKS: u32 = 15;
+const HIT_SHAKE: f32 = 0.8;
+const ABSORBED_HIT_SHAKE: f32 = 0.35;
 
 #[derive(Clone)]
 struct MathQuestion {
@@ -39,9 +130,8 @@ struct MathQuestion {
 }
 
 impl MathQuestion {
-    fn new() -> Self {
+    fn new(rng: &mut StdRng) -> Self {
         use ::rand::Rng;
-        let mut rng = ::rand::thread_rng();
 
         let question_type = rng.gen_range(0..3);
 
@@ -77,61 +167,257 @@ impl MathQuestion {
     }
 }
 
+// 第 lane 条车道的地面高度
+fn lane_ground(lane: usize) -> f32 {
+    GROUND_Y + lane as f32 * LANE_SPACING
+}
+
+// 第 lane 条车道上的障碍物，换算到第一条车道的坐标
+fn lane_obstacles(world: &World, lane: usize) -> Vec<Obstacle> {
+    let offset = lane as f32 * LANE_SPACING;
+    world
+        .iter()
+        .filter(|entity| entity.lane == lane)
+        .filter_map(Obstacle::from_entity)
+        .map(|mut obstacle| {
+            obstacle.rect.y -= offset;
+            obstacle
+        })
+        .collect()
+}
+
+// 在第 tick 步生成 pattern 之后，恐龙能否躲过同一车道上的全部障碍物（都在第一条车道的坐标中）。
+// 先模拟本步剩下的移动和碰撞检测，再从下一步开始规划
+fn pattern_is_clearable(
+    dino: Dino,
+    mut obstacles: Vec<Obstacle>,
+    pattern: &[Entity],
+    tick: u64,
+) -> bool {
+    obstacles.extend(pattern.iter().filter_map(Obstacle::from_entity));
+    for obstacle in &mut obstacles {
+        obstacle.advance(FIXED_DT, GAME_SPEED);
+    }
+    if obstacles
+        .iter()
+        .any(|obstacle| dino.rect().overlaps(&obstacle.rect))
+    {
+        return false;
+    }
+    fairness::plan(dino, &obstacles, GAME_SPEED, FIXED_DT, tick + 1).is_some()
+}
+
 struct GameState {
-    dino_y: f32,
-    dino_velocity: f32,
-    is_jumping: bool,
-    cacti: Vec<Cactus>,
-    stars: Vec<Star>,
-    stones: Vec<Stone>,
+    dino: Dino,
+    paused: bool,
+    // 基准测试模式下不检测仙人掌碰撞，保证场景持续滚动
+    invincible: bool,
+    // 仙人掌、星星、石子等所有滚动的游戏对象
+    world: World,
+    stone_batch: RefCell<CircleBatch>,
     score: u32,
+    // 星星连击
+    scoring: Scoring,
+    // 本局收集的星星数量
+    stars: u32,
     game_over: bool,
-    spawn_timer: f32,
-    star_spawn_timer: f32,
+    // 本局已推进的步数，决定游戏速度
+    ticks: u64,
+    // 新实体在视野右边缘生成。视野宽度在每局开始时取当前的窗口宽度，一局中途改变窗口大小不影响赛道
+    view_width: f32,
+    window_width: f32,
+    // 障碍物和道具的定义，以及每个生成器的计时器；热重载的定义先放在 pending_defs 中，从下一局开始生效
+    defs: EntityDefs,
+    pending_defs: Option<EntityDefs>,
+    spawn_timers: Vec<f32>,
     stone_spawn_timer: f32,
+    // 本局的随机种子，障碍物和背景都由它生成
+    seed: u64,
+    rng: StdRng,
+    quiz_rng: StdRng,
+    scenery: Scenery,
     font: Option<Font>,
+    palette: Palette,
+    // 玩家选择的恐龙外观和拖尾经过的位置，只影响绘制
+    look: Look,
+    trail: Trail,
+    // 0 表示白天，1 表示夜晚
+    night: f32,
+    // 画面的摄像机和屏幕震动
+    camera: Camera,
+    // 撞到障碍物后经过的步数，死亡动画据此播放；没有撞到时为 None
+    death: Option<u32>,
     // 复活系统
     showing_math_question: bool,
     math_question: Option<MathQuestion>,
     input_buffer: String,
+    // 本步发生的事件，一步结束时发给启动时注册的订阅者（音效、得分飘字等）
+    events: EventBus,
+    // 自动游戏：机器人代替玩家操作恐龙
+    bot: Option<Box<dyn Bot>>,
+    // 玩家接管后暂存的机器人，再次开启自动游戏时继续使用
+    parked_bot: Option<Box<dyn Bot>>,
+    // 标题画面的演示模式：机器人在背景中游戏，撞到障碍物后直接重新开始
+    attract: bool,
+    // 本局的操作记录，用于保存个人最佳录像
+    recording: Recording,
+    // 正在赛跑的幽灵
+    ghost: Option<Ghost>,
+    // 每日挑战：重新开始时继续使用当天的种子
+    daily: Option<DailyChallenge>,
+    // 本局结束后是否已经记录过成绩
+    run_reported: bool,
+    // 共用的赛道：每日挑战、双人赛跑、局域网对战和这些对局的录像中由领跑者生成赛道，自己不生成实体
+    course: Option<Box<Course>>,
+    // 局域网对战：大厅和结束画面由 lan 模块负责
+    networked: bool,
+    // 本步新生成的实体，共用的赛道从领跑者这里复制
+    spawned: Vec<Entity>,
+    // 设置中选择的车道数，从下一局开始生效；lanes 为本局实际的车道数
+    lane_setting: usize,
+    lanes: usize,
+    // 本局成绩上传到排行榜的进度
+    upload: Option<Upload>,
+    // 生效中的道具
+    power_ups: Vec<Active>,
 }
 
 impl GameState {
-    fn new() -> Self {
+    fn new(defs: EntityDefs) -> Self {
+        let seed = ::rand::random();
         Self {
-            dino_y: GROUND_Y - DINO_HEIGHT,
-            dino_velocity: 0.0,
-            is_jumping: false,
-            cacti: Vec::new(),
-            stars: Vec::new(),
-            stones: Vec::new(),
+            dino: Dino::new(),
+            paused: false,
+            invincible: false,
+            world: World::with_capacity(MAX_ENTITIES, MAX_DECORATIONS),
+            stone_batch: RefCell::new(CircleBatch::new()),
             score: 0,
+            scoring: Scoring::default(),
+            stars: 0,
             game_over: false,
-            spawn_timer: 0.0,
-            star_spawn_timer: 0.0,
+            ticks: 0,
+            view_width: VIEW_WIDTH,
+            window_width: VIEW_WIDTH,
+            spawn_timers: vec![0.0; defs.spawners.len()],
+            defs,
+            pending_defs: None,
             stone_spawn_timer: 0.0,
+            seed,
+            rng: StdRng::seed_from_u64(seed),
+            quiz_rng: StdRng::seed_from_u64(seed ^ QUIZ_SEED_SALT),
+            scenery: Scenery::new(seed, GROUND_Y, VIEW_WIDTH),
             font: None,
+            palette: Palette::default(),
+            look: Look::default(),
+            trail: Trail::default(),
+            night: 0.0,
+            camera: Camera::default(),
+            death: None,
             showing_math_question: false,
             math_question: None,
             input_buffer: String::new(),
+            events: EventBus::default(),
+            bot: None,
+            parked_bot: None,
+            attract: false,
+            recording: Recording::default(),
+            ghost: None,
+            daily: None,
+            run_reported: false,
+            course: None,
+            networked: false,
+            spawned: Vec::new(),
+            lane_setting: 1,
+            lanes: 1,
+            upload: None,
+            power_ups: Vec::new(),
         }
     }
 
     fn reset(&mut self) {
-        self.dino_y = GROUND_Y - DINO_HEIGHT;
-        self.dino_velocity = 0.0;
-        self.is_jumping = false;
-        self.cacti.clear();
-        self.stars.clear();
-        self.stones.clear();
+        self.end_power_ups();
+        // 每日挑战的赛道与窗口大小无关，所有人都使用默认的视野宽度
+        self.view_width = if self.daily.is_some() {
+            VIEW_WIDTH
+        } else {
+            self.window_width
+        };
+        if let Some(defs) = self.pending_defs.take() {
+            self.set_defs(defs);
+        }
+        self.dino = Dino::new();
+        self.paused = false;
+        self.world.clear();
         self.score = 0;
+        self.scoring.reset();
+        self.stars = 0;
         self.game_over = false;
-        self.spawn_timer = 0.0;
-        self.star_spawn_timer = 0.0;
+        self.ticks = 0;
+        // 每日挑战和对战中所有人跑同一条经典赛道
+        let lanes = if self.daily.is_some() || self.networked {
+            1
+        } else {
+            self.lane_setting
+        };
+        self.set_lanes(lanes);
         self.stone_spawn_timer = 0.0;
+        self.night = 0.0;
+        self.trail.clear();
+        let seed = self
+            .daily
+            .as_ref()
+            .map_or_else(::rand::random, DailyChallenge::seed);
+        self.course = None;
+        self.reseed(seed);
+        if self.daily.is_some() {
+            self.share_course();
+        }
+        self.camera.clear();
+        self.death = None;
         self.showing_math_question = false;
         self.math_question = None;
         self.input_buffer.clear();
+        self.recording = Recording::default();
+        self.ghost = None;
+        self.run_reported = false;
+        self.upload = None;
+        self.events.emit(GameEvent::Reset);
+        self.events.dispatch();
+    }
+
+    // 用新的种子重新生成本局的随机数流和背景，共用的赛道也按新的种子重新生成
+    fn reseed(&mut self, seed: u64) {
+        self.seed = seed;
+        self.rng = StdRng::seed_from_u64(seed);
+        self.quiz_rng = StdRng::seed_from_u64(seed ^ QUIZ_SEED_SALT);
+        self.scenery = Scenery::new(seed, GROUND_Y, self.view_width);
+        if self.course.is_some() {
+            self.share_course();
+        }
+    }
+
+    // 本局改为跑领跑者按当前的种子、定义和视野宽度生成的赛道，必须在本局开始前调用
+    fn share_course(&mut self) {
+        let course = Course::new(&self.defs, self.seed, self.view_width);
+        self.course = Some(Box::new(course));
+    }
+
+    // 热重载的定义从下一局开始生效：进行中的一局和与它同步的幽灵、录像保持原来的定义
+    fn reload_defs(&mut self, defs: EntityDefs) {
+        self.pending_defs = Some(defs);
+    }
+
+    // 替换障碍物和道具定义，已在场上的实体不受影响。
+    // 生成器的数量和顺序可能改变，每条车道的计时器按新的定义重新排列并清零
+    fn set_defs(&mut self, defs: EntityDefs) {
+        self.defs = defs;
+        self.set_lanes(self.lanes);
+    }
+
+    // 设置本局的车道数并清零生成计时器，每条车道的每个生成器各有一个计时器
+    fn set_lanes(&mut self, lanes: usize) {
+        self.lanes = lanes;
+        self.spawn_timers = vec![0.0; self.defs.spawners.len() * lanes];
     }
 
     async fn load_font(&mut self) {
@@ -146,47 +432,28 @@ impl GameState {
         }
     }
 
-    fn handle_math_input(&mut self) {
+    // 注册界面反馈：得分飘字、粒子特效和音效，音效放进 sounds 等待主循环播放
+    fn subscribe_feedback(&mut self, sounds: &SoundQueue, particles: &ParticleToggle) {
+        self.events.subscribe(Box::new(Popups::default()));
+        self.events.subscribe(Box::new(Particles::new(particles)));
+        self.events.subscribe(Box::new(SoundCues(sounds.clone())));
+    }
+
+    fn handle_math_input(&mut self, actions: &ActionState) {
         // 处理数字输入
-        for key_code in [
-            KeyCode::Key0,
-            KeyCode::Key1,
-            KeyCode::Key2,
-            KeyCode::Key3,
-            KeyCode::Key4,
-            KeyCode::Key5,
-            KeyCode::Key6,
-            KeyCode::Key7,
-            KeyCode::Key8,
-            KeyCode::Key9,
-        ] {
-            if is_key_pressed(key_code) {
-                let digit = match key_code {
-                    KeyCode::Key0 => '0',
-                    KeyCode::Key1 => '1',
-                    KeyCode::Key2 => '2',
-                    KeyCode::Key3 => '3',
-                    KeyCode::Key4 => '4',
-                    KeyCode::Key5 => '5',
-                    KeyCode::Key6 => '6',
-                    KeyCode::Key7 => '7',
-                    KeyCode::Key8 => '8',
-                    KeyCode::Key9 => '9',
-                    _ => continue,
-                };
-                if self.input_buffer.len() < 4 {
-                    self.input_buffer.push(digit);
-                }
+        for digit in actions.digits_pressed() {
+            if self.input_buffer.len() < 4 {
+                self.input_buffer.push(digit);
             }
         }
 
         // 处理退格键
-        if is_key_pressed(KeyCode::Backspace) && !self.input_buffer.is_empty() {
+        if actions.pressed(Action::Backspace) && !self.input_buffer.is_empty() {
             self.input_buffer.pop();
         }
 
         // 处理回车键提交答案
-        if is_key_pressed(KeyCode::Enter) && !self.input_buffer.is_empty() {
+        if actions.pressed(Action::Confirm) && !self.input_buffer.is_empty() {
             if let Ok(answer) = self.input_buffer.parse::<i32>() {
                 if let Some(question) = &self.math_question {
                     if answer == question.answer {
@@ -194,34 +461,256 @@ impl GameState {
                         self.revive();
                     } else {
                         // 答错了，重新生成题目让玩家再试
-                        self.math_question = Some(MathQuestion::new());
+                        self.events.emit(GameEvent::WrongAnswer);
+                        self.math_question = Some(MathQuestion::new(&mut self.quiz_rng));
                         self.input_buffer.clear();
                     }
                 }
             }
         }
 
-        // ESC 键取消复活，真正游戏结束
-        if is_key_pressed(KeyCode::Escape) {
+        // 取消复活，真正游戏结束
+        if actions.pressed(Action::Cancel) {
             self.showing_math_question = false;
         }
     }
 
     fn revive(&mut self) {
         self.game_over = false;
+        self.death = None;
         self.showing_math_question = false;
-        self.dino_y = GROUND_Y - DINO_HEIGHT;
-        self.dino_velocity = 0.0;
-        self.is_jumping = false;
+        let lane = self.dino.lane;
+        self.dino = Dino::new();
+        self.dino.switch_lane(lane);
         // 清除附近的仙人掌给玩家一些缓冲时间
-        self.cacti.retain(|cactus| cactus.x > DINO_X + 100.0);
+        self.world
+            .retain(|entity| !entity.is_damaging() || entity.pos.x > DINO_X + 100.0);
+        // 从地面重新起步后本车道剩下的障碍物仍要能够通过
+        self.drop_unclearable_obstacles();
+        self.scoring.reset();
         self.input_buffer.clear();
+        self.events.emit(GameEvent::Revived);
+        self.recording.revives.push(self.ticks);
+    }
+
+    // 恐龙所在车道的障碍物无法全部躲过时，从最近的开始逐个移除
+    fn drop_unclearable_obstacles(&mut self) {
+        let lane = self.dino.lane;
+        while self.plan_course().is_none() {
+            let in_lane = |entity: &Entity| entity.is_damaging() && entity.lane == lane;
+            let nearest = self
+                .world
+                .iter()
+                .filter(|entity| in_lane(entity))
+                .map(|entity| entity.pos.x)
+                .fold(f32::MAX, f32::min);
+            self.world
+                .retain(|entity| !in_lane(entity) || entity.pos.x > nearest);
+        }
+    }
+
+    // 获得道具效果，同一种道具正在生效时重新开始计时
+    fn activate_power_up(&mut self, name: &str) {
+        let Some(mut power_up) = powerup::by_name(name) else {
+            return;
+        };
+        // 再次拾取生效中的道具时，先让旧的正常结束，再从头计时
+        if let Some(index) = self
+            .power_ups
+            .iter()
+            .position(|active| active.power_up.name() == name)
+        {
+            let active = self.power_ups.remove(index);
+            self.end_power_up(active);
+        }
+        power_up.start(self);
+        self.events.emit(GameEvent::PowerUpCollected {
+            name: power_up.name(),
+        });
+        let duration = powerup::duration_ticks(power_up.as_ref());
+        self.power_ups.push(Active {
+            power_up,
+            remaining: duration,
+            duration,
+        });
+        // 道具可能改变恐龙的跳跃，已经生成的障碍物仍要能够通过
+        self.drop_unclearable_obstacles();
+    }
+
+    // 推进生效中的道具一步，移除到期的道具
+    fn step_power_ups(&mut self) {
+        let mut power_ups = std::mem::take(&mut self.power_ups);
+        for active in &mut power_ups {
+            active.power_up.step(self);
+            active.remaining = active.remaining.saturating_sub(1);
+        }
+        for active in power_ups {
+            if active.remaining == 0 {
+                self.end_power_up(active);
+            } else {
+                self.power_ups.push(active);
+            }
+        }
+    }
+
+    // 立即结束所有道具效果
+    fn end_power_ups(&mut self) {
+        for active in std::mem::take(&mut self.power_ups) {
+            self.end_power_up(active);
+        }
+    }
+
+    fn end_power_up(&mut self, mut active: Active) {
+        active.power_up.end(self);
+        self.events.emit(GameEvent::PowerUpEnded {
+            name: active.power_up.name(),
+        });
+    }
+
+    // 用掉一个能抵挡碰撞的道具，没有时返回 false
+    fn absorb_hit(&mut self) -> bool {
+        let Some(index) = self
+            .power_ups
+            .iter()
+            .position(|active| active.power_up.absorbs_hit())
+        else {
+            return false;
+        };
+        let active = self.power_ups.remove(index);
+        self.end_power_up(active);
+        true
+    }
+
+    fn score_multiplier(&self) -> u32 {
+        self.power_ups
+            .iter()
+            .map(|active| active.power_up.score_multiplier())
+            .product()
+    }
+
+    // 主循环推进游戏的速度倍数，慢动作道具生效时小于 1
+    fn time_scale(&self) -> f32 {
+        self.power_ups
+            .iter()
+            .map(|active| active.power_up.time_scale())
+            .fold(1.0, f32::min)
+    }
+
+    // 从当前这一步开始，为恐龙规划躲过场上所有障碍物的操作
+    fn plan_course(&self) -> Option<Vec<DinoInput>> {
+        self.observe().plan()
+    }
+
+    // 机器人看到的游戏状态：只有恐龙所在车道，换算到第一条车道的坐标
+    fn observe(&self) -> Observation {
+        let lane = self.dino.lane;
+        let spawners = self.defs.spawners.len();
+        Observation {
+            dino: self.dino.in_first_lane(),
+            tick: self.ticks,
+            speed: GAME_SPEED,
+            obstacles: lane_obstacles(&self.world, lane),
+            spawn_timers: self.spawn_timers[lane * spawners..(lane + 1) * spawners].to_vec(),
+        }
+    }
+
+    // 开关自动游戏
+    fn toggle_autoplay(&mut self) {
+        if let Some(bot) = self.bot.take() {
+            self.parked_bot = Some(bot);
+        } else {
+            let bot = self.parked_bot.take();
+            self.bot = Some(bot.unwrap_or_else(|| Box::new(PlannerBot::default())));
+        }
+    }
+
+    // 进入标题画面，由机器人在背景中演示
+    fn start_attract(&mut self) {
+        self.daily = None;
+        self.reset();
+        self.attract = true;
+        self.bot = Some(Box::new(PlannerBot::default()));
+    }
+
+    // 从标题画面开始真正的一局
+    fn start_game(&mut self) {
+        self.daily = None;
+        self.reset();
+        self.attract = false;
+        self.bot = None;
+    }
+
+    // 开始当天的每日挑战
+    fn start_daily(&mut self, challenge: DailyChallenge) {
+        self.daily = Some(challenge);
+        self.reset();
+        self.attract = false;
+        self.bot = None;
+    }
+
+    // 本局刚刚真正结束（撞到障碍物且不再复活）时返回 true，每局只返回一次
+    fn take_finished_run(&mut self) -> bool {
+        let finished = self.game_over && !self.showing_math_question && !self.attract;
+        if finished && !self.run_reported {
+            self.run_reported = true;
+            return true;
+        }
+        false
     }
 
-    fn update(&mut self, dt: f32) {
-        // 处理数学题界面
+    // 与录像中的幽灵赛跑：使用录像的种子、视野宽度、车道数和赛道来源，必须在本局开始前调用
+    fn race(&mut self, file: GhostFile) {
+        self.view_width = file.view_width;
+        self.set_lanes(file.lanes);
+        self.reseed(file.seed);
+        if file.shared_course {
+            self.share_course();
+        }
+        match Ghost::new(file, &self.defs) {
+            Ok(ghost) => self.ghost = Some(ghost),
+            Err(err) => println!("Warning: invalid ghost: {}", err),
+        }
+    }
+
+    // 本局能否计入个人最佳：玩家亲自操作的正式对局
+    fn counts_for_best(&self) -> bool {
+        !self.recording.assisted && !self.invincible && !self.attract && self.ticks > 0
+    }
+
+    // 游戏结束后能否按 U 上传成绩：计入个人最佳的经典模式对局，且没有正在上传或已经上传成功
+    fn can_upload(&self) -> bool {
+        self.counts_for_best() && self.lanes == 1 && self.upload.as_ref().is_none_or(Upload::failed)
+    }
+
+    // 以 FIXED_DT 推进一步，并把这一步发生的事件发给订阅者。
+    // 撞到障碍物后先定格片刻，特效和屏幕震动一起停住
+    fn update(&mut self, actions: &ActionState) {
+        self.simulate(actions);
+        let hit_stop = self.death.is_some_and(|ticks| ticks < HIT_STOP_TICKS);
+        if let Some(ticks) = &mut self.death {
+            *ticks = ticks.saturating_add(1);
+        }
+        if !self.paused && !hit_stop {
+            self.events.step(FIXED_DT);
+            self.camera.step(FIXED_DT);
+        }
+        self.events.dispatch();
+    }
+
+    // 数学题的不透明度：死亡动画播到一半后淡入
+    fn quiz_alpha(&self) -> f32 {
+        self.death.map_or(1.0, |ticks| {
+            (ticks.saturating_sub(QUIZ_DELAY_TICKS) as f32 / QUIZ_FADE_TICKS as f32).min(1.0)
+        })
+    }
+
+    fn simulate(&mut self, actions: &ActionState) {
+        let dt = FIXED_DT;
+        // 处理数学题界面，数学题开始淡入后才接受答案
         if self.showing_math_question {
-            self.handle_math_input();
+            if self.death.is_none_or(|ticks| ticks >= QUIZ_DELAY_TICKS) {
+                self.handle_math_input(actions);
+            }
             return;
         }
 
@@ -229,79 +718,174 @@ impl GameState {
             return;
         }
 
-        // 恐龙跳跃逻辑
-        if is_key_pressed(KeyCode::Space) && !self.is_jumping {
-            self.dino_velocity = JUMP_FORCE;
-            self.is_jumping = true;
+        if actions.pressed(Action::Pause) {
+            self.paused = !self.paused;
+        }
+        if self.paused {
+            return;
         }
 
-        // 应用重力
-        self.dino_velocity += GRAVITY * dt;
-        self.dino_y += self.dino_velocity * dt;
+        // 恐龙跳跃和下蹲，自动游戏时由机器人决定
+        let input = if let Some(mut bot) = self.bot.take() {
+            let input = bot.decide(&self.observe());
+            self.bot = Some(bot);
+            self.recording.assisted = true;
+            input
+        } else {
+            DinoInput {
+                jump: actions.pressed(Action::Jump),
+                duck: actions.down(Action::Duck),
+            }
+        };
+        self.recording.inputs.push(input);
+
+        // 多车道模式：换到相邻的车道，空中也可以换道
+        let lane = self.dino.lane;
+        if actions.pressed(Action::LaneUp) && lane > 0 {
+            self.dino.switch_lane(lane - 1);
+        } else if actions.pressed(Action::LaneDown) && lane + 1 < self.lanes {
+            self.dino.switch_lane(lane + 1);
+        }
+        if self.dino.lane != lane {
+            self.recording.lane_changes.push(LaneChange {
+                tick: self.ticks,
+                lane: self.dino.lane,
+            });
+        }
+        let was_jumping = self.dino.is_jumping;
+        if self.dino.step(dt, input) {
+            self.events.emit(GameEvent::Jumped);
+        } else if was_jumping && !self.dino.is_jumping {
+            let pos = vec2(DINO_X + DINO_WIDTH / 2.0, lane_ground(self.dino.lane));
+            self.events.emit(GameEvent::Landed { pos });
+        }
 
-        // 检查着地
-        if self.dino_y >= GROUND_Y - DINO_HEIGHT {
-            self.dino_y = GROUND_Y - DINO_HEIGHT;
-            self.dino_velocity = 0.0;
-            self.is_jumping = false;
+        // 昼夜交替：分数每过一个里程碑就在白天和夜晚之间渐变
+        let night_target = if (self.score / NIGHT_SCORE_INTERVAL) % 2 == 1 {
+            1.0
+        } else {
+            0.0
+        };
+        let night_step = dt / NIGHT_TRANSITION_TIME;
+        if self.night < night_target {
+            self.night = (self.night + night_step).min(night_target);
+        } else {
+            self.night = (self.night - night_step).max(night_target);
         }
 
-        // 生成仙人掌
-        self.spawn_timer += dt;
-        if self.spawn_timer > 2.5 {
-            use ::rand::Rng;
-            let mut rng = ::rand::thread_rng();
-
-            // 40% 概率生成仙人掌
-            if rng.gen_bool(0.4) {
-                // 仙人掌底部位置在两条地面线中间
-                let middle_y = GROUND_Y + (GROUND_Y2 - GROUND_Y) / 2.0;
-                let cactus_y = middle_y - CACTUS_HEIGHT;
-                
-                // 30% 概率生成两个连在一起的仙人掌
-                if rng.gen_bool(0.3) {
-                    // 生成第一个仙人掌
-                    self.cacti.push(Cactus {
-                        x: screen_width(),
-                        y: cactus_y,
-                    });
-                    // 生成第二个仙人掌，紧挨着第一个
-                    self.cacti.push(Cactus {
-                        x: screen_width() + CACTUS_WIDTH + 5.0, // 留5像素间隙
-                        y: cactus_y,
-                    });
-                } else {
-                    // 生成单个仙人掌
-                    self.cacti.push(Cactus {
-                        x: screen_width(),
-                        y: cactus_y,
-                    });
-                }
+        // 滚动远景视差层
+        self.scenery.update(dt, GAME_SPEED, self.view_width);
+        self.trail.step(self.dino.rect(), GAME_SPEED * dt);
+
+        // 生成障碍物、道具和石子；共用的赛道中复制领跑者这一步生成的实体
+        if let Some(course) = &mut self.course {
+            self.spawned.clear();
+            self.spawned.extend_from_slice(course.spawns_at(self.ticks));
+            for entity in &self.spawned {
+                self.world.spawn(entity.clone());
             }
-            self.spawn_timer = 0.0;
+        } else {
+            self.spawn_entities(dt);
         }
 
-        // 生成五角星奖励
-        self.star_spawn_timer += dt;
-        if self.star_spawn_timer > 1.5 {
-            use ::rand::Rng;
-            let mut rng = ::rand::thread_rng();
+        // 移动所有实体
+        self.world.integrate(dt, GAME_SPEED);
+        self.ticks += 1;
+        if let Some(ghost) = &mut self.ghost {
+            ghost.advance_to(self.ticks);
+        }
 
-            // 80% 概率生成五角星
-            if rng.gen_bool(0.8) {
-                self.stars.push(Star {
-                    x: screen_width(),
-                    y: GROUND_Y - 40.0 - rng.gen_range(0.0..60.0), // 降低高度，在较低空中随机
-                });
+        // 道具效果（例如磁铁吸引星星），到期的道具在这里移除
+        self.step_power_ups();
+
+        // 移除屏幕外的实体，碰撞检测：收集本车道的五角星奖励和道具，撞到本车道的仙人掌则游戏结束
+        let despawned = self.world.despawn_offscreen(self.dino.lane);
+        let contacts = self.world.collide(self.dino.rect(), self.dino.lane);
+
+        // 越过的仙人掌和收集的星星交给计分模块结算
+        let multiplier = self.score_multiplier();
+        self.scoring.settle(
+            &mut self.events,
+            &mut self.score,
+            despawned.passed_score,
+            &contacts.stars,
+            despawned.missed_stars,
+            multiplier,
+        );
+        self.stars += contacts.stars.len() as u32;
+        for name in &contacts.power_ups {
+            self.activate_power_up(name);
+        }
+
+        if self.invincible {
+            return;
+        }
+
+        if contacts.hit && self.absorb_hit() {
+            // 护盾挡下了这次碰撞，撞上的障碍物被撞飞
+            let (rect, lane) = (self.dino.rect(), self.dino.lane);
+            self.world.retain(|entity| {
+                !(entity.is_damaging()
+                    && entity.lane == lane
+                    && entity.rect().is_some_and(|hit| hit.overlaps(&rect)))
+            });
+            self.events.emit(GameEvent::HitAbsorbed);
+            self.camera.shake(ABSORBED_HIT_SHAKE);
+        } else if contacts.hit && self.attract {
+            self.reset();
+        } else if contacts.hit {
+            self.game_over = true;
+            self.end_power_ups();
+            let rect = self.dino.rect();
+            let pos = vec2(rect.right(), rect.center().y);
+            self.events.emit(GameEvent::Hit { pos });
+            self.camera.shake(HIT_SHAKE);
+            self.death = Some(0);
+            // 每次游戏结束都自动弹出数学题
+            self.showing_math_question = true;
+            self.math_question = Some(MathQuestion::new(&mut self.quiz_rng));
+            self.input_buffer.clear();
+        }
+    }
+
+    // 按定义文件生成障碍物和道具，以及地面小石子装饰，本步生成的实体同时记录在 spawned 中。
+    // 多车道模式中每条车道独立生成，组合在第一条车道的坐标中生成和检查，放入时再移到所在车道
+    fn spawn_entities(&mut self, dt: f32) {
+        self.spawned.clear();
+        let spawners = self.defs.spawners.len();
+        for (index, timer) in self.spawn_timers.iter_mut().enumerate() {
+            let (lane, spawner) = (index / spawners, &self.defs.spawners[index % spawners]);
+            *timer += dt;
+            if *timer > spawner.interval {
+                // 恐龙在别的车道时，按刚换进这条车道、站在地面上的恐龙检查
+                let dino = if self.dino.lane == lane {
+                    self.dino.in_first_lane()
+                } else {
+                    Dino {
+                        high_jump_ticks: self.dino.high_jump_ticks,
+                        ..Dino::new()
+                    }
+                };
+                let (obstacles, tick) = (lane_obstacles(&self.world, lane), self.ticks);
+                let entities =
+                    spawner.spawn(&mut self.rng, self.score, self.view_width, |pattern| {
+                        pattern_is_clearable(dino, obstacles.clone(), pattern, tick)
+                    });
+                for mut entity in entities {
+                    entity.pos.y += lane as f32 * LANE_SPACING;
+                    entity.lane = lane;
+                    self.spawned.push(entity.clone());
+                    self.world.spawn(entity);
+                }
+                *timer = 0.0;
             }
-            self.star_spawn_timer = 0.0;
         }
 
         // 生成地面小石子装饰
         self.stone_spawn_timer += dt;
         if self.stone_spawn_timer > 0.2 {
             use ::rand::Rng;
-            let mut rng = ::rand::thread_rng();
+            let rng = &mut self.rng;
 
             // 95% 概率生成小石子，更密集
             if rng.gen_bool(0.95) {
@@ -315,198 +899,73 @@ impl GameState {
                         let ground_line = if rng.gen_bool(0.5) { GROUND_Y } else { GROUND_Y2 };
                         ground_line + rng.gen_range(-8.0..12.0)
                     };
-                    
-                    // 生成随机颜色
-                    let random_colors = [
-                        Color::new(0.8, 0.2, 0.2, 1.0), // 红色
-                        Color::new(0.2, 0.8, 0.2, 1.0), // 绿色
-                        Color::new(0.2, 0.2, 0.8, 1.0), // 蓝色
-                        Color::new(0.8, 0.8, 0.2, 1.0), // 黄色
-                        Color::new(0.8, 0.2, 0.8, 1.0), // 紫色
-                        Color::new(0.2, 0.8, 0.8, 1.0), // 青色
-                        Color::new(0.9, 0.5, 0.2, 1.0), // 橙色
-                        DARKGRAY, // 灰色
-                        GRAY,     // 浅灰色
-                    ];
-                    
-                    let color = if stone_y > GROUND_Y && stone_y < GROUND_Y2 {
-                        // 两条线中间的石子使用随机颜色
-                        random_colors[rng.gen_range(0..7)] // 不包括灰色
-                    } else {
-                        // 地面线附近的石子使用灰色
-                        random_colors[rng.gen_range(7..9)]
-                    };
-                    
-                    self.stones.push(Stone {
-                        x: screen_width() + rng.gen_range(0.0..60.0),
-                        y: stone_y,
-                        size: rng.gen_range(1.5..4.5),
-                        color,
-                    });
+
+                    // 颜色在绘制时从当前主题的调色板中选取，这里只记录一个随机序号
+                    let tint = rng.gen_range(0..256);
+
+                    let stone_x = self.view_width + rng.gen_range(0.0..60.0);
+                    let radius = rng.gen_range(1.5..4.5);
+                    let stone = Entity::stone(stone_x, stone_y, radius, tint);
+                    self.spawned.push(stone.clone());
+                    self.world.spawn(stone);
                 }
             }
             self.stone_spawn_timer = 0.0;
         }
+    }
 
-        // 移动仙人掌
-        for cactus in &mut self.cacti {
-            cactus.x -= GAME_SPEED * dt;
-        }
-
-        // 移动五角星
-        for star in &mut self.stars {
-            star.x -= GAME_SPEED * dt;
-        }
-
-        // 移动小石子
-        for stone in &mut self.stones {
-            stone.x -= GAME_SPEED * dt;
-        }
-
-        // 移除屏幕外的仙人掌并增加分数
-        let initial_count = self.cacti.len();
-        self.cacti.retain(|cactus| cactus.x > -CACTUS_WIDTH);
-        let removed_count = initial_count - self.cacti.len();
-        self.score += removed_count as u32 * 10;
-
-        // 移除屏幕外的五角星
-        self.stars.retain(|star| star.x > -STAR_SIZE);
-
-        // 移除屏幕外的小石子
-        self.stones.retain(|stone| stone.x > -10.0);
-
-        // 五角星碰撞检测（收集奖励）
-        let dino_rect = Rect::new(DINO_X, self.dino_y, DINO_WIDTH, DINO_HEIGHT);
-        let initial_star_count = self.stars.len();
-        self.stars.retain(|star| {
-            let star_rect = Rect::new(star.x, star.y, STAR_SIZE, STAR_SIZE);
-            !dino_rect.overlaps(&star_rect)
-        });
-        let collected_stars = initial_star_count - self.stars.len();
-        self.score += collected_stars as u32 * 5;
-
-        // 仙人掌碰撞检测
-        for cactus in &self.cacti {
-            let cactus_rect = Rect::new(cactus.x, cactus.y, CACTUS_WIDTH, CACTUS_HEIGHT);
-            if dino_rect.overlaps(&cactus_rect) {
-                self.game_over = true;
-                // 每次游戏结束都自动弹出数学题
-                self.showing_math_question = true;
-                self.math_question = Some(MathQuestion::new());
-                self.input_buffer.clear();
-                break;
-            }
-        }
+    fn entity_count(&self) -> usize {
+        self.world.len()
     }
 
-    fn draw(&self) {
-        clear_background(WHITE);
-
-        // 绘制地面
-        draw_line(0.0, GROUND_Y, screen_width(), GROUND_Y, 2.0, BLACK);
-        
-        // 绘制第二条地面线
-        draw_line(0.0, GROUND_Y2, screen_width(), GROUND_Y2, 2.0, DARKGRAY);
-
-        // 绘制恐龙身体
-        draw_rectangle(DINO_X, self.dino_y, DINO_WIDTH, DINO_HEIGHT, GREEN);
-
-        // 绘制恐龙的头部（更宽一些）
-        draw_rectangle(
-            DINO_X - 5.0,
-            self.dino_y - 8.0,
-            DINO_WIDTH + 12.0,
-            15.0,
-            GREEN,
-        );
+    // 当前使用的调色板（已按昼夜混合）
+    fn current_palette(&self) -> Palette {
+        self.palette.at_night(self.night)
+    }
 
-        // 绘制恐龙的眼睛
-        draw_circle(DINO_X + 8.0, self.dino_y - 2.0, 2.5, BLACK);
-
-        // 绘制恐龙的嘴巴
-        draw_line(
-            DINO_X + 18.0,
-            self.dino_y + 2.0,
-            DINO_X + 25.0,
-            self.dino_y + 2.0,
-            2.0,
-            BLACK,
-        );
+    // 绘制场景：远景、地面、恐龙和所有实体，不含界面文字
+    fn draw_world(&self, palette: &Palette) {
+        // 绘制远景（月亮、星星、远山、云朵）
+        self.scenery.draw(palette, self.night);
 
-        // 绘制恐龙的尾巴
-        draw_rectangle(DINO_X - 5.0, self.dino_y + 15.0, 8.0, 4.0, GREEN);
-
-        // 绘制恐龙的腿（简单的线条）
-        if !self.is_jumping {
-            let leg_offset = (get_time() * 10.0).sin() as f32 * 2.0;
-            // 前腿
-            draw_line(
-                DINO_X + 8.0,
-                self.dino_y + DINO_HEIGHT,
-                DINO_X + 8.0,
-                self.dino_y + DINO_HEIGHT + 20.0 + leg_offset,
-                3.0,
-                BLACK,
-            );
-            // 后腿
-            draw_line(
-                DINO_X + 15.0,
-                self.dino_y + DINO_HEIGHT,
-                DINO_X + 15.0,
-                self.dino_y + DINO_HEIGHT + 20.0 - leg_offset,
-                3.0,
-                BLACK,
-            );
+        // 绘制地面和第二条地面线；多车道模式中恐龙所在车道的地面使用主色
+        let lines = self.lanes.max(2);
+        for lane in 0..lines {
+            let color = if lane == self.dino.lane {
+                palette.ground
+            } else {
+                palette.ground_secondary
+            };
+            let y = lane_ground(lane);
+            draw_line(0.0, y, screen_width(), y, 2.0, color);
         }
 
-        // 绘制仙人掌
-        for cactus in &self.cacti {
-            draw_rectangle(cactus.x, cactus.y, CACTUS_WIDTH, CACTUS_HEIGHT, DARKGREEN);
-            // 仙人掌的刺
-            for i in 0..3 {
-                let spike_y = cactus.y + (i as f32 * 20.0) + 10.0;
-                draw_line(cactus.x - 5.0, spike_y, cactus.x, spike_y, 2.0, DARKGREEN);
-                draw_line(
-                    cactus.x + CACTUS_WIDTH,
-                    spike_y,
-                    cactus.x + CACTUS_WIDTH + 5.0,
-                    spike_y,
-                    2.0,
-                    DARKGREEN,
-                );
-            }
+        // 绘制幽灵恐龙和恐龙
+        if let Some(ghost) = &self.ghost {
+            ghost.draw(palette);
         }
+        self.trail.draw(&self.look, palette);
+        let body_color = self.look.body_color(palette);
+        self.dino.draw(
+            &self.look,
+            palette,
+            body_color,
+            palette.dino_detail,
+            self.game_over,
+        );
 
-        // 绘制五角星
-        for star in &self.stars {
-            self.draw_star(
-                star.x + STAR_SIZE / 2.0,
-                star.y + STAR_SIZE / 2.0,
-                STAR_SIZE / 2.0,
-            );
-        }
+        // 绘制仙人掌、五角星和地面小石子，石子合并成一个网格批量提交
+        self.world.draw(palette, &mut self.stone_batch.borrow_mut());
+        self.events.draw(palette, self.font.as_ref());
+    }
 
-        // 绘制地面小石子
-        for stone in &self.stones {
-            // 使用石子自带的颜色
-            draw_circle(stone.x, stone.y, stone.size, stone.color);
-            
-            // 添加高光效果让石子更立体
-            let highlight_color = if stone.y > GROUND_Y && stone.y < GROUND_Y2 {
-                // 彩色石子使用白色高光
-                WHITE
-            } else {
-                // 灰色石子使用浅灰色高光
-                LIGHTGRAY
-            };
-            
-            draw_circle(
-                stone.x - stone.size * 0.3, 
-                stone.y - stone.size * 0.3, 
-                stone.size * 0.3, 
-                highlight_color
-            );
-        }
+    fn draw(&self) {
+        let palette = self.current_palette();
+        clear_background(palette.background);
+        let view = Rect::new(0.0, 0.0, screen_width(), screen_height());
+        self.camera.set(view, None);
+        self.draw_world(&palette);
+        set_default_camera();
 
         // 绘制分数
         let score_text = if self.font.is_some() {
@@ -523,22 +982,101 @@ impl GameState {
                 TextParams {
                     font: Some(font),
                     font_size: 30,
-                    color: BLACK,
+                    color: palette.text,
                     ..Default::default()
                 },
             );
         } else {
-            draw_text(&score_text, 20.0, 30.0, 30.0, BLACK);
+            draw_text(&score_text, 20.0, 30.0, 30.0, palette.text);
+        }
+
+        // 星星连击数
+        let combo = self.scoring.combo();
+        if combo > 1 {
+            let font = self.font.as_ref();
+            let combo_text = if self.font.is_some() {
+                format!("{} 连击", combo)
+            } else {
+                format!("Combo {}", combo)
+            };
+            let x = 40.0 + label_width(font, &score_text, 30);
+            draw_label(font, &combo_text, x, 30.0, 20, palette.success);
+        }
+
+        // 与幽灵的分数差
+        if let Some(ghost) = &self.ghost {
+            let delta = self.score as i64 - ghost.score() as i64;
+            let chinese = self.font.is_some();
+            let mut delta_text = match (delta.signum(), chinese) {
+                (1, true) => format!("幽灵 {}: 领先 {}", ghost.file.player, delta),
+                (1, false) => format!("Ghost {}: {} ahead", ghost.file.player, delta),
+                (-1, true) => format!("幽灵 {}: 落后 {}", ghost.file.player, -delta),
+                (-1, false) => format!("Ghost {}: {} behind", ghost.file.player, -delta),
+                (_, true) => format!("幽灵 {}: 持平", ghost.file.player),
+                (_, false) => format!("Ghost {}: even", ghost.file.player),
+            };
+            if ghost.finished() {
+                delta_text += if chinese {
+                    "（已结束）"
+                } else {
+                    " (finished)"
+                };
+            }
+            let color = match delta.signum() {
+                1 => palette.success,
+                -1 => palette.danger,
+                _ => palette.text_secondary,
+            };
+            draw_label(self.font.as_ref(), &delta_text, 20.0, 60.0, 20, color);
+        } else if let Some(daily) = &self.daily {
+            let daily_text = if self.font.is_some() {
+                format!("每日挑战 {}", daily.date)
+            } else {
+                format!("Daily challenge {}", daily.date)
+            };
+            draw_label(
+                self.font.as_ref(),
+                &daily_text,
+                20.0,
+                60.0,
+                20,
+                palette.text_secondary,
+            );
+        }
+
+        // 自动游戏提示
+        if let (Some(bot), false) = (&self.bot, self.attract) {
+            let autoplay_text = if self.font.is_some() {
+                format!("自动游戏中 ({}) - 按 A 接管", bot.name())
+            } else {
+                format!("Autoplay ({}) - press A to take over", bot.name())
+            };
+            draw_label(
+                self.font.as_ref(),
+                &autoplay_text,
+                20.0,
+                85.0,
+                20,
+                palette.text_secondary,
+            );
+            bot.draw(&palette, self.font.as_ref());
+        }
+
+        // 生效中的道具和剩余时间
+        if !self.attract {
+            powerup::draw_timers(&self.power_ups, &palette, self.font.as_ref(), 115.0);
         }
 
         // 绘制数学题界面
         if self.showing_math_question {
-            self.draw_math_question();
+            self.draw_math_question(&palette.faded(self.quiz_alpha()));
+        } else if self.networked && (self.game_over || self.attract) {
+            // 局域网对战的大厅和结束画面由 lan 模块绘制
         } else if self.game_over {
             let revive_text = if self.font.is_some() {
-                "游戏结束! 按 R 重新开始"
+                "游戏结束! 按 R 或点击屏幕重新开始"
             } else {
-                "Game Over! Press R to restart"
+                "Game Over! Press R or tap to restart"
             };
 
             let text_size = if let Some(font) = &self.font {
@@ -555,7 +1093,7 @@ impl GameState {
                     TextParams {
                         font: Some(font),
                         font_size: 30,
-                        color: RED,
+                        color: palette.danger,
                         ..Default::default()
                     },
                 );
@@ -565,15 +1103,42 @@ impl GameState {
                     screen_width() / 2.0 - text_size.width / 2.0,
                     screen_height() / 2.0,
                     30.0,
-                    RED,
+                    palette.danger,
                 );
             }
+
+            let font = self.font.as_ref();
+            let menu_text = match (self.can_upload(), self.font.is_some()) {
+                (true, true) => "按 ESC 返回标题画面，按 D 开始每日挑战，按 U 上传成绩",
+                (true, false) => {
+                    "ESC for title screen, D for the daily challenge, U to submit score"
+                }
+                (false, true) => "按 ESC 返回标题画面，按 D 开始每日挑战",
+                (false, false) => "ESC for title screen, D for the daily challenge",
+            };
+            draw_label(
+                font,
+                menu_text,
+                screen_width() / 2.0 - label_width(font, menu_text, 20) / 2.0,
+                screen_height() / 2.0 + 30.0,
+                20,
+                palette.text_secondary,
+            );
+            if let Some(upload) = &self.upload {
+                upload.draw(&palette, font, screen_height() / 2.0 - 40.0);
+            }
+            if let Some(daily) = &self.daily {
+                daily.draw_summary(&palette, font, screen_height() / 2.0 + 70.0);
+            }
+        } else if self.attract {
+            self.draw_title(&palette);
         } else {
             // 绘制操作提示
-            let help_text = if self.font.is_some() {
-                "按空格键跳跃"
-            } else {
-                "Press SPACE to jump"
+            let help_text = match (self.lanes > 1, self.font.is_some()) {
+                (false, true) => "按空格键跳跃，下方向键下蹲，P 暂停，Tab 设置",
+                (false, false) => "SPACE to jump, DOWN to duck, P to pause, TAB for settings",
+                (true, true) => "按空格键跳跃，S 下蹲，↑↓ 换道，P 暂停，Tab 设置",
+                (true, false) => "SPACE to jump, S to duck, UP/DOWN to switch lanes, P to pause",
             };
 
             if let Some(font) = &self.font {
@@ -584,33 +1149,140 @@ impl GameState {
                     TextParams {
                         font: Some(font),
                         font_size: 20,
-                        color: GRAY,
+                        color: palette.text_secondary,
                         ..Default::default()
                     },
                 );
             } else {
-                draw_text(help_text, 20.0, screen_height() - 20.0, 20.0, GRAY);
+                draw_text(
+                    help_text,
+                    20.0,
+                    screen_height() - 20.0,
+                    20.0,
+                    palette.text_secondary,
+                );
+            }
+
+            if self.paused {
+                let paused_text = if self.font.is_some() {
+                    "已暂停 - 按 P 继续"
+                } else {
+                    "Paused - press P to resume"
+                };
+                let font = self.font.as_ref();
+                draw_label(
+                    font,
+                    paused_text,
+                    screen_width() / 2.0 - label_width(font, paused_text, 30) / 2.0,
+                    screen_height() / 2.0,
+                    30,
+                    palette.input,
+                );
             }
         }
     }
 
-    fn draw_math_question(&self) {
+    // 标题画面：机器人在背景中演示，提示玩家开始游戏
+    fn draw_title(&self, palette: &Palette) {
+        let font = self.font.as_ref();
+        let (title, start_text, daily_text, versus_text, achievements_text, autoplay_text) =
+            if self.font.is_some() {
+                (
+                    "小恐龙快跑",
+                    "按空格键或点击屏幕开始",
+                    "按 D 开始每日挑战",
+                    "按 V 开始双人赛跑",
+                    "按 H 查看成就，按 C 更换外观",
+                    "游戏中按 A 开关自动游戏",
+                )
+            } else {
+                (
+                    "Chrome Dino",
+                    "Press SPACE or tap to start",
+                    "Press D for the daily challenge",
+                    "Press V for a two-player race",
+                    "Press H for achievements, C to customize",
+                    "Press A in game to toggle autoplay",
+                )
+            };
+        let center_x = screen_width() / 2.0;
+        let center_y = screen_height() / 2.0 - 60.0;
+        draw_label(
+            font,
+            title,
+            center_x - label_width(font, title, 50) / 2.0,
+            center_y,
+            50,
+            palette.text,
+        );
+        draw_label(
+            font,
+            start_text,
+            center_x - label_width(font, start_text, 30) / 2.0,
+            center_y + 50.0,
+            30,
+            palette.input,
+        );
+        draw_label(
+            font,
+            daily_text,
+            center_x - label_width(font, daily_text, 20) / 2.0,
+            center_y + 85.0,
+            20,
+            palette.text_secondary,
+        );
+        draw_label(
+            font,
+            versus_text,
+            center_x - label_width(font, versus_text, 20) / 2.0,
+            center_y + 110.0,
+            20,
+            palette.text_secondary,
+        );
+        draw_label(
+            font,
+            achievements_text,
+            center_x - label_width(font, achievements_text, 20) / 2.0,
+            center_y + 135.0,
+            20,
+            palette.text_secondary,
+        );
+        draw_label(
+            font,
+            autoplay_text,
+            center_x - label_width(font, autoplay_text, 20) / 2.0,
+            center_y + 160.0,
+            20,
+            palette.text_secondary,
+        );
+    }
+
+    fn draw_math_question(&self, palette: &Palette) {
         if let Some(question) = &self.math_question {
             // 绘制半透明背景
-            draw_rectangle(
-                0.0,
-                0.0,
-                screen_width(),
-                screen_height(),
-                Color::new(0.0, 0.0, 0.0, 0.7),
-            );
+            draw_rectangle(0.0, 0.0, screen_width(), screen_height(), palette.overlay);
 
-            let center_x = screen_width() / 2.0;
+            // 题目区域在左，屏幕数字键盘在右
+            let center_x = screen_width() / 2.0 - keypad::PANEL_WIDTH / 2.0;
             let center_y = screen_height() / 2.0;
+            let panel_width = 400.0 + keypad::PANEL_WIDTH;
 
             // 绘制题目背景框
-            draw_rectangle(center_x - 200.0, center_y - 150.0, 400.0, 300.0, WHITE);
-            draw_rectangle_lines(center_x - 200.0, center_y - 150.0, 400.0, 300.0, 3.0, BLACK);
+            draw_rectangle(
+                center_x - 200.0,
+                center_y - 150.0,
+                panel_width,
+                300.0,
+                palette.panel,
+            );
+            draw_rectangle_lines(
+                center_x - 200.0,
+                center_y - 150.0,
+                panel_width,
+                300.0,
+                3.0,
+                palette.panel_border,
+            );
 
             let title_text = if self.font.is_some() {
                 "答题复活！"
@@ -628,7 +1300,7 @@ impl GameState {
                     TextParams {
                         font: Some(font),
                         font_size: 30,
-                        color: BLUE,
+                        color: palette.title,
                         ..Default::default()
                     },
                 );
@@ -639,7 +1311,7 @@ impl GameState {
                     center_x - title_size.width / 2.0,
                     center_y - 100.0,
                     30.0,
-                    BLUE,
+                    palette.title,
                 );
             }
 
@@ -650,7 +1322,7 @@ impl GameState {
                 center_x - question_size.width / 2.0,
                 center_y - 40.0,
                 40.0,
-                BLACK,
+                palette.text,
             );
 
             // 绘制输入框边框
@@ -664,7 +1336,7 @@ impl GameState {
                 input_box_width,
                 input_box_height,
                 2.0,
-                GRAY,
+                palette.text_secondary,
             );
 
             // 绘制输入框
@@ -693,12 +1365,12 @@ impl GameState {
                     TextParams {
                         font: Some(font),
                         font_size: 30,
-                        color: DARKBLUE,
+                        color: palette.input,
                         ..Default::default()
                     },
                 );
             } else {
-                draw_text(&input_text, text_x, text_y, 30.0, DARKBLUE);
+                draw_text(&input_text, text_x, text_y, 30.0, palette.input);
             }
 
             // 绘制提示
@@ -717,7 +1389,7 @@ impl GameState {
                     TextParams {
                         font: Some(font),
                         font_size: 20,
-                        color: GRAY,
+                        color: palette.text_secondary,
                         ..Default::default()
                     },
                 );
@@ -728,67 +1400,545 @@ impl GameState {
                     center_x - hint_size.width / 2.0,
                     center_y + 80.0,
                     20.0,
-                    GRAY,
+                    palette.text_secondary,
                 );
             }
+
+            // 绘制屏幕数字键盘
+            keypad::draw(self.font.as_ref(), palette);
+        }
+    }
+}
+
+// 新的一局要与之赛跑的录像：--ghost 指定的文件，或开启幽灵赛跑时当前车道数下的个人最佳
+fn ghost_to_race(
+    shared: &Option<GhostFile>,
+    settings: &Settings,
+    profile: &Profile,
+) -> Option<GhostFile> {
+    shared.clone().or_else(|| {
+        profile
+            .best(settings.lanes)
+            .cloned()
+            .filter(|_| settings.ghost)
+    })
+}
+
+fn main() {
+    let options = Options::parse();
+    // 强化学习环境：通过标准输入输出驱动，不打开窗口
+    if options.env {
+        env::serve();
+        return;
+    }
+    // 神经进化训练：不打开窗口，每代结束后保存最优基因组
+    if let Some(generations) = options.train_generations {
+        train::run(&TrainConfig {
+            generations,
+            population: options.population,
+            seeds: options.train_seeds,
+            seconds: options.simulate_seconds,
+            output: options
+                .genome
+                .clone()
+                .unwrap_or_else(|| DEFAULT_GENOME_PATH.to_string()),
+        });
+        return;
+    }
+    // 指定基因组时由神经网络机器人游戏
+    let genome = match &options.genome {
+        Some(path) => match Genome::load(path) {
+            Ok(genome) => Some(genome),
+            Err(err) => {
+                eprintln!("Error: failed to load genome {}: {}", path, err);
+                return;
+            }
+        },
+        None => None,
+    };
+    // 无窗口模拟：用机器人批量跑若干局并输出统计，用于调整平衡
+    if let Some(runs) = options.simulate_runs {
+        simulate::run(runs, options.simulate_seconds, || match &genome {
+            Some(genome) => Box::new(NeuroBot::new(genome.clone())),
+            None => bot::by_name(&options.bot).expect("bot name is checked by cli"),
+        });
+        return;
+    }
+    macroquad::Window::new("Chrome Dino Game", run(options, genome));
+}
+
+async fn run(options: Options, genome: Option<Genome>) {
+    let mut game_state = GameState::new(EntityDefs::load());
+    let mut defs_watcher = DefsWatcher::new();
+    game_state.load_font().await;
+    let audio = Audio::load().await;
+    let sounds = SoundQueue::default();
+    let particles = ParticleToggle::default();
+    game_state.subscribe_feedback(&sounds, &particles);
+    let stats = StatQueue::default();
+    game_state
+        .events
+        .subscribe(Box::new(StatTracker::new(&stats)));
+
+    let mut settings = Settings::load();
+    let themes = load_themes();
+    game_state.palette = find_theme(&themes, &settings.theme).palette.clone();
+    game_state.lane_setting = settings.lanes;
+    particles.set(settings.particles);
+    game_state.camera.intensity = settings.shake_intensity();
+    let mut controls = Controls::new(Bindings::from_config(&settings.bindings));
+    let mut profile = Profile::load(options.profile.as_deref().unwrap_or(&settings.profile));
+    game_state.look = profile.look.clone();
+    // --ghost 指定的录像优先于个人最佳
+    let shared_ghost = options.ghost.as_deref().and_then(|path| {
+        GhostFile::load(Path::new(path))
+            .map_err(|err| println!("Warning: failed to load ghost {}: {}", path, err))
+            .ok()
+    });
+    let mut settings_menu: Option<SettingsMenu> = None;
+    // 标题画面按 H 打开的成就一览，按 C 打开的外观定制界面
+    let mut showing_achievements = false;
+    let mut customizing: Option<CustomizeScreen> = None;
+    let mut pointer = Pointer::new();
+    // 本地双人赛跑，进行中时代替单人游戏
+    let mut versus: Option<Versus> = None;
+    // 局域网对战：--host 或 --join 时直接进入大厅，以档案名作为玩家名
+    let session = match (&options.host, &options.join) {
+        (Some(address), _) => Some(Session::host(address, &profile.name)),
+        (None, Some(address)) => Some(Session::join(address, &profile.name)),
+        (None, None) => None,
+    };
+    let mut lan = match session {
+        Some(Ok(session)) => Some(LanRace::new(session)),
+        Some(Err(err)) => {
+            println!("Warning: failed to start the LAN race: {}", err);
+            None
         }
+        None => None,
+    };
+    game_state.networked = lan.is_some();
+
+    // 尚未推进的时间，以及还没有被任何一步处理的按键事件
+    let mut accumulator = 0.0;
+    let mut carried_actions = ActionState::default();
+
+    let mut bench = options.bench_frames.map(Bench::new);
+    game_state.invincible = bench.is_some();
+    // 基准测试不重新开始，直接使用当前的窗口宽度
+    game_state.window_width = screen_width();
+    game_state.view_width = game_state.window_width;
+    if let Some(genome) = genome {
+        // 直接开始一局，由神经网络机器人游戏并显示网络的激活值
+        game_state.start_game();
+        game_state.bot = Some(Box::new(NeuroBot::new(genome)));
+    } else if bench.is_none() {
+        game_state.start_attract();
     }
 
-    fn draw_star(&self, center_x: f32, center_y: f32, radius: f32) {
-        let points = 5;
-        let outer_radius = radius;
-        let inner_radius = radius * 0.4;
+    loop {
+        let dt = get_frame_time();
+        // 窗口宽度从下一局开始生效；与幽灵赛跑或局域网对战时使用录像或主机的视野宽度
+        game_state.window_width = screen_width();
+        controls.update();
+        if let Some(defs) = defs_watcher.poll(dt) {
+            if let Some(race) = &mut versus {
+                race.set_defs(&defs);
+            }
+            game_state.reload_defs(defs);
+        }
+        let mut update_time = Duration::ZERO;
+
+        if let Some(menu) = &mut settings_menu {
+            // 设置菜单打开时游戏暂停，关闭时保存设置
+            let open = menu.update(&mut controls, &mut settings, &themes);
+            game_state.palette = find_theme(&themes, &settings.theme).palette.clone();
+            game_state.lane_setting = settings.lanes;
+            particles.set(settings.particles);
+            game_state.camera.intensity = settings.shake_intensity();
+            if let Some(race) = &mut versus {
+                race.set_palette(&game_state.palette);
+                race.set_shake(settings.shake_intensity());
+            }
+            if !open {
+                settings.bindings = controls.bindings.to_config();
+                settings.save();
+                settings_menu = None;
+                // 标题画面的演示立即换成新的车道数，进行中的一局从下一局开始生效
+                if game_state.attract && game_state.lanes != settings.lanes {
+                    game_state.start_attract();
+                }
+            }
+        } else if let Some(screen) = &mut customizing {
+            // 关闭时保存外观，还没解锁的部分不会保存
+            if !screen.update(dt) {
+                profile.look = screen.look(&profile.achievements);
+                profile.save_look();
+                game_state.look = profile.look.clone();
+                customizing = None;
+            }
+        } else if showing_achievements {
+            let actions = controls.actions();
+            if actions.pressed(Action::Cancel) || actions.pressed(Action::Achievements) {
+                showing_achievements = false;
+            }
+        } else if let Some(race) = &mut versus {
+            // 双人赛跑：每名玩家的按键由 Versus 按各自的绑定读取，ESC 返回标题画面
+            if is_key_pressed(KeyCode::Tab) {
+                settings_menu = Some(SettingsMenu::new());
+            }
+            let actions = controls.actions();
+            if actions.pressed(Action::Cancel) {
+                versus = None;
+                game_state.start_attract();
+            } else {
+                let update_start = Instant::now();
+                race.update(dt, &controls, &actions);
+                update_time = update_start.elapsed();
+            }
+        } else {
+            if is_key_pressed(KeyCode::Tab) && !game_state.showing_math_question {
+                settings_menu = Some(SettingsMenu::new());
+            }
+
+            // 多车道模式中 ↑ ↓ 只用来换道
+            let mut actions = if game_state.lanes > 1 {
+                controls.actions_for(&controls.bindings.for_lanes())
+            } else {
+                controls.actions()
+            };
+            let pointer_mode = if game_state.attract {
+                // 标题画面上点击屏幕会触发跳跃，用来开始游戏
+                PointerMode::Playing
+            } else if game_state.showing_math_question {
+                PointerMode::Quiz
+            } else if game_state.game_over {
+                PointerMode::GameOver
+            } else if game_state.paused {
+                PointerMode::Paused
+            } else {
+                PointerMode::Playing
+            };
+            pointer.apply(pointer_mode, &mut actions);
+            actions.merge_pressed(&carried_actions);
+
+            // 局域网对战中开始比赛和离开由 lan 模块处理，不能开启自动游戏；
+            // 否则在标题画面按跳跃或确认开始游戏，按 D 开始每日挑战，按 H 查看成就，按 C 更换外观；游戏中按 A 开关自动游戏
+            if let Some(race) = &mut lan {
+                if !race.update(&mut game_state, &mut actions) {
+                    lan = None;
+                    game_state.networked = false;
+                    game_state.start_attract();
+                }
+            } else if game_state.attract {
+                if actions.pressed(Action::Jump) || actions.pressed(Action::Confirm) {
+                    game_state.start_game();
+                    if let Some(file) = ghost_to_race(&shared_ghost, &settings, &profile) {
+                        game_state.race(file);
+                    }
+                    actions.clear_pressed();
+                } else if actions.pressed(Action::Daily) {
+                    game_state.start_daily(DailyChallenge::start(&profile));
+                    actions.clear_pressed();
+                } else if actions.pressed(Action::Versus) {
+                    let mut race = Versus::new(
+                        &game_state.defs,
+                        screen_width(),
+                        game_state.font.clone(),
+                        &game_state.palette,
+                        &particles,
+                    );
+                    race.set_shake(settings.shake_intensity());
+                    versus = Some(race);
+                    actions.clear_pressed();
+                } else if actions.pressed(Action::Achievements) {
+                    showing_achievements = true;
+                    actions.clear_pressed();
+                } else if actions.pressed(Action::Customize) {
+                    customizing = Some(CustomizeScreen::new(&profile.look));
+                    actions.clear_pressed();
+                }
+            } else if actions.pressed(Action::Autoplay) && !game_state.showing_math_question {
+                game_state.toggle_autoplay();
+            }
 
-        let mut vertices = Vec::new();
+            // 重新开始游戏（每日挑战中继续使用当天的种子）；局域网对战中由主机开始下一局
+            let solo_game_over = game_state.game_over && lan.is_none();
+            if solo_game_over && actions.pressed(Action::Restart) {
+                game_state.reset();
+                if game_state.daily.is_none() {
+                    if let Some(file) = ghost_to_race(&shared_ghost, &settings, &profile) {
+                        game_state.race(file);
+                    }
+                }
+            } else if solo_game_over && !game_state.showing_math_question {
+                // 游戏结束画面：按 D 开始每日挑战，按 U 上传成绩，按 ESC 返回标题画面
+                if actions.pressed(Action::Daily) {
+                    game_state.start_daily(DailyChallenge::start(&profile));
+                } else if actions.pressed(Action::Upload) && game_state.can_upload() {
+                    let run = GhostFile::new(&profile.name, &game_state);
+                    game_state.upload = Some(Upload::start(&settings.leaderboard_url, run));
+                } else if actions.pressed(Action::Cancel) {
+                    game_state.start_attract();
+                }
+            }
 
-        for i in 0..(points * 2) {
-            let angle = (i as f32 * std::f32::consts::PI) / points as f32;
-            let r = if i % 2 == 0 {
-                outer_radius
+            // 按固定步长推进，按键事件只交给这一帧的第一步
+            let update_start = Instant::now();
+            // 慢动作道具生效时放慢推进，逻辑仍按固定步长进行
+            accumulator = (accumulator + dt * game_state.time_scale())
+                .min(FIXED_DT * MAX_STEPS_PER_FRAME as f32);
+            let mut stepped = false;
+            while accumulator >= FIXED_DT {
+                game_state.update(&actions);
+                actions.clear_pressed();
+                accumulator -= FIXED_DT;
+                stepped = true;
+            }
+            carried_actions = if stepped {
+                ActionState::default()
             } else {
-                inner_radius
+                actions
             };
-            let x = center_x + r * angle.cos();
-            let y = center_y + r * angle.sin();
-            vertices.push(Vec2::new(x, y));
+            update_time = update_start.elapsed();
+            if let Some(race) = &mut lan {
+                race.send_state(&game_state);
+            }
+            if let Some(upload) = &mut game_state.upload {
+                upload.poll();
+            }
+
+            // 成就只统计计入个人最佳的对局
+            let counts_for_best = game_state.counts_for_best();
+            for stat in stats.borrow_mut().drain(..) {
+                if counts_for_best {
+                    profile.achievements.record(stat);
+                }
+            }
+
+            // 一局结束：保存成就进度，超过个人最佳时保存录像，每日挑战记录当天的成绩和连续天数
+            if game_state.take_finished_run() {
+                profile.achievements.save();
+                if counts_for_best {
+                    profile.submit_run(GhostFile::new(&profile.name, &game_state));
+                    if let Some(daily) = &mut game_state.daily {
+                        daily.submit(&profile.name, game_state.score);
+                    }
+                }
+            }
         }
 
-        // 绘制五角星的填充
-        for i in 1..(vertices.len() - 1) {
-            draw_triangle(vertices[0], vertices[i], vertices[i + 1], GOLD);
+        // 标题画面的演示不播放音效
+        for effect in sounds.borrow_mut().drain(..) {
+            if !game_state.attract {
+                audio.play(effect);
+            }
+        }
+        if let Some(race) = &mut versus {
+            for effect in race.take_sounds() {
+                audio.play(effect);
+            }
+        }
+        let draw_start = Instant::now();
+        match &versus {
+            Some(race) => race.draw(),
+            None => game_state.draw(),
+        }
+        if let Some(race) = &lan {
+            race.draw(&game_state);
+        }
+        let palette = game_state.current_palette();
+        let font = game_state.font.as_ref();
+        if showing_achievements {
+            profile.achievements.draw_screen(&palette, font);
+        }
+        if let Some(screen) = &customizing {
+            screen.draw(&palette, font, &profile.achievements);
+        }
+        profile.achievements.step_toasts(dt);
+        profile.achievements.draw_toast(&palette, font);
+        let draw_time = draw_start.elapsed();
+
+        if let Some(bench) = &mut bench {
+            bench.record(update_time, draw_time);
+            bench.draw_overlay(
+                game_state.entity_count(),
+                &game_state.current_palette(),
+                game_state.font.as_ref(),
+            );
+            if bench.finished() {
+                bench.report();
+                break;
+            }
         }
 
-        // 绘制五角星的边框
-        for i in 0..vertices.len() {
-            let next = (i + 1) % vertices.len();
-            draw_line(
-                vertices[i].x,
-                vertices[i].y,
-                vertices[next].x,
-                vertices[next].y,
-                2.0,
-                ORANGE,
+        if let Some(menu) = &settings_menu {
+            menu.draw(
+                &controls.bindings,
+                &settings,
+                &game_state.current_palette(),
+                game_state.font.as_ref(),
             );
         }
+
+        next_frame().await;
     }
 }
 
-#[macroquad::main("Chrome Dino Game")]
-async fn main() {
-    let mut game_state = GameState::new();
-    game_state.load_font().await;
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::entity::Sprite;
+    use crate::ghost::tests::scripted_jumps;
+
+    const SEEDS: u64 = 2000;
+    // 每个种子模拟 45 秒
+    const TICKS: u32 = (45.0 / FIXED_DT) as u32;
+
+    #[test]
+    fn perfect_bot_clears_every_seed() {
+        let mut max_score = 0;
+        for seed in 0..SEEDS {
+            let mut game = GameState::new(EntityDefs::default());
+            game.reseed(seed);
+            let mut bot = PlannerBot::default();
+            for _ in 0..TICKS {
+                let input = bot.next_input(&game.observe()).unwrap_or_else(|| {
+                    panic!("seed {}: impossible layout at tick {}", seed, game.ticks)
+                });
+                game.update(&ActionState::from(input));
+                assert!(!game.game_over, "seed {}: hit at tick {}", seed, game.ticks);
+            }
+            max_score = max_score.max(game.score);
+        }
+        // 确认模拟足够长，带最低分数要求的组合也出现过
+        assert!(max_score >= 150, "max score {}", max_score);
+    }
 
-    loop {
-        let dt = get_frame_time();
+    #[test]
+    fn every_lane_is_fair_on_its_own() {
+        // 三车道模式中一开始换到中间或最下面的车道后一直不动，完美机器人只看本车道也能一直躲过
+        for seed in 0..SEEDS / 10 {
+            let mut game = GameState::new(EntityDefs::default());
+            game.set_lanes(MAX_LANES);
+            game.reseed(seed);
+            let mut switch = ActionState::default();
+            switch.press(Action::LaneDown);
+            let lane = 1 + seed as usize % (MAX_LANES - 1);
+            let mut bot = PlannerBot::default();
+            for _ in 0..TICKS {
+                let input = bot.next_input(&game.observe()).unwrap_or_else(|| {
+                    panic!("seed {}: impossible layout at tick {}", seed, game.ticks)
+                });
+                let mut actions = ActionState::from(input);
+                if game.dino.lane < lane {
+                    actions.merge_pressed(&switch);
+                }
+                game.update(&actions);
+                assert!(!game.game_over, "seed {}: hit at tick {}", seed, game.ticks);
+            }
+            assert_eq!(game.dino.lane, lane);
+            assert!(game.world.iter().any(|entity| entity.lane != lane));
+        }
+    }
 
-        // 重新开始游戏
-        if game_state.game_over && is_key_pressed(KeyCode::R) {
-            game_state.reset();
+    #[test]
+    fn reloading_defs_rebuilds_every_lanes_timers() {
+        let mut game = GameState::new(EntityDefs::default());
+        game.set_lanes(MAX_LANES);
+        for _ in 0..120 {
+            game.update(&ActionState::default());
         }
+        let mut defs = EntityDefs::default();
+        defs.spawners.pop();
+        let spawners = defs.spawners.len();
+        game.set_defs(defs);
+        assert_eq!(game.spawn_timers.len(), spawners * MAX_LANES);
+        assert!(game.spawn_timers.iter().all(|&timer| timer == 0.0));
+        // 机器人看到的是自己车道上新定义的计时器
+        game.dino.switch_lane(MAX_LANES - 1);
+        assert_eq!(game.observe().spawn_timers.len(), spawners);
+        game.update(&ActionState::default());
+    }
 
-        game_state.update(dt);
-        game_state.draw();
+    #[test]
+    fn window_size_and_reloaded_defs_apply_from_the_next_run() {
+        let mut game = GameState::new(EntityDefs::default());
+        game.window_width = 1200.0;
+        game.reset();
+        assert_eq!(game.view_width, 1200.0);
+
+        let mut defs = EntityDefs::default();
+        defs.spawners.pop();
+        let spawners = defs.spawners.len();
+        game.window_width = 640.0;
+        game.reload_defs(defs);
+        game.update(&ActionState::default());
+        assert_eq!(game.view_width, 1200.0);
+        assert_eq!(game.defs.spawners.len(), spawners + 1);
+
+        game.reset();
+        assert_eq!(game.view_width, 640.0);
+        assert_eq!(game.defs.spawners.len(), spawners);
+    }
 
-        next_frame().await;
+    #[test]
+    fn daily_course_does_not_depend_on_window_width() {
+        let date = daily::Date { days: 20_000 };
+        let course = |window_width: f32| {
+            let mut game = GameState::new(EntityDefs::default());
+            game.window_width = window_width;
+            game.invincible = true;
+            game.start_daily(daily::tests::challenge(date));
+            let mut spawns = Vec::new();
+            for _ in 0..60 * 30 {
+                game.update(&ActionState::default());
+                let tick = game.ticks;
+                spawns.extend(
+                    game.spawned
+                        .iter()
+                        .map(|entity| (tick, entity.pos, entity.lane, entity.sprite)),
+                );
+            }
+            spawns
+        };
+        let narrow = course(640.0);
+        assert!(!narrow.is_empty());
+        assert!(narrow == course(1920.0));
+    }
+
+    #[test]
+    fn daily_course_does_not_depend_on_how_the_player_runs() {
+        // 一名玩家站着不动但无敌，另一名按固定节奏起跳，撞上障碍物就立即复活并拾取道具
+        let date = daily::Date { days: 20_000 };
+        let course = |careful: bool| {
+            let mut game = GameState::new(EntityDefs::default());
+            game.invincible = !careful;
+            game.start_daily(daily::tests::challenge(date));
+            let mut spawns = Vec::new();
+            while game.ticks < 60 * 60 {
+                if game.game_over {
+                    game.revive();
+                    game.activate_power_up("high_jump");
+                }
+                let actions = if careful {
+                    scripted_jumps(game.ticks)
+                } else {
+                    ActionState::default()
+                };
+                game.update(&actions);
+                let tick = game.ticks;
+                spawns.extend(
+                    game.spawned
+                        .iter()
+                        .map(|entity| (tick, entity.pos, entity.lane, entity.sprite)),
+                );
+            }
+            (spawns, game.recording.revives.len())
+        };
+        let (idle, _) = course(false);
+        let (jumping, revives) = course(true);
+        assert!(revives > 0);
+        assert!(idle.iter().any(|(.., sprite)| *sprite == Sprite::Cactus));
+        assert!(idle == jumping);
     }
 }
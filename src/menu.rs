@@ -0,0 +1,333 @@
+use macroquad::prelude::*;
+
+use crate::input::{Action, Bindings, Controls};
+use crate::settings::{Settings, MAX_SCREEN_SHAKE};
+use crate::theme::{Palette, Theme};
+use crate::ui::{draw_label, label_width};
+use crate::MAX_LANES;
+
+// 面板中同时显示的行数，行数更多时随选中行滚动
+const VISIBLE_ROWS: usize = 18;
+// 屏幕震动强度每次调整的幅度
+const SHAKE_STEP: f32 = 0.25;
+
+// 设置菜单中的一行
+#[derive(Clone, Copy, PartialEq)]
+enum Row {
+    Theme,
+    Ghost,
+    Lanes,
+    Particles,
+    ScreenShake,
+    ReducedMotion,
+    Binding(Action),
+    ResetBindings,
+}
+
+// 设置菜单：切换主题、查看和修改按键绑定
+// 菜单本身使用固定的方向键/回车/退格/ESC 操作，避免改键后无法再进入菜单
+pub struct SettingsMenu {
+    rows: Vec<Row>,
+    selected: usize,
+    capturing: bool,
+}
+
+impl SettingsMenu {
+    pub fn new() -> Self {
+        let mut rows = vec![
+            Row::Theme,
+            Row::Ghost,
+            Row::Lanes,
+            Row::Particles,
+            Row::ScreenShake,
+            Row::ReducedMotion,
+        ];
+        rows.extend(Action::all().into_iter().map(Row::Binding));
+        rows.push(Row::ResetBindings);
+        Self {
+            rows,
+            selected: 0,
+            capturing: false,
+        }
+    }
+
+    // 返回 false 表示菜单已关闭
+    pub fn update(
+        &mut self,
+        controls: &mut Controls,
+        settings: &mut Settings,
+        themes: &[Theme],
+    ) -> bool {
+        let row = self.rows[self.selected];
+
+        if self.capturing {
+            if is_key_pressed(KeyCode::Escape) {
+                self.capturing = false;
+            } else if let Some(binding) = controls.captured_binding() {
+                if let Row::Binding(action) = row {
+                    controls.bindings.add(action, binding);
+                }
+                self.capturing = false;
+            }
+            return true;
+        }
+
+        let count = self.rows.len();
+        if is_key_pressed(KeyCode::Up) {
+            self.selected = (self.selected + count - 1) % count;
+        }
+        if is_key_pressed(KeyCode::Down) {
+            self.selected = (self.selected + 1) % count;
+        }
+
+        match row {
+            Row::Theme => {
+                let step = if is_key_pressed(KeyCode::Left) {
+                    Some(themes.len() - 1)
+                } else if is_key_pressed(KeyCode::Right) || is_key_pressed(KeyCode::Enter) {
+                    Some(1)
+                } else {
+                    None
+                };
+                if let Some(step) = step {
+                    let current = themes
+                        .iter()
+                        .position(|theme| theme.name == settings.theme)
+                        .unwrap_or(0);
+                    settings.theme = themes[(current + step) % themes.len()].name.clone();
+                }
+            }
+            Row::Ghost => {
+                if is_key_pressed(KeyCode::Left)
+                    || is_key_pressed(KeyCode::Right)
+                    || is_key_pressed(KeyCode::Enter)
+                {
+                    settings.ghost = !settings.ghost;
+                }
+            }
+            Row::Particles => {
+                if is_key_pressed(KeyCode::Left)
+                    || is_key_pressed(KeyCode::Right)
+                    || is_key_pressed(KeyCode::Enter)
+                {
+                    settings.particles = !settings.particles;
+                }
+            }
+            Row::ScreenShake => {
+                // 在 0..=MAX_SCREEN_SHAKE 之间按 SHAKE_STEP 循环
+                let count = (MAX_SCREEN_SHAKE / SHAKE_STEP) as usize + 1;
+                let step = if is_key_pressed(KeyCode::Left) {
+                    Some(count - 1)
+                } else if is_key_pressed(KeyCode::Right) || is_key_pressed(KeyCode::Enter) {
+                    Some(1)
+                } else {
+                    None
+                };
+                if let Some(step) = step {
+                    let current = (settings.screen_shake / SHAKE_STEP).round() as usize;
+                    settings.screen_shake = ((current + step) % count) as f32 * SHAKE_STEP;
+                }
+            }
+            Row::ReducedMotion => {
+                if is_key_pressed(KeyCode::Left)
+                    || is_key_pressed(KeyCode::Right)
+                    || is_key_pressed(KeyCode::Enter)
+                {
+                    settings.reduced_motion = !settings.reduced_motion;
+                }
+            }
+            Row::Lanes => {
+                // 在 1..=MAX_LANES 之间循环，从下一局开始生效
+                let step = if is_key_pressed(KeyCode::Left) {
+                    Some(MAX_LANES - 1)
+                } else if is_key_pressed(KeyCode::Right) || is_key_pressed(KeyCode::Enter) {
+                    Some(1)
+                } else {
+                    None
+                };
+                if let Some(step) = step {
+                    settings.lanes = (settings.lanes - 1 + step) % MAX_LANES + 1;
+                }
+            }
+            Row::Binding(action) => {
+                if is_key_pressed(KeyCode::Enter) {
+                    self.capturing = true;
+                }
+                if is_key_pressed(KeyCode::Backspace) || is_key_pressed(KeyCode::Delete) {
+                    controls.bindings.clear(action);
+                }
+            }
+            Row::ResetBindings => {
+                if is_key_pressed(KeyCode::Enter) {
+                    controls.bindings = Bindings::defaults();
+                }
+            }
+        }
+
+        !(is_key_pressed(KeyCode::Escape) || is_key_pressed(KeyCode::Tab))
+    }
+
+    pub fn draw(
+        &self,
+        bindings: &Bindings,
+        settings: &Settings,
+        palette: &Palette,
+        font: Option<&Font>,
+    ) {
+        let chinese = font.is_some();
+
+        draw_rectangle(0.0, 0.0, screen_width(), screen_height(), palette.overlay);
+
+        let panel_w = 560.0;
+        let panel_h = 520.0;
+        let panel_x = screen_width() / 2.0 - panel_w / 2.0;
+        let panel_y = screen_height() / 2.0 - panel_h / 2.0;
+        draw_rectangle(panel_x, panel_y, panel_w, panel_h, palette.panel);
+        draw_rectangle_lines(
+            panel_x,
+            panel_y,
+            panel_w,
+            panel_h,
+            3.0,
+            palette.panel_border,
+        );
+
+        let title = if chinese { "设置" } else { "Settings" };
+        draw_label(
+            font,
+            title,
+            screen_width() / 2.0 - label_width(font, title, 28) / 2.0,
+            panel_y + 38.0,
+            28,
+            palette.title,
+        );
+
+        let row_h = 22.0;
+        let first_row_y = panel_y + 70.0;
+        let scroll = (self.selected + 1).saturating_sub(VISIBLE_ROWS);
+        let visible = self.rows.iter().enumerate().skip(scroll).take(VISIBLE_ROWS);
+        for (index, row) in visible {
+            let y = first_row_y + (index - scroll) as f32 * row_h;
+            if index == self.selected {
+                draw_rectangle(
+                    panel_x + 10.0,
+                    y - row_h + 5.0,
+                    panel_w - 20.0,
+                    row_h,
+                    palette.highlight,
+                );
+            }
+
+            let (label, value) = match row {
+                Row::Theme => {
+                    let label = if chinese { "主题" } else { "Theme" };
+                    (label.to_string(), format!("< {} >", settings.theme))
+                }
+                Row::Ghost => {
+                    let label = if chinese {
+                        "幽灵赛跑"
+                    } else {
+                        "Ghost race"
+                    };
+                    (label.to_string(), on_off(settings.ghost, chinese))
+                }
+                Row::Particles => {
+                    let label = if chinese { "粒子特效" } else { "Particles" };
+                    (label.to_string(), on_off(settings.particles, chinese))
+                }
+                Row::ScreenShake => {
+                    let label = if chinese {
+                        "屏幕震动"
+                    } else {
+                        "Screen shake"
+                    };
+                    let value = if settings.reduced_motion {
+                        if chinese {
+                            "< 关（减少动态效果）>".to_string()
+                        } else {
+                            "< Off (reduced motion) >".to_string()
+                        }
+                    } else {
+                        format!("< {:.0}% >", settings.screen_shake * 100.0)
+                    };
+                    (label.to_string(), value)
+                }
+                Row::ReducedMotion => {
+                    let label = if chinese {
+                        "减少动态效果"
+                    } else {
+                        "Reduced motion"
+                    };
+                    (label.to_string(), on_off(settings.reduced_motion, chinese))
+                }
+                Row::Lanes => {
+                    let label = if chinese { "车道" } else { "Lanes" };
+                    let value = match (settings.lanes, chinese) {
+                        (1, true) => "< 经典（1 条）>".to_string(),
+                        (1, false) => "< Classic (1) >".to_string(),
+                        (lanes, true) => format!("< {} 条，↑↓ 换道 >", lanes),
+                        (lanes, false) => format!("< {}, Up/Down to switch >", lanes),
+                    };
+                    (label.to_string(), value)
+                }
+                Row::Binding(action) => {
+                    let value = if self.capturing && index == self.selected {
+                        if chinese {
+                            "按下新按键... (ESC 取消)".to_string()
+                        } else {
+                            "Press a key... (ESC to cancel)".to_string()
+                        }
+                    } else {
+                        bindings
+                            .get(*action)
+                            .iter()
+                            .map(|binding| binding.name())
+                            .collect::<Vec<_>>()
+                            .join(", ")
+                    };
+                    (action.label(chinese), value)
+                }
+                Row::ResetBindings => {
+                    let label = if chinese {
+                        "恢复默认按键"
+                    } else {
+                        "Reset key bindings"
+                    };
+                    (label.to_string(), String::new())
+                }
+            };
+
+            let label_color = if *row == Row::ResetBindings {
+                palette.input
+            } else {
+                palette.text
+            };
+            draw_label(font, &label, panel_x + 20.0, y, 20, label_color);
+            draw_label(font, &value, panel_x + 170.0, y, 20, palette.text_secondary);
+        }
+
+        let hint = if chinese {
+            "↑↓ 选择  ←→ 切换  回车 添加按键  退格 清除  ESC 保存并返回"
+        } else {
+            "Up/Down select  Left/Right change  ENTER add key  BACKSPACE clear  ESC back"
+        };
+        draw_label(
+            font,
+            hint,
+            screen_width() / 2.0 - label_width(font, hint, 18) / 2.0,
+            panel_y + panel_h - 15.0,
+            18,
+            palette.text_secondary,
+        );
+    }
+}
+
+fn on_off(value: bool, chinese: bool) -> String {
+    match (value, chinese) {
+        (true, true) => "< 开 >",
+        (false, true) => "< 关 >",
+        (true, false) => "< On >",
+        (false, false) => "< Off >",
+    }
+    .to_string()
+}
@@ -0,0 +1,613 @@
+use std::collections::BTreeMap;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{Shutdown, SocketAddr, TcpListener, TcpStream, ToSocketAddrs};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::thread;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use crate::dino::Dino;
+
+// 局域网对战的网络层：TCP 上的 JSON Lines，每行一条消息。
+// 一个实例作为主机监听端口，其他实例连接到主机（星形拓扑），主机负责分配编号、
+// 开始比赛（广播种子）并把每名玩家的状态快照转发给其他玩家。协议见 README
+
+// 除了消息格式，生成规则改变后同一种子的赛道也会不同，同样需要增加版本号
+pub const PROTOCOL_VERSION: u32 = 4;
+pub const DEFAULT_PORT: u16 = 7878;
+// 主机自己的编号，客户端从 1 开始编号
+pub const HOST_ID: u32 = 0;
+// 每隔多少步发送一次状态快照（约每秒 20 次）
+pub const SNAPSHOT_INTERVAL: u64 = 3;
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+// 消息在游戏线程中发送；对方不再读取、发送缓冲区满了时最多等这么久，随后断开这个连接，不让游戏卡住
+const WRITE_TIMEOUT: Duration = Duration::from_millis(200);
+// 一条消息的大小上限，对方发送不带换行的超长数据时断开连接，不会无限占用内存
+const MAX_LINE_BYTES: u64 = 64 << 10;
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+pub enum Message {
+    // 客户端 → 主机：连接后发送的第一条消息
+    Hello {
+        version: u32,
+        name: String,
+    },
+    // 主机 → 客户端：分配给这名玩家的编号
+    Welcome {
+        id: u32,
+    },
+    // 主机 → 客户端：拒绝加入，随后断开连接
+    Reject {
+        reason: String,
+    },
+    // 主机 → 所有客户端：大厅中的全部玩家（包括主机），有人加入或离开时发送
+    Lobby {
+        players: Vec<PeerInfo>,
+    },
+    // 主机 → 所有客户端：开始第 race 局，所有人使用同一个种子和视野宽度
+    Start {
+        race: u32,
+        seed: u64,
+        view_width: f32,
+    },
+    // 双向：玩家的状态快照，主机收到后转发给其他客户端
+    State(Snapshot),
+    // 主机 → 所有客户端：有玩家断开了连接
+    Leave {
+        id: u32,
+    },
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PeerInfo {
+    pub id: u32,
+    pub name: String,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Snapshot {
+    pub id: u32,
+    // 快照所属的局，旧局的快照直接丢弃
+    pub race: u32,
+    pub tick: u64,
+    pub dino: Dino,
+    pub score: u32,
+    // 撞到障碍物（可能正在答题复活）
+    pub game_over: bool,
+    // 本局真正结束，不再复活
+    pub finished: bool,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Start {
+    pub race: u32,
+    pub seed: u64,
+    pub view_width: f32,
+}
+
+pub struct Peer {
+    pub name: String,
+    // 当前这一局最近一次收到的快照
+    pub snapshot: Option<Snapshot>,
+}
+
+// 读取线程交给主线程的网络事件，conn 为连接编号（客户端上到主机的连接为 HOST_ID）
+enum Event {
+    Received(u32, Message),
+    Closed(u32),
+}
+
+pub struct Session {
+    pub host: bool,
+    // 自己的编号，客户端在收到 Welcome 之前为 None
+    pub id: Option<u32>,
+    pub name: String,
+    // 其他玩家，按编号排列
+    pub peers: BTreeMap<u32, Peer>,
+    // 连接断开或被拒绝的原因
+    pub error: Option<String>,
+    race: u32,
+    start: Option<Start>,
+    // 主机监听的端口，非阻塞，在 poll 中接受新连接
+    listener: Option<TcpListener>,
+    next_conn: u32,
+    sender: Sender<Event>,
+    events: Receiver<Event>,
+    // 主机：每个客户端连接；客户端：到主机的连接
+    streams: BTreeMap<u32, TcpStream>,
+    local_addr: SocketAddr,
+}
+
+impl Session {
+    // 在 addr 上监听，等待其他玩家加入
+    pub fn host(addr: &str, name: &str) -> io::Result<Self> {
+        let listener = TcpListener::bind(addr)?;
+        listener.set_nonblocking(true)?;
+        let mut session = Self::new(true, Some(HOST_ID), name, listener.local_addr()?);
+        session.listener = Some(listener);
+        Ok(session)
+    }
+
+    // 连接到主机，addr 不带端口时使用默认端口
+    pub fn join(addr: &str, name: &str) -> io::Result<Self> {
+        let addr = if addr.contains(':') {
+            addr.to_string()
+        } else {
+            format!("{}:{}", addr, DEFAULT_PORT)
+        };
+        let target = addr.to_socket_addrs()?.next().ok_or_else(|| {
+            io::Error::new(io::ErrorKind::NotFound, format!("cannot resolve {}", addr))
+        })?;
+        let mut stream = TcpStream::connect_timeout(&target, CONNECT_TIMEOUT)?;
+        stream.set_nodelay(true)?;
+        stream.set_write_timeout(Some(WRITE_TIMEOUT))?;
+        send(
+            &mut stream,
+            &Message::Hello {
+                version: PROTOCOL_VERSION,
+                name: name.to_string(),
+            },
+        )?;
+        let mut session = Self::new(false, None, name, target);
+        spawn_reader(HOST_ID, stream.try_clone()?, session.sender.clone());
+        session.streams.insert(HOST_ID, stream);
+        Ok(session)
+    }
+
+    fn new(host: bool, id: Option<u32>, name: &str, local_addr: SocketAddr) -> Self {
+        let (sender, events) = mpsc::channel();
+        Self {
+            host,
+            id,
+            name: name.to_string(),
+            peers: BTreeMap::new(),
+            error: None,
+            race: 0,
+            start: None,
+            listener: None,
+            next_conn: HOST_ID + 1,
+            sender,
+            events,
+            streams: BTreeMap::new(),
+            local_addr,
+        }
+    }
+
+    // 主机监听的地址，或客户端连接的主机地址
+    pub fn address(&self) -> SocketAddr {
+        self.local_addr
+    }
+
+    pub fn race(&self) -> u32 {
+        self.race
+    }
+
+    // 取出尚未处理的开始比赛通知
+    pub fn take_start(&mut self) -> Option<Start> {
+        self.start.take()
+    }
+
+    // 每帧调用一次：接受新连接，处理读取线程收到的所有消息
+    pub fn poll(&mut self) {
+        while let Some(Ok((stream, _))) = self.listener.as_ref().map(TcpListener::accept) {
+            if let Err(err) = self.accept(stream) {
+                eprintln!("Warning: failed to accept connection: {}", err);
+            }
+        }
+        while let Ok(event) = self.events.try_recv() {
+            match event {
+                Event::Received(conn, message) => {
+                    if self.host {
+                        self.host_receive(conn, message);
+                    } else {
+                        self.client_receive(message);
+                    }
+                }
+                Event::Closed(conn) => self.disconnect(conn),
+            }
+        }
+    }
+
+    fn accept(&mut self, stream: TcpStream) -> io::Result<()> {
+        stream.set_nonblocking(false)?;
+        stream.set_nodelay(true)?;
+        stream.set_write_timeout(Some(WRITE_TIMEOUT))?;
+        let conn = self.next_conn;
+        self.next_conn += 1;
+        spawn_reader(conn, stream.try_clone()?, self.sender.clone());
+        self.streams.insert(conn, stream);
+        Ok(())
+    }
+
+    fn host_receive(&mut self, conn: u32, message: Message) {
+        match message {
+            Message::Hello { version, name } => {
+                if version != PROTOCOL_VERSION {
+                    let reason = format!(
+                        "protocol version {} does not match host version {}",
+                        version, PROTOCOL_VERSION
+                    );
+                    self.send_to(conn, &Message::Reject { reason });
+                    if let Some(stream) = self.streams.remove(&conn) {
+                        let _ = stream.shutdown(Shutdown::Both);
+                    }
+                    return;
+                }
+                println!("Player '{}' joined", name);
+                self.peers.insert(
+                    conn,
+                    Peer {
+                        name,
+                        snapshot: None,
+                    },
+                );
+                self.send_to(conn, &Message::Welcome { id: conn });
+                self.broadcast(&self.lobby(), None);
+            }
+            // 只接受已加入的玩家自己的快照
+            Message::State(mut snapshot) => {
+                if !self.peers.contains_key(&conn) {
+                    return;
+                }
+                snapshot.id = conn;
+                self.broadcast(&Message::State(snapshot.clone()), Some(conn));
+                self.store(snapshot);
+            }
+            other => eprintln!(
+                "Warning: unexpected message from connection {}: {:?}",
+                conn, other
+            ),
+        }
+    }
+
+    fn client_receive(&mut self, message: Message) {
+        match message {
+            Message::Welcome { id } => self.id = Some(id),
+            Message::Reject { reason } => self.error = Some(reason),
+            Message::Lobby { players } => {
+                let mut peers = BTreeMap::new();
+                for info in players {
+                    if Some(info.id) == self.id {
+                        continue;
+                    }
+                    let snapshot = self.peers.remove(&info.id).and_then(|peer| peer.snapshot);
+                    peers.insert(
+                        info.id,
+                        Peer {
+                            name: info.name,
+                            snapshot,
+                        },
+                    );
+                }
+                self.peers = peers;
+            }
+            Message::Start {
+                race,
+                seed,
+                view_width,
+            } => self.begin(Start {
+                race,
+                seed,
+                view_width,
+            }),
+            Message::State(snapshot) => self.store(snapshot),
+            Message::Leave { id } => {
+                self.peers.remove(&id);
+            }
+            Message::Hello { .. } => eprintln!("Warning: unexpected hello from host"),
+        }
+    }
+
+    fn disconnect(&mut self, conn: u32) {
+        self.streams.remove(&conn);
+        if !self.host {
+            if self.error.is_none() {
+                self.error = Some("connection to host closed".to_string());
+            }
+            return;
+        }
+        if let Some(peer) = self.peers.remove(&conn) {
+            println!("Player '{}' left", peer.name);
+            self.broadcast(&Message::Leave { id: conn }, None);
+            self.broadcast(&self.lobby(), None);
+        }
+    }
+
+    fn lobby(&self) -> Message {
+        let mut players = vec![PeerInfo {
+            id: HOST_ID,
+            name: self.name.clone(),
+        }];
+        players.extend(self.peers.iter().map(|(&id, peer)| PeerInfo {
+            id,
+            name: peer.name.clone(),
+        }));
+        Message::Lobby { players }
+    }
+
+    fn begin(&mut self, start: Start) {
+        self.race = start.race;
+        for peer in self.peers.values_mut() {
+            peer.snapshot = None;
+        }
+        self.start = Some(start);
+    }
+
+    fn store(&mut self, snapshot: Snapshot) {
+        if snapshot.race != self.race {
+            return;
+        }
+        if let Some(peer) = self.peers.get_mut(&snapshot.id) {
+            peer.snapshot = Some(snapshot);
+        }
+    }
+
+    // 主机开始新的一局，所有人（包括主机自己）都会收到 Start
+    pub fn start_race(&mut self, view_width: f32) {
+        if !self.host {
+            return;
+        }
+        let start = Start {
+            race: self.race + 1,
+            seed: ::rand::random(),
+            view_width,
+        };
+        self.broadcast(
+            &Message::Start {
+                race: start.race,
+                seed: start.seed,
+                view_width: start.view_width,
+            },
+            None,
+        );
+        self.begin(start);
+    }
+
+    // 发送自己的状态快照：主机发给所有客户端，客户端发给主机
+    pub fn send_snapshot(&mut self, snapshot: Snapshot) {
+        let message = Message::State(snapshot);
+        if self.host {
+            self.broadcast(&message, None);
+        } else {
+            self.send_to(HOST_ID, &message);
+        }
+    }
+
+    // 当前这一局中其他玩家是否都已结束（还没有发送过快照的玩家不参与这一局）
+    pub fn others_finished(&self) -> bool {
+        self.peers
+            .values()
+            .filter_map(|peer| peer.snapshot.as_ref())
+            .all(|snapshot| snapshot.finished)
+    }
+
+    // 发送失败（包括超时）时关闭连接，读取线程随后报告 Closed，按断开处理
+    fn send_to(&mut self, conn: u32, message: &Message) {
+        if let Some(stream) = self.streams.get_mut(&conn) {
+            if let Err(err) = send(stream, message) {
+                eprintln!("Warning: failed to send to connection {}: {}", conn, err);
+                let _ = stream.shutdown(Shutdown::Both);
+                self.streams.remove(&conn);
+            }
+        }
+    }
+
+    fn broadcast(&mut self, message: &Message, except: Option<u32>) {
+        let targets: Vec<u32> = self
+            .peers
+            .keys()
+            .copied()
+            .filter(|&id| Some(id) != except)
+            .collect();
+        for conn in targets {
+            self.send_to(conn, message);
+        }
+    }
+}
+
+// 读取线程持有连接的副本，需要显式关闭连接才能让对方和读取线程结束
+impl Drop for Session {
+    fn drop(&mut self) {
+        for stream in self.streams.values() {
+            let _ = stream.shutdown(Shutdown::Both);
+        }
+    }
+}
+
+fn send(stream: &mut TcpStream, message: &Message) -> io::Result<()> {
+    let mut line = serde_json::to_string(message)?;
+    line.push('\n');
+    stream.write_all(line.as_bytes())
+}
+
+// 在后台线程中逐行读取消息，连接关闭或消息超长后发送 Closed
+fn spawn_reader(conn: u32, stream: TcpStream, events: Sender<Event>) {
+    thread::spawn(move || {
+        let mut reader = BufReader::new(stream);
+        let mut line = Vec::new();
+        loop {
+            line.clear();
+            let read = (&mut reader)
+                .take(MAX_LINE_BYTES)
+                .read_until(b'\n', &mut line);
+            if !matches!(read, Ok(n) if n > 0) {
+                break;
+            }
+            if !line.ends_with(b"\n") && line.len() as u64 == MAX_LINE_BYTES {
+                eprintln!("Warning: message from connection {} is too long", conn);
+                break;
+            }
+            match serde_json::from_slice(&line) {
+                Ok(message) => {
+                    if events.send(Event::Received(conn, message)).is_err() {
+                        return;
+                    }
+                }
+                Err(err) => eprintln!("Warning: invalid message from connection {}: {}", conn, err),
+            }
+        }
+        let _ = events.send(Event::Closed(conn));
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Instant;
+
+    use super::*;
+
+    // 反复处理两端的消息，直到条件满足或超时
+    fn poll_until(
+        host: &mut Session,
+        client: &mut Session,
+        done: impl Fn(&Session, &Session) -> bool,
+    ) {
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while !done(host, client) {
+            assert!(Instant::now() < deadline, "timed out waiting for messages");
+            host.poll();
+            client.poll();
+            thread::sleep(Duration::from_millis(5));
+        }
+    }
+
+    // 只处理主机一端的消息，用于直接用 TcpStream 模拟客户端的测试
+    fn poll_until_host(host: &mut Session, done: impl Fn(&Session) -> bool) {
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while !done(host) {
+            assert!(Instant::now() < deadline, "timed out waiting for messages");
+            host.poll();
+            thread::sleep(Duration::from_millis(5));
+        }
+    }
+
+    #[test]
+    fn messages_use_documented_json_format() {
+        let hello = Message::Hello {
+            version: 1,
+            name: "bob".to_string(),
+        };
+        let text = serde_json::to_string(&hello).unwrap();
+        assert_eq!(text, r#"{"type":"hello","version":1,"name":"bob"}"#);
+        let state: Message = serde_json::from_str(
+            r#"{"type":"state","id":2,"race":1,"tick":30,"score":10,"game_over":false,"finished":false,
+                "dino":{"y":365.0,"velocity":0.0,"is_jumping":false,"is_ducking":false}}"#,
+        )
+        .unwrap();
+        assert!(matches!(
+            state,
+            Message::State(Snapshot {
+                id: 2,
+                tick: 30,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn client_joins_lobby_and_races_with_host() {
+        let mut host = Session::host("127.0.0.1:0", "alice").unwrap();
+        let mut client = Session::join(&host.address().to_string(), "bob").unwrap();
+        poll_until(&mut host, &mut client, |host, client| {
+            client.id.is_some() && client.peers.len() == 1 && host.peers.len() == 1
+        });
+        let id = client.id.unwrap();
+        assert_eq!(client.peers[&HOST_ID].name, "alice");
+        assert_eq!(host.peers[&id].name, "bob");
+
+        host.start_race(640.0);
+        poll_until(&mut host, &mut client, |_, client| client.race() == 1);
+        let start = client.take_start().unwrap();
+        assert_eq!(Some(start), host.take_start());
+        assert_eq!(start.view_width, 640.0);
+
+        client.send_snapshot(Snapshot {
+            id,
+            race: 1,
+            tick: 3,
+            dino: Dino::new(),
+            score: 7,
+            game_over: true,
+            finished: true,
+        });
+        poll_until(&mut host, &mut client, |host, _| {
+            host.peers[&id].snapshot.is_some()
+        });
+        assert_eq!(host.peers[&id].snapshot.as_ref().unwrap().score, 7);
+        assert!(host.others_finished());
+
+        drop(client);
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while !host.peers.is_empty() {
+            assert!(
+                Instant::now() < deadline,
+                "host did not notice the disconnect"
+            );
+            host.poll();
+            thread::sleep(Duration::from_millis(5));
+        }
+    }
+
+    #[test]
+    fn host_drops_a_client_that_sends_an_endless_line() {
+        let mut host = Session::host("127.0.0.1:0", "alice").unwrap();
+        let mut flood = TcpStream::connect(host.address()).unwrap();
+        send(
+            &mut flood,
+            &Message::Hello {
+                version: PROTOCOL_VERSION,
+                name: "bob".to_string(),
+            },
+        )
+        .unwrap();
+        poll_until_host(&mut host, |host| !host.peers.is_empty());
+
+        flood
+            .write_all(&vec![b'A'; MAX_LINE_BYTES as usize])
+            .unwrap();
+        poll_until_host(&mut host, |host| host.peers.is_empty());
+    }
+
+    #[test]
+    fn host_drops_a_client_that_stops_reading() {
+        let mut host = Session::host("127.0.0.1:0", "alice").unwrap();
+        // 加入后再也不读取消息的客户端
+        let mut stalled = TcpStream::connect(host.address()).unwrap();
+        send(
+            &mut stalled,
+            &Message::Hello {
+                version: PROTOCOL_VERSION,
+                name: "bob".to_string(),
+            },
+        )
+        .unwrap();
+        let deadline = Instant::now() + Duration::from_secs(10);
+        while host.peers.is_empty() {
+            assert!(Instant::now() < deadline, "client did not join");
+            host.poll();
+            thread::sleep(Duration::from_millis(5));
+        }
+
+        // 发送缓冲区填满后，主机的每次发送最多等待 WRITE_TIMEOUT，随后断开这名玩家
+        let snapshot = Snapshot {
+            id: HOST_ID,
+            race: 0,
+            tick: 0,
+            dino: Dino::new(),
+            score: 0,
+            game_over: false,
+            finished: false,
+        };
+        while !host.peers.is_empty() {
+            assert!(Instant::now() < deadline, "host did not drop the client");
+            let start = Instant::now();
+            host.send_snapshot(snapshot.clone());
+            assert!(start.elapsed() < WRITE_TIMEOUT * 5);
+            host.poll();
+        }
+    }
+}
@@ -0,0 +1,249 @@
+use std::fs;
+
+use ::rand::rngs::StdRng;
+use ::rand::Rng;
+use macroquad::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::bot::{Bot, Observation};
+use crate::dino::DinoInput;
+use crate::theme::Palette;
+use crate::ui::draw_label;
+
+// 神经网络控制器：固定拓扑的全连接网络，输入为 Observation::features，
+// 输出三个动作（不操作、跳跃、下蹲）中得分最高的一个。权重由 train.rs 的遗传算法进化得到
+
+// 网络看到的前方障碍物数量
+pub const SEEN_OBSTACLES: usize = 2;
+pub const INPUTS: usize = 5 + SEEN_OBSTACLES * 4;
+pub const HIDDEN: usize = 8;
+pub const OUTPUTS: usize = 3;
+
+// 一个网络的全部参数，按层依次存放：每个神经元先是各输入的权重，然后是偏置
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Genome {
+    pub layers: Vec<usize>,
+    // 训练时的适应度，仅供参考
+    #[serde(default)]
+    pub fitness: f32,
+    pub weights: Vec<f32>,
+}
+
+impl Genome {
+    pub fn random(rng: &mut StdRng) -> Self {
+        let layers = vec![INPUTS, HIDDEN, OUTPUTS];
+        let weights = (0..weight_count(&layers))
+            .map(|_| rng.gen_range(-1.0..1.0))
+            .collect();
+        Self {
+            layers,
+            fitness: 0.0,
+            weights,
+        }
+    }
+
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let genome: Genome = toml::from_str(text).map_err(|err| err.to_string())?;
+        if genome.layers != [INPUTS, HIDDEN, OUTPUTS] {
+            return Err(format!(
+                "layers must be [{}, {}, {}]",
+                INPUTS, HIDDEN, OUTPUTS
+            ));
+        }
+        if genome.weights.len() != weight_count(&genome.layers) {
+            return Err(format!(
+                "expected {} weights, found {}",
+                weight_count(&genome.layers),
+                genome.weights.len()
+            ));
+        }
+        Ok(genome)
+    }
+
+    pub fn load(path: &str) -> Result<Self, String> {
+        let text = fs::read_to_string(path).map_err(|err| err.to_string())?;
+        Genome::parse(&text)
+    }
+
+    pub fn save(&self, path: &str) -> Result<(), String> {
+        let text = toml::to_string(self).map_err(|err| err.to_string())?;
+        fs::write(path, text).map_err(|err| err.to_string())
+    }
+
+    // 前向计算，返回每一层（包括输入层）的激活值
+    pub fn forward(&self, inputs: &[f32]) -> Vec<Vec<f32>> {
+        let mut activations = vec![inputs.to_vec()];
+        let mut weights = self.weights.iter();
+        for (i, &size) in self.layers.iter().enumerate().skip(1) {
+            let previous = &activations[i - 1];
+            let output_layer = i == self.layers.len() - 1;
+            let layer = (0..size)
+                .map(|_| {
+                    let sum: f32 = previous
+                        .iter()
+                        .zip(weights.by_ref())
+                        .map(|(input, weight)| input * weight)
+                        .sum::<f32>()
+                        + weights.next().copied().unwrap_or(0.0);
+                    // 隐藏层使用 tanh，输出层保留原始得分
+                    if output_layer {
+                        sum
+                    } else {
+                        sum.tanh()
+                    }
+                })
+                .collect();
+            activations.push(layer);
+        }
+        activations
+    }
+}
+
+fn weight_count(layers: &[usize]) -> usize {
+    layers.windows(2).map(|pair| (pair[0] + 1) * pair[1]).sum()
+}
+
+// 由一个基因组控制的机器人，记录最近一次的激活值用于可视化
+pub struct NeuroBot {
+    genome: Genome,
+    activations: Vec<Vec<f32>>,
+}
+
+impl NeuroBot {
+    pub fn new(genome: Genome) -> Self {
+        Self {
+            genome,
+            activations: Vec::new(),
+        }
+    }
+}
+
+impl Bot for NeuroBot {
+    fn name(&self) -> &'static str {
+        "neuro"
+    }
+
+    fn decide(&mut self, observation: &Observation) -> DinoInput {
+        self.activations = self.genome.forward(&observation.features(SEEN_OBSTACLES));
+        let outputs = self.activations.last().map_or(&[][..], Vec::as_slice);
+        let action = (0..outputs.len())
+            .max_by(|&a, &b| outputs[a].total_cmp(&outputs[b]))
+            .unwrap_or(0);
+        DinoInput {
+            jump: action == 1,
+            duck: action == 2,
+        }
+    }
+
+    // 在屏幕右上角画出网络：连线颜色表示权重正负，粗细表示大小；节点亮度表示激活值
+    fn draw(&self, palette: &Palette, font: Option<&Font>) {
+        if self.activations.len() != self.genome.layers.len() {
+            return;
+        }
+        const NODE_SPACING: f32 = 14.0;
+        const LAYER_SPACING: f32 = 70.0;
+        let left = screen_width() - 70.0 - LAYER_SPACING * (self.activations.len() - 1) as f32;
+        let top = 50.0;
+        let node = |layer: usize, index: usize| {
+            // 每层竖直居中对齐到输入层
+            let offset = (INPUTS - self.activations[layer].len()) as f32 * NODE_SPACING / 2.0;
+            vec2(
+                left + layer as f32 * LAYER_SPACING,
+                top + offset + index as f32 * NODE_SPACING,
+            )
+        };
+
+        let mut weights = self.genome.weights.iter();
+        for layer in 1..self.activations.len() {
+            for to in 0..self.activations[layer].len() {
+                for from in 0..self.activations[layer - 1].len() {
+                    let weight = weights.next().copied().unwrap_or(0.0);
+                    let color = if weight >= 0.0 {
+                        palette.success
+                    } else {
+                        palette.danger
+                    };
+                    let (a, b) = (node(layer - 1, from), node(layer, to));
+                    draw_line(
+                        a.x,
+                        a.y,
+                        b.x,
+                        b.y,
+                        weight.abs().min(2.0) * 0.75,
+                        Color { a: 0.5, ..color },
+                    );
+                }
+                // 跳过偏置
+                weights.next();
+            }
+        }
+
+        let outputs = &self.activations[self.activations.len() - 1];
+        let chosen = (0..outputs.len())
+            .max_by(|&a, &b| outputs[a].total_cmp(&outputs[b]))
+            .unwrap_or(0);
+        for (layer, values) in self.activations.iter().enumerate() {
+            for (index, value) in values.iter().enumerate() {
+                let center = node(layer, index);
+                let strength = value.tanh().abs();
+                draw_circle(center.x, center.y, 5.0, palette.panel);
+                draw_circle(
+                    center.x,
+                    center.y,
+                    5.0,
+                    Color {
+                        a: strength,
+                        ..palette.text
+                    },
+                );
+                draw_circle_lines(center.x, center.y, 5.0, 1.0, palette.panel_border);
+            }
+        }
+
+        let labels = if font.is_some() {
+            ["不动", "跳跃", "下蹲"]
+        } else {
+            ["idle", "jump", "duck"]
+        };
+        for (index, label) in labels.iter().enumerate() {
+            let center = node(self.activations.len() - 1, index);
+            let color = if index == chosen {
+                palette.highlight
+            } else {
+                palette.text_secondary
+            };
+            draw_label(font, label, center.x + 10.0, center.y + 5.0, 16, color);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ::rand::SeedableRng;
+
+    use super::*;
+
+    #[test]
+    fn genome_round_trips_through_file_format() {
+        let genome = Genome::random(&mut StdRng::seed_from_u64(1));
+        let text = toml::to_string(&genome).unwrap();
+        assert_eq!(Genome::parse(&text).unwrap(), genome);
+    }
+
+    #[test]
+    fn wrong_weight_count_is_rejected() {
+        let mut genome = Genome::random(&mut StdRng::seed_from_u64(1));
+        genome.weights.pop();
+        let err = Genome::parse(&toml::to_string(&genome).unwrap()).unwrap_err();
+        assert!(err.contains("weights"), "{}", err);
+    }
+
+    #[test]
+    fn forward_produces_one_activation_per_node() {
+        let genome = Genome::random(&mut StdRng::seed_from_u64(1));
+        let activations = genome.forward(&[0.5; INPUTS]);
+        let sizes: Vec<usize> = activations.iter().map(Vec::len).collect();
+        assert_eq!(sizes, genome.layers);
+    }
+}
@@ -0,0 +1,219 @@
+use std::cell::Cell;
+use std::rc::Rc;
+
+use ::rand::rngs::StdRng;
+use ::rand::{Rng, SeedableRng};
+use macroquad::prelude::*;
+
+use crate::events::{GameEvent, Listener};
+use crate::scoring::ScoreEvent;
+use crate::theme::Palette;
+
+// 粒子特效：订阅游戏事件，落地时扬起尘土、收集星星时闪光、撞到障碍物时碎片飞散。
+// 粒子随游戏逻辑的固定步长运动，只用于绘制，使用自己的随机数，不影响对局。
+// 同时存在的粒子数有上限，超出的部分不再生成；设置中关闭后不再生成并清除已有的粒子
+
+// 同时存在的粒子数上限
+const PARTICLE_BUDGET: usize = 256;
+
+// 设置中的粒子特效开关，由主循环按设置更新
+pub type ParticleToggle = Rc<Cell<bool>>;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum Kind {
+    Dust,
+    Sparkle,
+    Debris,
+}
+
+struct Particle {
+    kind: Kind,
+    pos: Vec2,
+    velocity: Vec2,
+    gravity: f32,
+    size: f32,
+    age: f32,
+    lifetime: f32,
+}
+
+// 一次喷发：粒子数量、速度大小和方向的范围（弧度，0 为向右，负值向上）、重力和寿命
+struct Burst {
+    kind: Kind,
+    count: usize,
+    speed: (f32, f32),
+    angle: (f32, f32),
+    gravity: f32,
+    lifetime: (f32, f32),
+    size: (f32, f32),
+}
+
+const DUST: Burst = Burst {
+    kind: Kind::Dust,
+    count: 8,
+    speed: (20.0, 70.0),
+    angle: (-2.9, -1.9),
+    gravity: 120.0,
+    lifetime: (0.3, 0.5),
+    size: (1.5, 3.0),
+};
+
+const SPARKLE: Burst = Burst {
+    kind: Kind::Sparkle,
+    count: 10,
+    speed: (40.0, 120.0),
+    angle: (-std::f32::consts::PI, std::f32::consts::PI),
+    gravity: 0.0,
+    lifetime: (0.3, 0.6),
+    size: (1.5, 2.5),
+};
+
+const DEBRIS: Burst = Burst {
+    kind: Kind::Debris,
+    count: 20,
+    speed: (80.0, 220.0),
+    angle: (-2.6, -0.3),
+    gravity: 600.0,
+    lifetime: (0.6, 1.0),
+    size: (2.0, 4.0),
+};
+
+pub struct Particles {
+    enabled: ParticleToggle,
+    particles: Vec<Particle>,
+    // 只用于特效的随机数，与对局的随机数分开
+    rng: StdRng,
+}
+
+impl Particles {
+    pub fn new(enabled: &ParticleToggle) -> Self {
+        Self {
+            enabled: enabled.clone(),
+            particles: Vec::new(),
+            rng: StdRng::seed_from_u64(0),
+        }
+    }
+
+    fn emit(&mut self, burst: &Burst, origin: Vec2) {
+        let room = PARTICLE_BUDGET.saturating_sub(self.particles.len());
+        for _ in 0..burst.count.min(room) {
+            let angle = self.rng.gen_range(burst.angle.0..burst.angle.1);
+            let speed = self.rng.gen_range(burst.speed.0..burst.speed.1);
+            self.particles.push(Particle {
+                kind: burst.kind,
+                pos: origin,
+                velocity: Vec2::from_angle(angle) * speed,
+                gravity: burst.gravity,
+                size: self.rng.gen_range(burst.size.0..burst.size.1),
+                age: 0.0,
+                lifetime: self.rng.gen_range(burst.lifetime.0..burst.lifetime.1),
+            });
+        }
+    }
+}
+
+impl Listener for Particles {
+    fn on_event(&mut self, event: &GameEvent) {
+        if let GameEvent::Reset = event {
+            self.particles.clear();
+        }
+        if !self.enabled.get() {
+            return;
+        }
+        match *event {
+            GameEvent::Landed { pos } => self.emit(&DUST, pos),
+            GameEvent::Score(ScoreEvent::Star { pos, .. }) => self.emit(&SPARKLE, pos),
+            GameEvent::Hit { pos } => self.emit(&DEBRIS, pos),
+            _ => {}
+        }
+    }
+
+    fn step(&mut self, dt: f32) {
+        if !self.enabled.get() {
+            self.particles.clear();
+            return;
+        }
+        for particle in &mut self.particles {
+            particle.velocity.y += particle.gravity * dt;
+            particle.pos += particle.velocity * dt;
+            particle.age += dt;
+        }
+        self.particles
+            .retain(|particle| particle.age < particle.lifetime);
+    }
+
+    fn draw(&self, palette: &Palette, _font: Option<&Font>) {
+        for particle in &self.particles {
+            let fade = 1.0 - particle.age / particle.lifetime;
+            let (x, y, size) = (particle.pos.x, particle.pos.y, particle.size);
+            match particle.kind {
+                Kind::Dust => {
+                    let color = Color {
+                        a: palette.ground.a * fade * 0.8,
+                        ..palette.ground
+                    };
+                    draw_circle(x, y, size * (1.5 - fade * 0.5), color);
+                }
+                Kind::Sparkle => {
+                    let color = Color {
+                        a: fade,
+                        ..palette.star
+                    };
+                    draw_line(x - size, y, x + size, y, 1.0, color);
+                    draw_line(x, y - size, x, y + size, 1.0, color);
+                }
+                Kind::Debris => {
+                    let color = Color {
+                        a: palette.cactus.a * fade,
+                        ..palette.cactus
+                    };
+                    draw_rectangle(x - size / 2.0, y - size / 2.0, size, size, color);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn particles_expire_stay_within_budget_and_can_be_disabled() {
+        let enabled = ParticleToggle::new(Cell::new(true));
+        let mut particles = Particles::new(&enabled);
+        let hit = GameEvent::Hit { pos: Vec2::ZERO };
+        particles.on_event(&GameEvent::Landed { pos: Vec2::ZERO });
+        assert_eq!(particles.particles.len(), DUST.count);
+
+        // 碎片受重力落下，寿命结束后移除
+        particles.on_event(&hit);
+        let falling = |particles: &Particles| -> Vec<f32> {
+            particles
+                .particles
+                .iter()
+                .filter(|particle| particle.kind == Kind::Debris)
+                .map(|particle| particle.velocity.y)
+                .collect()
+        };
+        let before = falling(&particles);
+        particles.step(1.0 / 60.0);
+        let after = falling(&particles);
+        assert_eq!(before.len(), DEBRIS.count);
+        assert!(before.iter().zip(&after).all(|(b, a)| a > b));
+        for _ in 0..60 {
+            particles.step(1.0 / 60.0);
+        }
+        assert!(particles.particles.is_empty());
+
+        for _ in 0..100 {
+            particles.on_event(&hit);
+        }
+        assert_eq!(particles.particles.len(), PARTICLE_BUDGET);
+
+        // 关闭后清除已有的粒子，也不再生成
+        enabled.set(false);
+        particles.step(1.0 / 60.0);
+        particles.on_event(&hit);
+        assert!(particles.particles.is_empty());
+    }
+}
@@ -0,0 +1,194 @@
+use macroquad::prelude::*;
+
+use crate::input::{Action, ActionState};
+use crate::keypad;
+
+// 向下滑动超过这个距离视为下蹲手势
+const SWIPE_DISTANCE: f32 = 40.0;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum PointerMode {
+    Playing,
+    Paused,
+    Quiz,
+    GameOver,
+}
+
+struct Press {
+    start: Vec2,
+    swiped: bool,
+}
+
+// 一帧中鼠标左键的状态
+struct Sample {
+    pos: Vec2,
+    screen: Vec2,
+    clicked: bool,
+    released: bool,
+    down: bool,
+}
+
+impl Sample {
+    fn read() -> Self {
+        Self {
+            pos: Vec2::from(mouse_position()),
+            screen: vec2(screen_width(), screen_height()),
+            clicked: is_mouse_button_pressed(MouseButton::Left),
+            released: is_mouse_button_released(MouseButton::Left),
+            down: is_mouse_button_down(MouseButton::Left),
+        }
+    }
+}
+
+// 鼠标和触摸输入（macroquad 默认把触摸模拟成鼠标左键），转换成游戏动作
+pub struct Pointer {
+    press: Option<Press>,
+}
+
+impl Pointer {
+    pub fn new() -> Self {
+        Self { press: None }
+    }
+
+    pub fn apply(&mut self, mode: PointerMode, actions: &mut ActionState) {
+        self.apply_sample(mode, &Sample::read(), actions);
+    }
+
+    fn apply_sample(&mut self, mode: PointerMode, sample: &Sample, actions: &mut ActionState) {
+        let clicked = sample.clicked;
+        match mode {
+            PointerMode::Quiz => {
+                if clicked {
+                    if let Some(key) = keypad::hit(sample.pos, sample.screen) {
+                        actions.press(key.action());
+                    }
+                }
+                self.press = None;
+            }
+            PointerMode::GameOver => {
+                if clicked {
+                    actions.press(Action::Restart);
+                }
+                self.press = None;
+            }
+            PointerMode::Paused => {
+                if clicked {
+                    actions.press(Action::Pause);
+                }
+                self.press = None;
+            }
+            PointerMode::Playing => self.apply_gestures(sample, actions),
+        }
+    }
+
+    // 按下时立即跳跃，之后向下滑动则下蹲（空中加速下落，松开前一直保持下蹲）
+    fn apply_gestures(&mut self, sample: &Sample, actions: &mut ActionState) {
+        if sample.clicked {
+            self.press = Some(Press {
+                start: sample.pos,
+                swiped: false,
+            });
+            actions.press(Action::Jump);
+        }
+
+        let Some(press) = &mut self.press else {
+            return;
+        };
+
+        if sample.pos.y - press.start.y > SWIPE_DISTANCE {
+            press.swiped = true;
+        }
+        if press.swiped {
+            actions.hold(Action::Duck);
+        }
+
+        if sample.released || !sample.down {
+            self.press = None;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample(y: f32, clicked: bool, released: bool) -> Sample {
+        Sample {
+            pos: vec2(300.0, y),
+            screen: vec2(1200.0, 700.0),
+            clicked,
+            released,
+            down: !released,
+        }
+    }
+
+    // 依次送入每一帧，返回每一帧得到的动作
+    fn frames(mode: PointerMode, samples: &[Sample]) -> Vec<ActionState> {
+        let mut pointer = Pointer::new();
+        samples
+            .iter()
+            .map(|sample| {
+                let mut actions = ActionState::default();
+                pointer.apply_sample(mode, sample, &mut actions);
+                actions
+            })
+            .collect()
+    }
+
+    #[test]
+    fn presses_jump_at_once_and_swipes_duck_until_released() {
+        // 按下的那一帧就跳跃，松开时不再跳
+        let tap = frames(
+            PointerMode::Playing,
+            &[sample(300.0, true, false), sample(300.0, false, true)],
+        );
+        assert!(tap[0].pressed(Action::Jump));
+        assert!(!tap[1].pressed(Action::Jump));
+
+        // 按住不动只跳一次，也不会下蹲
+        let hold = frames(
+            PointerMode::Playing,
+            &[
+                sample(300.0, true, false),
+                sample(300.0, false, false),
+                sample(300.0, false, false),
+            ],
+        );
+        assert!(hold[0].pressed(Action::Jump));
+        assert!(hold[1..]
+            .iter()
+            .all(|actions| !actions.pressed(Action::Jump) && !actions.down(Action::Duck)));
+
+        // 按下后向下滑动则一直下蹲，松开后结束
+        let swipe = frames(
+            PointerMode::Playing,
+            &[
+                sample(300.0, true, false),
+                sample(300.0 + SWIPE_DISTANCE + 1.0, false, false),
+                sample(300.0 + SWIPE_DISTANCE + 1.0, false, false),
+                sample(300.0, false, true),
+                sample(300.0, false, true),
+            ],
+        );
+        assert!(swipe[0].pressed(Action::Jump) && !swipe[0].down(Action::Duck));
+        assert!(swipe[1].down(Action::Duck) && swipe[2].down(Action::Duck));
+        assert!(swipe[1..]
+            .iter()
+            .all(|actions| !actions.pressed(Action::Jump)));
+        assert!(!swipe[4].down(Action::Duck));
+    }
+
+    #[test]
+    fn clicks_outside_play_map_to_the_screen_actions() {
+        let click = [sample(300.0, true, false)];
+        assert!(frames(PointerMode::GameOver, &click)[0].pressed(Action::Restart));
+        assert!(frames(PointerMode::Paused, &click)[0].pressed(Action::Pause));
+        // 复活题界面只响应数字键盘上的按钮
+        assert!(!frames(PointerMode::Quiz, &click)[0].pressed(Action::Jump));
+        let five = Sample {
+            pos: vec2(800.0, 294.0),
+            ..sample(0.0, true, false)
+        };
+        assert!(frames(PointerMode::Quiz, &[five])[0].pressed(Action::Digit(5)));
+    }
+}
@@ -0,0 +1,88 @@
+use std::collections::VecDeque;
+
+// 固定容量的环形缓冲区，用来存放仙人掌、星星、石子等实体
+// 创建时一次性分配好空间，运行中 push/retain 都不会再分配内存；满了以后丢弃最旧的元素
+pub struct RingBuffer<T> {
+    items: VecDeque<T>,
+    capacity: usize,
+}
+
+impl<T> RingBuffer<T> {
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            items: VecDeque::with_capacity(capacity),
+            capacity,
+        }
+    }
+
+    pub fn push(&mut self, item: T) {
+        if self.items.len() == self.capacity {
+            self.items.pop_front();
+        }
+        self.items.push_back(item);
+    }
+
+    pub fn retain(&mut self, f: impl FnMut(&T) -> bool) {
+        self.items.retain(f);
+    }
+
+    pub fn clear(&mut self) {
+        self.items.clear();
+    }
+
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &T> {
+        self.items.iter()
+    }
+
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = &mut T> {
+        self.items.iter_mut()
+    }
+}
+
+impl<'a, T> IntoIterator for &'a RingBuffer<T> {
+    type Item = &'a T;
+    type IntoIter = std::collections::vec_deque::Iter<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.items.iter()
+    }
+}
+
+impl<'a, T> IntoIterator for &'a mut RingBuffer<T> {
+    type Item = &'a mut T;
+    type IntoIter = std::collections::vec_deque::IterMut<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.items.iter_mut()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn full_buffer_drops_the_oldest_without_growing() {
+        let mut buffer = RingBuffer::with_capacity(3);
+        let allocated = buffer.items.capacity();
+        for i in 1..=5 {
+            buffer.push(i);
+        }
+        assert_eq!(buffer.len(), 3);
+        assert_eq!(buffer.iter().copied().collect::<Vec<_>>(), [3, 4, 5]);
+
+        // retain 腾出的位置可以直接复用，仍然按加入的顺序丢弃最旧的
+        buffer.retain(|i| *i != 4);
+        buffer.push(6);
+        buffer.push(7);
+        assert_eq!(buffer.iter().copied().collect::<Vec<_>>(), [5, 6, 7]);
+        assert_eq!(buffer.items.capacity(), allocated);
+
+        buffer.clear();
+        assert_eq!(buffer.len(), 0);
+    }
+}
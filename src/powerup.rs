@@ -0,0 +1,371 @@
+use macroquad::prelude::*;
+
+use crate::theme::Palette;
+use crate::ui::draw_label;
+use crate::{GameState, DINO_HEIGHT, DINO_WIDTH, DINO_X, FIXED_DT};
+
+// 道具：收集后获得一段时间的效果，到期、被用掉或重新开始时移除。
+// 效果都在固定步长的游戏逻辑中生效，同一种子和输入仍然得到完全相同的一局（慢动作只放慢画面推进）。
+// 新的道具只需实现 PowerUp 并在 by_name 中登记，再在 data/entities.toml 中添加对应的种类
+pub trait PowerUp {
+    // 定义文件中使用的名字
+    fn name(&self) -> &'static str;
+    fn label(&self, chinese: bool) -> &'static str;
+    // 道具图标上的字母和颜色，颜色按浅色背景设计，绘制时经过 Palette::adapt 随夜晚和主题变化
+    fn symbol(&self) -> &'static str;
+    fn color(&self) -> Color;
+    // 持续的秒数
+    fn duration(&self) -> f32;
+    // 获得时、生效期间每一步、结束时调用
+    fn start(&mut self, _game: &mut GameState) {}
+    fn step(&mut self, _game: &mut GameState) {}
+    fn end(&mut self, _game: &mut GameState) {}
+    // 撞到障碍物时能否抵挡，抵挡后效果立即结束
+    fn absorbs_hit(&self) -> bool {
+        false
+    }
+    // 得分倍数
+    fn score_multiplier(&self) -> u32 {
+        1
+    }
+    // 画面推进速度的倍数，只影响主循环推进游戏的快慢
+    fn time_scale(&self) -> f32 {
+        1.0
+    }
+}
+
+pub const POWER_UP_NAMES: [&str; 5] = [
+    "shield",
+    "magnet",
+    "slow_motion",
+    "double_score",
+    "high_jump",
+];
+
+pub fn by_name(name: &str) -> Option<Box<dyn PowerUp>> {
+    match name {
+        "shield" => Some(Box::new(Shield)),
+        "magnet" => Some(Box::new(Magnet)),
+        "slow_motion" => Some(Box::new(SlowMotion)),
+        "double_score" => Some(Box::new(DoubleScore)),
+        "high_jump" => Some(Box::new(HighJump)),
+        _ => None,
+    }
+}
+
+// 秒数换算成步数
+pub fn duration_ticks(power_up: &dyn PowerUp) -> u32 {
+    (power_up.duration() / FIXED_DT).round() as u32
+}
+
+// 正在生效的道具和剩余步数
+pub struct Active {
+    pub power_up: Box<dyn PowerUp>,
+    pub remaining: u32,
+    pub duration: u32,
+}
+
+// 护盾：抵挡一次碰撞
+struct Shield;
+
+impl PowerUp for Shield {
+    fn name(&self) -> &'static str {
+        "shield"
+    }
+
+    fn label(&self, chinese: bool) -> &'static str {
+        if chinese {
+            "护盾"
+        } else {
+            "Shield"
+        }
+    }
+
+    fn symbol(&self) -> &'static str {
+        "S"
+    }
+
+    fn color(&self) -> Color {
+        Color::from_rgba(70, 150, 230, 255)
+    }
+
+    fn duration(&self) -> f32 {
+        10.0
+    }
+
+    fn absorbs_hit(&self) -> bool {
+        true
+    }
+}
+
+// 磁铁吸引星星的范围和每秒移动的距离
+const MAGNET_RADIUS: f32 = 250.0;
+const MAGNET_PULL: f32 = 500.0;
+
+// 磁铁：把附近同一车道的星星吸向恐龙
+struct Magnet;
+
+impl PowerUp for Magnet {
+    fn name(&self) -> &'static str {
+        "magnet"
+    }
+
+    fn label(&self, chinese: bool) -> &'static str {
+        if chinese {
+            "磁铁"
+        } else {
+            "Magnet"
+        }
+    }
+
+    fn symbol(&self) -> &'static str {
+        "M"
+    }
+
+    fn color(&self) -> Color {
+        Color::from_rgba(220, 70, 70, 255)
+    }
+
+    fn duration(&self) -> f32 {
+        8.0
+    }
+
+    fn step(&mut self, game: &mut GameState) {
+        let center = vec2(DINO_X + DINO_WIDTH / 2.0, game.dino.y + DINO_HEIGHT / 2.0);
+        game.world.pull_collectibles(
+            center,
+            game.dino.lane,
+            MAGNET_RADIUS,
+            MAGNET_PULL * FIXED_DT,
+        );
+    }
+}
+
+// 慢动作：画面以 60% 的速度推进，给玩家更多反应时间
+struct SlowMotion;
+
+impl PowerUp for SlowMotion {
+    fn name(&self) -> &'static str {
+        "slow_motion"
+    }
+
+    fn label(&self, chinese: bool) -> &'static str {
+        if chinese {
+            "慢动作"
+        } else {
+            "Slow motion"
+        }
+    }
+
+    fn symbol(&self) -> &'static str {
+        "T"
+    }
+
+    fn color(&self) -> Color {
+        Color::from_rgba(150, 90, 210, 255)
+    }
+
+    fn duration(&self) -> f32 {
+        5.0
+    }
+
+    fn time_scale(&self) -> f32 {
+        0.6
+    }
+}
+
+// 双倍得分：越过障碍物和收集星星的得分翻倍
+struct DoubleScore;
+
+impl PowerUp for DoubleScore {
+    fn name(&self) -> &'static str {
+        "double_score"
+    }
+
+    fn label(&self, chinese: bool) -> &'static str {
+        if chinese {
+            "双倍得分"
+        } else {
+            "Double score"
+        }
+    }
+
+    fn symbol(&self) -> &'static str {
+        "2"
+    }
+
+    fn color(&self) -> Color {
+        Color::from_rgba(230, 170, 30, 255)
+    }
+
+    fn duration(&self) -> f32 {
+        10.0
+    }
+
+    fn score_multiplier(&self) -> u32 {
+        2
+    }
+}
+
+// 超级跳跃：跳得更高。剩余步数记在恐龙身上，可通过性检查按同样的规则模拟
+struct HighJump;
+
+impl PowerUp for HighJump {
+    fn name(&self) -> &'static str {
+        "high_jump"
+    }
+
+    fn label(&self, chinese: bool) -> &'static str {
+        if chinese {
+            "超级跳跃"
+        } else {
+            "High jump"
+        }
+    }
+
+    fn symbol(&self) -> &'static str {
+        "J"
+    }
+
+    fn color(&self) -> Color {
+        Color::from_rgba(60, 170, 90, 255)
+    }
+
+    fn duration(&self) -> f32 {
+        8.0
+    }
+
+    fn start(&mut self, game: &mut GameState) {
+        game.dino.high_jump_ticks = duration_ticks(self);
+    }
+
+    fn end(&mut self, game: &mut GameState) {
+        game.dino.high_jump_ticks = 0;
+    }
+}
+
+// 场上的道具图标：带字母的彩色圆
+pub fn draw_pickup(name: &str, x: f32, y: f32, radius: f32, palette: &Palette) {
+    let Some(power_up) = by_name(name) else {
+        return;
+    };
+    draw_circle(x, y, radius, palette.adapt(power_up.color()));
+    draw_circle_lines(x, y, radius, 2.0, palette.star_outline);
+    let symbol = power_up.symbol();
+    let size = measure_text(symbol, None, 20, 1.0);
+    draw_text(
+        symbol,
+        x - size.width / 2.0,
+        y + size.offset_y / 2.0,
+        20.0,
+        palette.adapt(WHITE),
+    );
+}
+
+// 左上角列出生效中的道具和剩余时间
+pub fn draw_timers(active: &[Active], palette: &Palette, font: Option<&Font>, top: f32) {
+    let chinese = font.is_some();
+    for (i, effect) in active.iter().enumerate() {
+        let y = top + i as f32 * 24.0;
+        let color = palette.adapt(effect.power_up.color());
+        draw_circle(28.0, y - 6.0, 8.0, color);
+        let text = format!(
+            "{} {:.1}s",
+            effect.power_up.label(chinese),
+            effect.remaining as f32 * FIXED_DT
+        );
+        draw_label(font, &text, 42.0, y, 20, palette.text);
+        // 剩余时间条
+        let fraction = effect.remaining as f32 / effect.duration.max(1) as f32;
+        draw_rectangle(42.0, y + 4.0, 100.0 * fraction, 3.0, color);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bot::PlannerBot;
+    use crate::defs::EntityDefs;
+    use crate::events::tests::EventLog;
+    use crate::events::GameEvent;
+    use crate::input::ActionState;
+
+    #[test]
+    fn effects_expire_absorb_hits_and_end_on_reset() {
+        let mut game = GameState::new(EntityDefs::default());
+        game.reseed(3);
+        let log = EventLog::default();
+        game.events.subscribe(Box::new(log.clone()));
+        let idle = ActionState::default();
+
+        // 站着不动，护盾挡下第一次碰撞后消失，下一次碰撞才结束游戏，结束时所有效果一起移除
+        game.activate_power_up("shield");
+        game.activate_power_up("double_score");
+        assert_eq!(game.score_multiplier(), 2);
+        while !game.game_over {
+            game.update(&idle);
+        }
+        assert!(log.0.borrow().contains(&GameEvent::HitAbsorbed));
+        assert!(game.power_ups.is_empty());
+
+        // 到期后移除，超级跳跃的剩余步数随之清零；完美机器人按更高的跳跃重新规划
+        game.revive();
+        game.bot = Some(Box::new(PlannerBot::default()));
+        game.activate_power_up("high_jump");
+        assert!(game.dino.high_jump_ticks > 0);
+        for _ in 0..duration_ticks(&HighJump) {
+            game.update(&idle);
+        }
+        assert!(!game.game_over);
+        assert_eq!(game.dino.high_jump_ticks, 0);
+        assert!(game.power_ups.is_empty());
+
+        game.activate_power_up("slow_motion");
+        assert!(game.time_scale() < 1.0);
+        game.reset();
+        assert!(game.power_ups.is_empty());
+        assert_eq!(game.time_scale(), 1.0);
+    }
+
+    #[test]
+    fn picking_up_an_active_power_up_ends_the_old_one_first() {
+        let mut game = GameState::new(EntityDefs::default());
+        game.reseed(3);
+        game.invincible = true;
+        let log = EventLog::default();
+        game.events.subscribe(Box::new(log.clone()));
+        let idle = ActionState::default();
+
+        game.activate_power_up("high_jump");
+        for _ in 0..60 {
+            game.update(&idle);
+        }
+        game.activate_power_up("high_jump");
+        game.events.dispatch();
+
+        let name = "high_jump";
+        let power_up_events: Vec<_> = log
+            .0
+            .borrow()
+            .iter()
+            .filter(|event| {
+                matches!(
+                    event,
+                    GameEvent::PowerUpCollected { .. } | GameEvent::PowerUpEnded { .. }
+                )
+            })
+            .cloned()
+            .collect();
+        assert_eq!(
+            power_up_events,
+            [
+                GameEvent::PowerUpCollected { name },
+                GameEvent::PowerUpEnded { name },
+                GameEvent::PowerUpCollected { name },
+            ]
+        );
+        assert_eq!(game.power_ups.len(), 1);
+        assert_eq!(game.power_ups[0].remaining, duration_ticks(&HighJump));
+        assert_eq!(game.dino.high_jump_ticks, duration_ticks(&HighJump));
+    }
+}
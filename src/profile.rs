@@ -0,0 +1,109 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+
+use crate::achievements::{Achievements, ACHIEVEMENTS_FILE};
+use crate::cosmetics::{Look, COSMETICS_FILE};
+use crate::daily::save_toml;
+use crate::ghost::GhostFile;
+use crate::MAX_LANES;
+
+const PROFILES_DIR: &str = "profiles";
+pub const DEFAULT_PROFILE: &str = "default";
+const BEST_GHOST_FILE: &str = "best.ghost.toml";
+
+// 玩家档案：每个档案的数据保存在 profiles/<名字>/ 目录下
+pub struct Profile {
+    pub name: String,
+    // 每种车道数各自的个人最佳录像，用作幽灵
+    bests: BTreeMap<usize, GhostFile>,
+    pub achievements: Achievements,
+    // 恐龙的外观
+    pub look: Look,
+}
+
+impl Profile {
+    pub fn load(name: &str) -> Self {
+        let name = if valid_name(name) {
+            name.to_string()
+        } else {
+            println!(
+                "Warning: invalid profile name '{}', using '{}'",
+                name, DEFAULT_PROFILE
+            );
+            DEFAULT_PROFILE.to_string()
+        };
+
+        let dir = PathBuf::from(PROFILES_DIR).join(&name);
+        let achievements = Achievements::load(dir.join(ACHIEVEMENTS_FILE));
+        let look = Look::load(&dir.join(COSMETICS_FILE), &achievements);
+        let mut profile = Self {
+            name,
+            bests: BTreeMap::new(),
+            achievements,
+            look,
+        };
+        for lanes in 1..=MAX_LANES {
+            let path = profile.path(&best_ghost_file(lanes));
+            if !path.exists() {
+                continue;
+            }
+            match GhostFile::load(&path) {
+                Ok(ghost) => {
+                    profile.bests.insert(lanes, ghost);
+                }
+                Err(err) => println!("Warning: failed to load {}: {}", path.display(), err),
+            }
+        }
+        profile
+    }
+
+    pub fn path(&self, file: &str) -> PathBuf {
+        PathBuf::from(PROFILES_DIR).join(&self.name).join(file)
+    }
+
+    // 指定车道数下的个人最佳
+    pub fn best(&self, lanes: usize) -> Option<&GhostFile> {
+        self.bests.get(&lanes)
+    }
+
+    // 一局结束后调用，超过同一车道数下的个人最佳时保存录像
+    pub fn submit_run(&mut self, run: GhostFile) {
+        if self
+            .best(run.lanes)
+            .is_some_and(|best| best.score >= run.score)
+        {
+            return;
+        }
+        let path = self.path(&best_ghost_file(run.lanes));
+        let result = fs::create_dir_all(PathBuf::from(PROFILES_DIR).join(&self.name))
+            .map_err(|err| err.to_string())
+            .and_then(|_| run.save(&path));
+        match result {
+            Ok(()) => println!("New personal best: {}", run.score),
+            Err(err) => println!("Warning: failed to save {}: {}", path.display(), err),
+        }
+        self.bests.insert(run.lanes, run);
+    }
+
+    pub fn save_look(&self) {
+        save_toml(&self.path(COSMETICS_FILE), &self.look);
+    }
+}
+
+// 经典模式沿用原来的文件名，多车道模式的个人最佳分开保存
+fn best_ghost_file(lanes: usize) -> String {
+    if lanes == 1 {
+        BEST_GHOST_FILE.to_string()
+    } else {
+        format!("best-{}-lanes.ghost.toml", lanes)
+    }
+}
+
+// 名字只允许字母、数字、- 和 _，避免写到档案目录之外
+pub fn valid_name(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
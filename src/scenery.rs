@@ -0,0 +1,216 @@
+use ::rand::rngs::StdRng;
+use ::rand::{Rng, SeedableRng};
+use macroquad::prelude::*;
+
+use crate::theme::Palette;
+
+// 远景视差层：夜空的月亮和星星、远山、云朵
+// 每层按游戏速度的一个比例滚动，物体移出屏幕后在右侧重新随机生成，不会产生新的分配
+const SKY_STAR_SPEED: f32 = 0.02;
+const MOON_SPEED: f32 = 0.01;
+const HILL_SPEED: f32 = 0.15;
+const CLOUD_SPEED: f32 = 0.3;
+
+const SKY_STAR_COUNT: usize = 24;
+const HILL_COUNT: usize = 6;
+const CLOUD_COUNT: usize = 5;
+
+// 和游戏使用同一个种子，但用独立的随机数流，避免背景影响障碍物生成
+const SCENERY_SEED_SALT: u64 = 0x5CE7_E12A;
+
+struct SkyStar {
+    x: f32,
+    y: f32,
+    size: f32,
+}
+
+struct Hill {
+    x: f32,
+    width: f32,
+    height: f32,
+}
+
+struct Cloud {
+    x: f32,
+    y: f32,
+    scale: f32,
+}
+
+pub struct Scenery {
+    rng: StdRng,
+    ground_y: f32,
+    sky_stars: Vec<SkyStar>,
+    moon_x: f32,
+    hills: Vec<Hill>,
+    clouds: Vec<Cloud>,
+}
+
+impl Scenery {
+    pub fn new(seed: u64, ground_y: f32, view_width: f32) -> Self {
+        let mut rng = StdRng::seed_from_u64(seed ^ SCENERY_SEED_SALT);
+
+        let sky_stars = (0..SKY_STAR_COUNT)
+            .map(|_| SkyStar {
+                x: rng.gen_range(0.0..view_width),
+                y: rng.gen_range(20.0..ground_y * 0.6),
+                size: rng.gen_range(1.0..2.5),
+            })
+            .collect();
+
+        let mut hills = Vec::with_capacity(HILL_COUNT);
+        let mut x = rng.gen_range(-100.0..0.0);
+        for _ in 0..HILL_COUNT {
+            let width = rng.gen_range(140.0..280.0);
+            hills.push(Hill {
+                x,
+                width,
+                height: rng.gen_range(40.0..110.0),
+            });
+            x += width * rng.gen_range(0.5..0.9);
+        }
+
+        let clouds = (0..CLOUD_COUNT)
+            .map(|i| Cloud {
+                x: view_width * i as f32 / CLOUD_COUNT as f32 + rng.gen_range(0.0..80.0),
+                y: rng.gen_range(60.0..ground_y * 0.5),
+                scale: rng.gen_range(0.7..1.3),
+            })
+            .collect();
+
+        Self {
+            rng,
+            ground_y,
+            sky_stars,
+            moon_x: view_width * 0.75,
+            hills,
+            clouds,
+        }
+    }
+
+    pub fn update(&mut self, dt: f32, game_speed: f32, view_width: f32) {
+        let rng = &mut self.rng;
+
+        for star in &mut self.sky_stars {
+            star.x -= game_speed * SKY_STAR_SPEED * dt;
+            if star.x < -star.size {
+                star.x = view_width + rng.gen_range(0.0..40.0);
+                star.y = rng.gen_range(20.0..self.ground_y * 0.6);
+                star.size = rng.gen_range(1.0..2.5);
+            }
+        }
+
+        self.moon_x -= game_speed * MOON_SPEED * dt;
+        if self.moon_x < -40.0 {
+            self.moon_x = view_width + 40.0;
+        }
+
+        // 远山首尾相接，最左边的山完全移出后接到最右边那座山后面
+        let rightmost = self
+            .hills
+            .iter()
+            .map(|hill| hill.x + hill.width)
+            .fold(f32::MIN, f32::max);
+        let mut next_x = rightmost.max(view_width);
+        for hill in &mut self.hills {
+            hill.x -= game_speed * HILL_SPEED * dt;
+            if hill.x + hill.width < 0.0 {
+                hill.width = rng.gen_range(140.0..280.0);
+                hill.height = rng.gen_range(40.0..110.0);
+                hill.x = next_x - hill.width * rng.gen_range(0.1..0.5);
+                next_x = hill.x + hill.width;
+            }
+        }
+
+        for cloud in &mut self.clouds {
+            cloud.x -= game_speed * CLOUD_SPEED * dt;
+            if cloud.x < -80.0 * cloud.scale {
+                cloud.x = view_width + rng.gen_range(20.0..200.0);
+                cloud.y = rng.gen_range(60.0..self.ground_y * 0.5);
+                cloud.scale = rng.gen_range(0.7..1.3);
+            }
+        }
+    }
+
+    // night 为 0 时月亮和星星完全隐藏
+    pub fn draw(&self, palette: &Palette, night: f32) {
+        if night > 0.0 {
+            let sky = Color::new(palette.ground.r, palette.ground.g, palette.ground.b, night);
+            for star in &self.sky_stars {
+                draw_rectangle(star.x, star.y, star.size, star.size, sky);
+            }
+
+            // 月牙：先画满月，再用背景色盖住一部分
+            let moon_y = 70.0;
+            draw_circle(self.moon_x, moon_y, 18.0, sky);
+            let shadow = Color::new(
+                palette.background.r,
+                palette.background.g,
+                palette.background.b,
+                night,
+            );
+            draw_circle(self.moon_x + 8.0, moon_y - 4.0, 15.0, shadow);
+        }
+
+        for hill in &self.hills {
+            draw_triangle(
+                vec2(hill.x, self.ground_y),
+                vec2(hill.x + hill.width / 2.0, self.ground_y - hill.height),
+                vec2(hill.x + hill.width, self.ground_y),
+                palette.hill,
+            );
+        }
+
+        for cloud in &self.clouds {
+            let s = cloud.scale;
+            draw_rectangle(cloud.x, cloud.y, 70.0 * s, 14.0 * s, palette.cloud);
+            draw_circle(cloud.x + 20.0 * s, cloud.y, 14.0 * s, palette.cloud);
+            draw_circle(
+                cloud.x + 42.0 * s,
+                cloud.y - 4.0 * s,
+                18.0 * s,
+                palette.cloud,
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 所有物体的位置和大小
+    fn layout(scenery: &Scenery) -> Vec<f32> {
+        let stars = scenery.sky_stars.iter().flat_map(|s| [s.x, s.y, s.size]);
+        let hills = scenery.hills.iter().flat_map(|h| [h.x, h.width, h.height]);
+        let clouds = scenery.clouds.iter().flat_map(|c| [c.x, c.y, c.scale]);
+        stars
+            .chain(hills)
+            .chain(clouds)
+            .chain([scenery.moon_x])
+            .collect()
+    }
+
+    fn run(seed: u64) -> Scenery {
+        let mut scenery = Scenery::new(seed, 300.0, 800.0);
+        for _ in 0..60 * 60 {
+            scenery.update(1.0 / 60.0, 600.0, 800.0);
+        }
+        scenery
+    }
+
+    #[test]
+    fn same_seed_gives_the_same_scenery() {
+        assert_eq!(layout(&run(7)), layout(&run(7)));
+        assert_ne!(layout(&run(7)), layout(&run(8)));
+
+        // 一分钟后所有物体都已重新生成过，远山仍然首尾相接地铺满画面
+        let scenery = run(7);
+        let mut hills: Vec<&Hill> = scenery.hills.iter().collect();
+        hills.sort_by(|a, b| a.x.total_cmp(&b.x));
+        assert!(hills[0].x <= 0.0);
+        for pair in hills.windows(2) {
+            assert!(pair[1].x <= pair[0].x + pair[0].width);
+        }
+        assert!(hills.iter().any(|hill| hill.x + hill.width >= 800.0));
+    }
+}
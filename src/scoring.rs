@@ -0,0 +1,202 @@
+use macroquad::prelude::*;
+
+use crate::events::{EventBus, GameEvent, Listener};
+use crate::theme::Palette;
+use crate::ui::{draw_label, label_width};
+use crate::FIXED_DT;
+
+// 计分：每一步越过的障碍物、收集和漏掉的星星都交给 Scoring 结算，结算结果以 ScoreEvent 的形式
+// 放进事件队列，音效、飘字等订阅者据此给出反馈
+
+// 连续收集星星时连击数加一，星星得分乘以连击倍数（最多 MAX_COMBO_MULTIPLIER 倍）；
+// 漏掉本车道的一颗星星或超过 COMBO_TIMEOUT 秒没有收集到星星时连击清零
+const COMBO_TIMEOUT: f32 = 5.0;
+const MAX_COMBO_MULTIPLIER: u32 = 5;
+// 得分飘字持续的秒数和每秒上升的像素
+const POPUP_LIFETIME: f32 = 0.8;
+const POPUP_RISE: f32 = 50.0;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum ScoreEvent {
+    // 越过障碍物获得 points 分
+    Passed {
+        points: u32,
+    },
+    // 收集一颗星星：基础分、总倍数（连击倍数乘以道具倍数）、实际得分、当前连击数和星星的位置
+    Star {
+        base: u32,
+        multiplier: u32,
+        points: u32,
+        combo: u32,
+        pos: Vec2,
+    },
+    // 连击中断，combo 为中断前的连击数
+    ComboBroken {
+        combo: u32,
+    },
+}
+
+#[derive(Default)]
+pub struct Scoring {
+    combo: u32,
+    // 距离连击超时还剩的步数
+    combo_timer: u32,
+}
+
+impl Scoring {
+    pub fn reset(&mut self) {
+        *self = Self::default();
+    }
+
+    pub fn combo(&self) -> u32 {
+        self.combo
+    }
+
+    // 结算一步的得分并加到 score 上。multiplier 为道具带来的得分倍数
+    pub fn settle(
+        &mut self,
+        events: &mut EventBus,
+        score: &mut u32,
+        passed_score: u32,
+        stars: &[(Vec2, u32)],
+        missed_stars: u32,
+        multiplier: u32,
+    ) {
+        self.combo_timer = self.combo_timer.saturating_sub(1);
+        if self.combo_timer == 0 || missed_stars > 0 {
+            self.break_combo(events);
+        }
+
+        if passed_score > 0 {
+            let points = passed_score * multiplier;
+            *score += points;
+            events.emit(GameEvent::Score(ScoreEvent::Passed { points }));
+        }
+
+        for &(pos, base) in stars {
+            self.combo += 1;
+            self.combo_timer = (COMBO_TIMEOUT / FIXED_DT) as u32;
+            let multiplier = self.combo.min(MAX_COMBO_MULTIPLIER) * multiplier;
+            let points = base * multiplier;
+            *score += points;
+            events.emit(GameEvent::Score(ScoreEvent::Star {
+                base,
+                multiplier,
+                points,
+                combo: self.combo,
+                pos,
+            }));
+        }
+    }
+
+    fn break_combo(&mut self, events: &mut EventBus) {
+        if self.combo > 0 {
+            events.emit(GameEvent::Score(ScoreEvent::ComboBroken {
+                combo: self.combo,
+            }));
+        }
+        self.combo = 0;
+        self.combo_timer = 0;
+    }
+}
+
+struct Popup {
+    pos: Vec2,
+    text: String,
+    age: f32,
+}
+
+// 在收集星星的位置飘出 "+5 x3" 这样的得分，随游戏逻辑的固定步长上升并淡出
+#[derive(Default)]
+pub struct Popups {
+    popups: Vec<Popup>,
+}
+
+impl Listener for Popups {
+    fn on_event(&mut self, event: &GameEvent) {
+        match *event {
+            GameEvent::Score(ScoreEvent::Star {
+                base,
+                multiplier,
+                pos,
+                ..
+            }) => {
+                let text = if multiplier > 1 {
+                    format!("+{} x{}", base, multiplier)
+                } else {
+                    format!("+{}", base)
+                };
+                self.popups.push(Popup {
+                    pos,
+                    text,
+                    age: 0.0,
+                });
+            }
+            GameEvent::Reset => self.popups.clear(),
+            _ => {}
+        }
+    }
+
+    fn step(&mut self, dt: f32) {
+        for popup in &mut self.popups {
+            popup.age += dt;
+            popup.pos.y -= POPUP_RISE * dt;
+        }
+        self.popups.retain(|popup| popup.age < POPUP_LIFETIME);
+    }
+
+    fn draw(&self, palette: &Palette, font: Option<&Font>) {
+        for popup in &self.popups {
+            let color = Color {
+                a: 1.0 - popup.age / POPUP_LIFETIME,
+                ..palette.star_outline
+            };
+            let x = popup.pos.x - label_width(font, &popup.text, 20) / 2.0;
+            draw_label(font, &popup.text, x, popup.pos.y, 20, color);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::events::tests::EventLog;
+
+    #[test]
+    fn combo_grows_with_consecutive_stars_and_resets() {
+        let mut scoring = Scoring::default();
+        let mut events = EventBus::default();
+        let log = EventLog::default();
+        events.subscribe(Box::new(log.clone()));
+        let mut score = 0;
+        let star = [(Vec2::ZERO, 5)];
+        for _ in 0..6 {
+            scoring.settle(&mut events, &mut score, 0, &star, 0, 1);
+        }
+        // 5 + 10 + 15 + 20 + 25 + 25：倍数封顶
+        assert_eq!(score, 100);
+        assert_eq!(scoring.combo(), 6);
+
+        // 漏掉一颗星星后连击清零，道具倍数叠加在连击倍数上
+        scoring.settle(&mut events, &mut score, 10, &star, 1, 2);
+        assert_eq!(score, 100 + 20 + 10);
+        assert_eq!(scoring.combo(), 1);
+
+        // 超时后连击同样清零
+        for _ in 0..(COMBO_TIMEOUT / FIXED_DT) as u32 {
+            scoring.settle(&mut events, &mut score, 0, &[], 0, 1);
+        }
+        assert_eq!(scoring.combo(), 0);
+
+        events.dispatch();
+        let log = log.0.borrow();
+        assert_eq!(log.len(), 6 + 3 + 1);
+        let score_event = GameEvent::Score;
+        assert_eq!(log[6], score_event(ScoreEvent::ComboBroken { combo: 6 }));
+        assert_eq!(log[7], score_event(ScoreEvent::Passed { points: 20 }));
+        assert_eq!(
+            log.last(),
+            Some(&score_event(ScoreEvent::ComboBroken { combo: 1 }))
+        );
+    }
+}
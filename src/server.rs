@@ -0,0 +1,382 @@
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+use serde::{Deserialize, Serialize};
+
+use crate::daily::{load_toml, save_toml};
+use crate::defs::EntityDefs;
+use crate::ghost::{Ghost, GhostFile};
+use crate::leaderboard::{json, Accepted, Rejected, CONNECT_TIMEOUT};
+use crate::profile::valid_name;
+
+// 排行榜服务器：在无窗口的 GameState 中重新模拟上传的录像，协议见 README。
+// 只编译进 src/bin/leaderboard.rs 使用的库（src/lib.rs），游戏程序中只有客户端（src/leaderboard.rs）
+
+pub const DEFAULT_SERVER_ADDRESS: &str = "0.0.0.0:7879";
+const LEADERBOARD_PATH: &str = "leaderboard.toml";
+// 排行榜保留的名次数量，每名玩家只保留最好的一次
+const BOARD_SIZE: usize = 50;
+// 接受的最长一局（30 分钟），限制服务器重新模拟的时间
+const MAX_RUN_TICKS: u64 = 60 * 60 * 30;
+// 录像的视野宽度范围，视野越宽障碍物出现得越早
+const VIEW_WIDTH_RANGE: std::ops::RangeInclusive<f32> = 320.0..=3840.0;
+const MAX_NAME_LENGTH: usize = 32;
+// 请求体的大小上限，30 分钟的录像编码后远小于这个值
+const MAX_BODY_BYTES: usize = 1 << 20;
+// 请求行加上所有请求头的大小上限
+const MAX_HEAD_BYTES: u64 = 8 << 10;
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct BoardEntry {
+    pub player: String,
+    pub score: u32,
+    pub ticks: u64,
+}
+
+// 排行榜，按分数从高到低排列
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Board {
+    pub entries: Vec<BoardEntry>,
+}
+
+impl Board {
+    // 返回本次成绩的名次（从 1 开始）；没有超过这名玩家之前的成绩或没有进入排行榜时返回 None
+    pub fn submit(&mut self, player: &str, score: u32, ticks: u64) -> Option<usize> {
+        if let Some(index) = self.entries.iter().position(|entry| entry.player == player) {
+            if self.entries[index].score >= score {
+                return None;
+            }
+            self.entries.remove(index);
+        }
+        let rank = self.entries.partition_point(|entry| entry.score >= score);
+        if rank >= BOARD_SIZE {
+            return None;
+        }
+        self.entries.insert(
+            rank,
+            BoardEntry {
+                player: player.to_string(),
+                score,
+                ticks,
+            },
+        );
+        self.entries.truncate(BOARD_SIZE);
+        Some(rank + 1)
+    }
+}
+
+// 重新模拟录像：必须恰好在第 ticks 步撞上障碍物且不再复活，并且得到相同的分数
+pub fn verify(file: &GhostFile, defs: &EntityDefs) -> Result<(), String> {
+    if file.player.len() > MAX_NAME_LENGTH || !valid_name(&file.player) {
+        return Err(format!("invalid player name '{}'", file.player));
+    }
+    if file.lanes != 1 {
+        return Err("only classic single-lane runs are ranked".to_string());
+    }
+    if file.ticks > MAX_RUN_TICKS {
+        return Err("run is too long".to_string());
+    }
+    if !VIEW_WIDTH_RANGE.contains(&file.view_width) {
+        return Err(format!("invalid view width {}", file.view_width));
+    }
+    file.check()?;
+    if file.revives.contains(&file.ticks) {
+        return Err("run did not end".to_string());
+    }
+
+    let mut ghost = Ghost::new(file.clone(), defs)?;
+    ghost.advance_to(file.ticks);
+    if ghost.ticks() != file.ticks || !ghost.game_over() {
+        return Err("replay does not end where the run ended".to_string());
+    }
+    if ghost.score() != file.score {
+        return Err(format!(
+            "replay scores {} instead of {}",
+            ghost.score(),
+            file.score
+        ));
+    }
+    Ok(())
+}
+
+struct Request {
+    method: String,
+    path: String,
+    body: String,
+}
+
+// 请求头和请求体分别限制读取的字节数，不带换行的超长请求行也不会无限占用内存
+fn read_request(stream: &TcpStream) -> Result<Request, String> {
+    let mut reader = BufReader::new(stream.take(MAX_HEAD_BYTES));
+    let mut line = String::new();
+    reader.read_line(&mut line).map_err(|err| err.to_string())?;
+    let mut parts = line.split_whitespace();
+    let (Some(method), Some(path)) = (parts.next(), parts.next()) else {
+        return Err("malformed request line".to_string());
+    };
+    let (method, path) = (method.to_string(), path.to_string());
+
+    let mut length = 0;
+    loop {
+        line.clear();
+        reader.read_line(&mut line).map_err(|err| err.to_string())?;
+        if !line.ends_with('\n') {
+            return Err("request head is too large or incomplete".to_string());
+        }
+        let header = line.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            if name.eq_ignore_ascii_case("content-length") {
+                length = value.trim().parse().map_err(|_| "invalid content length")?;
+            }
+        }
+    }
+    if length > MAX_BODY_BYTES {
+        return Err("request body is too large".to_string());
+    }
+    reader.get_mut().set_limit(length as u64);
+    let mut body = vec![0; length];
+    reader
+        .read_exact(&mut body)
+        .map_err(|err| err.to_string())?;
+    let body = String::from_utf8(body).map_err(|err| err.to_string())?;
+    Ok(Request { method, path, body })
+}
+
+fn write_response(mut stream: &TcpStream, status: u16, body: &str) -> io::Result<()> {
+    let reason = match status {
+        200 => "OK",
+        400 => "Bad Request",
+        404 => "Not Found",
+        _ => "Unprocessable Entity",
+    };
+    write!(
+        stream,
+        "HTTP/1.1 {} {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        reason,
+        body.len(),
+        body
+    )?;
+    stream.flush()
+}
+
+fn rejected(error: String) -> String {
+    json(&Rejected { error })
+}
+
+// 每个连接在自己的线程中处理，慢的客户端不会挡住其他人的提交
+pub struct Server {
+    listener: TcpListener,
+    handler: Handler,
+}
+
+// 各个连接线程共用的排行榜和障碍物定义
+#[derive(Clone)]
+struct Handler {
+    board: Arc<Mutex<Board>>,
+    path: Arc<PathBuf>,
+    defs: Arc<EntityDefs>,
+}
+
+impl Server {
+    pub fn bind(address: &str, path: PathBuf, defs: EntityDefs) -> io::Result<Self> {
+        Ok(Self {
+            listener: TcpListener::bind(address)?,
+            handler: Handler {
+                board: Arc::new(Mutex::new(load_toml(&path))),
+                path: Arc::new(path),
+                defs: Arc::new(defs),
+            },
+        })
+    }
+
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.listener.local_addr()
+    }
+
+    // 接受下一个连接，在新线程中处理
+    pub fn handle_next(&self) -> io::Result<()> {
+        let (stream, _) = self.listener.accept()?;
+        stream.set_read_timeout(Some(CONNECT_TIMEOUT))?;
+        stream.set_write_timeout(Some(CONNECT_TIMEOUT))?;
+        let handler = self.handler.clone();
+        thread::spawn(move || {
+            if let Err(err) = handler.handle(&stream) {
+                println!("Warning: leaderboard request failed: {}", err);
+            }
+        });
+        Ok(())
+    }
+}
+
+impl Handler {
+    fn handle(&self, stream: &TcpStream) -> io::Result<()> {
+        let (status, body) = match read_request(stream) {
+            Ok(request) => self.respond(&request),
+            Err(err) => (400, rejected(err)),
+        };
+        write_response(stream, status, &body)
+    }
+
+    fn respond(&self, request: &Request) -> (u16, String) {
+        match (request.method.as_str(), request.path.trim_end_matches('/')) {
+            ("GET", "/scores") => (200, json(&*self.board.lock().unwrap())),
+            ("POST", "/scores") => {
+                let file: GhostFile = match serde_json::from_str(&request.body) {
+                    Ok(file) => file,
+                    Err(err) => return (400, rejected(err.to_string())),
+                };
+                if let Err(err) = verify(&file, &self.defs) {
+                    println!("Rejected {} ({}): {}", file.player, file.score, err);
+                    return (422, rejected(err));
+                }
+                let mut board = self.board.lock().unwrap();
+                let rank = board.submit(&file.player, file.score, file.ticks);
+                println!("Accepted {} ({}), rank {:?}", file.player, file.score, rank);
+                if rank.is_some() {
+                    save_toml(&self.path, &*board);
+                }
+                (200, json(&Accepted { rank }))
+            }
+            _ => (404, rejected("not found".to_string())),
+        }
+    }
+}
+
+// 排行榜服务器程序（src/bin/leaderboard.rs）的主循环，运行到进程退出
+pub fn serve(address: &str) {
+    let server = match Server::bind(address, PathBuf::from(LEADERBOARD_PATH), EntityDefs::load()) {
+        Ok(server) => server,
+        Err(err) => {
+            eprintln!("Error: failed to listen on {}: {}", address, err);
+            return;
+        }
+    };
+    if let Ok(local) = server.local_addr() {
+        println!("Leaderboard server listening on {}", local);
+    }
+    loop {
+        if let Err(err) = server.handle_next() {
+            println!("Warning: leaderboard request failed: {}", err);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ghost::tests::scripted_jumps;
+    use crate::leaderboard::submit;
+    use crate::GameState;
+
+    // 按固定节奏起跳跑完的一局，中途复活一次
+    fn recorded_run() -> GhostFile {
+        let mut game = GameState::new(EntityDefs::default());
+        game.reseed(11);
+        let mut revived = false;
+        while game.ticks < 60 * 120 {
+            game.update(&scripted_jumps(game.ticks));
+            if game.game_over {
+                if revived {
+                    break;
+                }
+                game.revive();
+                revived = true;
+            }
+        }
+        assert!(game.game_over);
+        GhostFile::new("tester", &game)
+    }
+
+    #[test]
+    fn verification_rejects_tampered_runs() {
+        let defs = EntityDefs::default();
+        let file = recorded_run();
+        assert_eq!(verify(&file, &defs), Ok(()));
+
+        let inflated = GhostFile {
+            score: file.score + 10,
+            ..file.clone()
+        };
+        assert!(verify(&inflated, &defs).is_err());
+        let cut_short = GhostFile {
+            ticks: file.ticks - 1,
+            inputs: crate::ghost::encode_inputs(
+                &crate::ghost::decode_inputs(&file.inputs).unwrap()[..file.ticks as usize - 1],
+            ),
+            ..file.clone()
+        };
+        assert!(verify(&cut_short, &defs).is_err());
+        let huge = GhostFile {
+            inputs: "99999999999-".to_string(),
+            ..file.clone()
+        };
+        assert!(verify(&huge, &defs).is_err());
+        let extra_revive = GhostFile {
+            revives: vec![file.ticks],
+            ..file
+        };
+        assert!(verify(&extra_revive, &defs).is_err());
+    }
+
+    #[test]
+    fn server_verifies_and_ranks_submissions() {
+        let path =
+            std::env::temp_dir().join(format!("dino-leaderboard-test-{}.toml", std::process::id()));
+        let server = Server::bind("127.0.0.1:0", path.clone(), EntityDefs::default()).unwrap();
+        let address = server.local_addr().unwrap();
+        let url = format!("http://{}", address);
+        let board = server.handler.board.clone();
+        thread::spawn(move || loop {
+            server.handle_next().unwrap();
+        });
+        // 一个连上后什么也不发的客户端不会挡住其他人的提交
+        let _idle = TcpStream::connect(address).unwrap();
+
+        let file = recorded_run();
+        assert_eq!(submit(&url, &file), Ok(Some(1)));
+        // 同一局再交一次不会刷新成绩
+        assert_eq!(submit(&url, &file), Ok(None));
+        let forged = GhostFile {
+            player: "cheater".to_string(),
+            score: file.score * 2,
+            ..file.clone()
+        };
+        assert!(submit(&url, &forged).is_err());
+
+        let board = board.lock().unwrap().clone();
+        assert_eq!(board.entries.len(), 1);
+        assert_eq!(board.entries[0].score, file.score);
+        let saved: Board = load_toml(&path);
+        assert_eq!(saved, board);
+        let _ = std::fs::remove_file(path);
+    }
+
+    #[test]
+    fn server_rejects_endless_request_lines() {
+        let path = std::env::temp_dir().join(format!(
+            "dino-leaderboard-head-test-{}.toml",
+            std::process::id()
+        ));
+        let server = Server::bind("127.0.0.1:0", path, EntityDefs::default()).unwrap();
+        let address = server.local_addr().unwrap();
+        // 请求行一直不换行，服务器读到上限后直接拒绝，不等到超时
+        let start = std::time::Instant::now();
+        let mut stream = TcpStream::connect(address).unwrap();
+        server.handle_next().unwrap();
+        stream
+            .write_all(&vec![b'A'; MAX_HEAD_BYTES as usize])
+            .unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        assert!(response.starts_with("HTTP/1.1 400"), "{}", response);
+        assert!(start.elapsed() < CONNECT_TIMEOUT);
+    }
+}
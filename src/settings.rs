@@ -0,0 +1,116 @@
+use std::collections::BTreeMap;
+use std::fs;
+
+use serde::{Deserialize, Serialize};
+
+use crate::leaderboard::DEFAULT_LEADERBOARD_URL;
+use crate::profile::DEFAULT_PROFILE;
+use crate::theme::DEFAULT_THEME;
+use crate::MAX_LANES;
+
+const SETTINGS_PATH: &str = "settings.toml";
+// 屏幕震动强度的上限
+pub const MAX_SCREEN_SHAKE: f32 = 2.0;
+
+// 保存在 settings.toml 中的玩家设置
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    // 主题名，对应内置主题或 themes/ 目录下的文件名
+    pub theme: String,
+    // 动作名 -> 按键名列表，例如 jump = ["Space", "Up", "W"]
+    pub bindings: BTreeMap<String, Vec<String>>,
+    // 当前玩家档案名，个人最佳等数据保存在 profiles/<档案名>/ 下
+    pub profile: String,
+    // 开始新一局时与个人最佳的幽灵赛跑
+    pub ghost: bool,
+    // 车道数：1 为经典模式，2 或 3 时用上下方向键在车道之间切换
+    pub lanes: usize,
+    // 游戏结束后按 U 上传成绩的排行榜服务器地址
+    pub leaderboard_url: String,
+    // 尘土、星星闪光和碰撞碎片等粒子特效，配置较低的电脑上可以关闭
+    pub particles: bool,
+    // 碰撞时屏幕震动的强度，1.0 为默认强度，0 为不震动
+    pub screen_shake: f32,
+    // 减少动态效果：关闭屏幕震动，适合容易晕动的玩家
+    pub reduced_motion: bool,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            theme: DEFAULT_THEME.to_string(),
+            bindings: BTreeMap::new(),
+            profile: DEFAULT_PROFILE.to_string(),
+            ghost: false,
+            lanes: 1,
+            leaderboard_url: DEFAULT_LEADERBOARD_URL.to_string(),
+            particles: true,
+            screen_shake: 1.0,
+            reduced_motion: false,
+        }
+    }
+}
+
+impl Settings {
+    pub fn load() -> Self {
+        let Ok(text) = fs::read_to_string(SETTINGS_PATH) else {
+            return Self::default();
+        };
+        match toml::from_str::<Self>(&text) {
+            Ok(settings) => settings.clamped(),
+            Err(err) => {
+                println!("Warning: failed to parse {}: {}", SETTINGS_PATH, err);
+                Self::default()
+            }
+        }
+    }
+
+    // 把手动改坏的数值限制在有效范围内；nan 和 inf 的震动强度恢复默认值
+    fn clamped(self) -> Self {
+        let screen_shake = if self.screen_shake.is_finite() {
+            self.screen_shake
+        } else {
+            Self::default().screen_shake
+        };
+        Self {
+            lanes: self.lanes.clamp(1, MAX_LANES),
+            screen_shake: screen_shake.clamp(0.0, MAX_SCREEN_SHAKE),
+            ..self
+        }
+    }
+
+    // 实际使用的屏幕震动强度
+    pub fn shake_intensity(&self) -> f32 {
+        if self.reduced_motion {
+            0.0
+        } else {
+            self.screen_shake
+        }
+    }
+
+    pub fn save(&self) {
+        let result = toml::to_string_pretty(self)
+            .map_err(|err| err.to_string())
+            .and_then(|text| fs::write(SETTINGS_PATH, text).map_err(|err| err.to_string()));
+        if let Err(err) = result {
+            println!("Warning: failed to save {}: {}", SETTINGS_PATH, err);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn out_of_range_values_are_clamped() {
+        let clamped = |text: &str| toml::from_str::<Settings>(text).unwrap().clamped();
+        assert_eq!(clamped("screen_shake = nan").screen_shake, 1.0);
+        assert_eq!(clamped("screen_shake = -inf").screen_shake, 1.0);
+        assert_eq!(clamped("screen_shake = 9.0").screen_shake, MAX_SCREEN_SHAKE);
+        assert_eq!(clamped("screen_shake = -1.0").screen_shake, 0.0);
+        assert_eq!(clamped("lanes = 0").lanes, 1);
+        assert_eq!(clamped("lanes = 9").lanes, MAX_LANES);
+    }
+}
@@ -0,0 +1,346 @@
+// 程序化音效合成（类似 sfxr）
+// 所有音效都由代码实时生成 PCM 数据，游戏发布时只需要一个可执行文件，不依赖音频资源
+
+pub const SAMPLE_RATE: u32 = 44100;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Waveform {
+    Square,
+    Triangle,
+    Noise,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum SoundEffect {
+    Jump,
+    Star,
+    Hit,
+    Revive,
+    WrongAnswer,
+    PowerUp,
+    ShieldBreak,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct SfxParams {
+    pub waveform: Waveform,
+    // 起始频率和结束频率（Hz），在整个音效时长内线性滑动
+    pub start_freq: f32,
+    pub end_freq: f32,
+    // 方波占空比 (0.0..1.0)
+    pub duty: f32,
+    // 琶音：经过 arp_time 秒后频率乘以 arp_ratio，1.0 表示不使用
+    pub arp_ratio: f32,
+    pub arp_time: f32,
+    // 音量包络（秒）：起音、保持、衰减
+    pub attack: f32,
+    pub sustain: f32,
+    pub decay: f32,
+    pub volume: f32,
+}
+
+impl SfxParams {
+    pub fn duration(&self) -> f32 {
+        self.attack + self.sustain + self.decay
+    }
+
+    pub fn sample_count(&self) -> usize {
+        (self.duration() * SAMPLE_RATE as f32).ceil() as usize
+    }
+}
+
+// 音效参数表，调整音色只需要修改这里
+pub const SFX_TABLE: [(SoundEffect, SfxParams); 7] = [
+    (
+        SoundEffect::Jump,
+        SfxParams {
+            waveform: Waveform::Square,
+            start_freq: 320.0,
+            end_freq: 640.0,
+            duty: 0.5,
+            arp_ratio: 1.0,
+            arp_time: 0.0,
+            attack: 0.005,
+            sustain: 0.06,
+            decay: 0.08,
+            volume: 0.35,
+        },
+    ),
+    (
+        SoundEffect::Star,
+        SfxParams {
+            waveform: Waveform::Square,
+            start_freq: 988.0,
+            end_freq: 988.0,
+            duty: 0.25,
+            arp_ratio: 1.335, // 纯四度，经典的“叮-叮”
+            arp_time: 0.06,
+            attack: 0.002,
+            sustain: 0.1,
+            decay: 0.12,
+            volume: 0.3,
+        },
+    ),
+    (
+        SoundEffect::Hit,
+        SfxParams {
+            waveform: Waveform::Noise,
+            start_freq: 1200.0,
+            end_freq: 200.0,
+            duty: 0.5,
+            arp_ratio: 1.0,
+            arp_time: 0.0,
+            attack: 0.0,
+            sustain: 0.05,
+            decay: 0.3,
+            volume: 0.5,
+        },
+    ),
+    (
+        SoundEffect::Revive,
+        SfxParams {
+            waveform: Waveform::Triangle,
+            start_freq: 262.0,
+            end_freq: 784.0,
+            duty: 0.5,
+            arp_ratio: 1.5,
+            arp_time: 0.15,
+            attack: 0.01,
+            sustain: 0.25,
+            decay: 0.2,
+            volume: 0.5,
+        },
+    ),
+    (
+        SoundEffect::WrongAnswer,
+        SfxParams {
+            waveform: Waveform::Square,
+            start_freq: 220.0,
+            end_freq: 110.0,
+            duty: 0.5,
+            arp_ratio: 1.0,
+            arp_time: 0.0,
+            attack: 0.005,
+            sustain: 0.15,
+            decay: 0.1,
+            volume: 0.3,
+        },
+    ),
+    (
+        SoundEffect::PowerUp,
+        SfxParams {
+            waveform: Waveform::Square,
+            start_freq: 523.0,
+            end_freq: 1046.0,
+            duty: 0.25,
+            arp_ratio: 1.25, // 大三度
+            arp_time: 0.08,
+            attack: 0.005,
+            sustain: 0.16,
+            decay: 0.12,
+            volume: 0.3,
+        },
+    ),
+    (
+        SoundEffect::ShieldBreak,
+        SfxParams {
+            waveform: Waveform::Noise,
+            start_freq: 3000.0,
+            end_freq: 800.0,
+            duty: 0.5,
+            arp_ratio: 1.0,
+            arp_time: 0.0,
+            attack: 0.0,
+            sustain: 0.03,
+            decay: 0.15,
+            volume: 0.35,
+        },
+    ),
+];
+
+// 生成单声道 16 位 PCM 采样
+pub fn synthesize(params: &SfxParams) -> Vec<i16> {
+    let count = params.sample_count();
+    let mut samples = Vec::with_capacity(count);
+
+    let mut phase = 0.0f32;
+    // 噪声使用固定种子的 xorshift，保证同一参数每次生成完全相同的波形
+    let mut noise_state: u32 = 0x9E37_79B9;
+    let mut noise_value = 0.0f32;
+
+    for i in 0..count {
+        let t = i as f32 / SAMPLE_RATE as f32;
+        let progress = t / params.duration();
+
+        let mut freq = params.start_freq + (params.end_freq - params.start_freq) * progress;
+        if params.arp_ratio != 1.0 && t >= params.arp_time {
+            freq *= params.arp_ratio;
+        }
+
+        let last_phase = phase;
+        phase = (phase + freq / SAMPLE_RATE as f32).fract();
+
+        let raw = match params.waveform {
+            Waveform::Square => {
+                if phase < params.duty {
+                    1.0
+                } else {
+                    -1.0
+                }
+            }
+            Waveform::Triangle => 1.0 - 4.0 * (phase - 0.5).abs(),
+            Waveform::Noise => {
+                // 每个周期更换一次噪声值，频率越低噪声越“粗”
+                if phase < last_phase {
+                    noise_state ^= noise_state << 13;
+                    noise_state ^= noise_state >> 17;
+                    noise_state ^= noise_state << 5;
+                    noise_value = noise_state as f32 / u32::MAX as f32 * 2.0 - 1.0;
+                }
+                noise_value
+            }
+        };
+
+        let value = raw * envelope(params, t) * params.volume;
+        samples.push((value.clamp(-1.0, 1.0) * i16::MAX as f32) as i16);
+    }
+
+    samples
+}
+
+fn envelope(params: &SfxParams, t: f32) -> f32 {
+    if t < params.attack {
+        t / params.attack
+    } else if t < params.attack + params.sustain {
+        1.0
+    } else if params.decay > 0.0 {
+        let decay_t = t - params.attack - params.sustain;
+        (1.0 - decay_t / params.decay).max(0.0)
+    } else {
+        0.0
+    }
+}
+
+// 把 PCM 数据包装成内存中的 WAV 文件，交给音频层加载
+pub fn encode_wav(samples: &[i16]) -> Vec<u8> {
+    let data_len = (samples.len() * 2) as u32;
+    let mut bytes = Vec::with_capacity(44 + data_len as usize);
+
+    bytes.extend_from_slice(b"RIFF");
+    bytes.extend_from_slice(&(36 + data_len).to_le_bytes());
+    bytes.extend_from_slice(b"WAVE");
+
+    bytes.extend_from_slice(b"fmt ");
+    bytes.extend_from_slice(&16u32.to_le_bytes());
+    bytes.extend_from_slice(&1u16.to_le_bytes()); // PCM
+    bytes.extend_from_slice(&1u16.to_le_bytes()); // 单声道
+    bytes.extend_from_slice(&SAMPLE_RATE.to_le_bytes());
+    bytes.extend_from_slice(&(SAMPLE_RATE * 2).to_le_bytes());
+    bytes.extend_from_slice(&2u16.to_le_bytes());
+    bytes.extend_from_slice(&16u16.to_le_bytes());
+
+    bytes.extend_from_slice(b"data");
+    bytes.extend_from_slice(&data_len.to_le_bytes());
+    for sample in samples {
+        bytes.extend_from_slice(&sample.to_le_bytes());
+    }
+
+    bytes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn params(effect: SoundEffect) -> &'static SfxParams {
+        SFX_TABLE
+            .iter()
+            .find(|(e, _)| *e == effect)
+            .map(|(_, p)| p)
+            .expect("every sound effect has an entry in SFX_TABLE")
+    }
+
+    #[test]
+    fn every_effect_has_params() {
+        for effect in [
+            SoundEffect::Jump,
+            SoundEffect::Star,
+            SoundEffect::Hit,
+            SoundEffect::Revive,
+            SoundEffect::WrongAnswer,
+            SoundEffect::PowerUp,
+            SoundEffect::ShieldBreak,
+        ] {
+            assert!(params(effect).duration() > 0.0);
+        }
+    }
+
+    #[test]
+    fn sample_count_matches_duration() {
+        let p = params(SoundEffect::Jump);
+        let samples = synthesize(p);
+        assert_eq!(samples.len(), p.sample_count());
+    }
+
+    #[test]
+    fn envelope_starts_and_ends_silent() {
+        for (_, p) in SFX_TABLE.iter() {
+            let samples = synthesize(p);
+            assert!(samples.last().unwrap().unsigned_abs() < 200);
+            if p.attack > 0.0 {
+                assert_eq!(samples[0], 0);
+            }
+        }
+    }
+
+    #[test]
+    fn peak_respects_volume() {
+        for (_, p) in SFX_TABLE.iter() {
+            let peak = synthesize(p)
+                .iter()
+                .map(|s| s.unsigned_abs())
+                .max()
+                .unwrap();
+            let limit = (p.volume * i16::MAX as f32) as u16 + 1;
+            assert!(peak <= limit);
+            assert!(peak > limit / 2);
+        }
+    }
+
+    #[test]
+    fn square_wave_uses_duty_cycle() {
+        let p = SfxParams {
+            waveform: Waveform::Square,
+            start_freq: 441.0,
+            end_freq: 441.0,
+            duty: 0.25,
+            arp_ratio: 1.0,
+            arp_time: 0.0,
+            attack: 0.0,
+            sustain: 1.0,
+            decay: 0.0,
+            volume: 1.0,
+        };
+        let samples = synthesize(&p);
+        let positive = samples.iter().filter(|s| **s > 0).count();
+        let ratio = positive as f32 / samples.len() as f32;
+        assert!((ratio - 0.25).abs() < 0.02);
+    }
+
+    #[test]
+    fn synthesis_is_deterministic() {
+        let p = params(SoundEffect::Hit);
+        assert_eq!(synthesize(p), synthesize(p));
+    }
+
+    #[test]
+    fn wav_header_is_valid() {
+        let samples = synthesize(params(SoundEffect::Star));
+        let wav = encode_wav(&samples);
+        assert_eq!(&wav[0..4], b"RIFF");
+        assert_eq!(&wav[8..12], b"WAVE");
+        assert_eq!(wav.len(), 44 + samples.len() * 2);
+        let data_len = u32::from_le_bytes([wav[40], wav[41], wav[42], wav[43]]);
+        assert_eq!(data_len as usize, samples.len() * 2);
+    }
+}
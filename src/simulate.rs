@@ -0,0 +1,48 @@
+use crate::bot::Bot;
+use crate::defs::EntityDefs;
+use crate::input::ActionState;
+use crate::{GameState, FIXED_DT};
+
+// 无窗口模拟：机器人依次用种子 0..runs 各跑一局，撞到障碍物或超过 seconds 秒后结束，
+// 最后输出分数和存活时间的统计，用于调整障碍物定义和速度曲线
+pub fn run(runs: u32, seconds: f32, make_bot: impl Fn() -> Box<dyn Bot>) {
+    let max_ticks = (seconds / FIXED_DT) as u64;
+    let defs = EntityDefs::load();
+    let mut scores = Vec::new();
+    let mut survival_ticks = 0;
+    let mut deaths = 0;
+    let bot_name = make_bot().name();
+
+    for seed in 0..runs {
+        let mut game = GameState::new(defs.clone());
+        game.reseed(seed as u64);
+        game.bot = Some(make_bot());
+        let actions = ActionState::default();
+        while !game.game_over && game.ticks < max_ticks {
+            game.update(&actions);
+        }
+        if game.game_over {
+            deaths += 1;
+        }
+        survival_ticks += game.ticks;
+        scores.push(game.score);
+    }
+
+    scores.sort_unstable();
+    let count = scores.len().max(1);
+    println!(
+        "Simulation: {} runs, bot '{}', up to {} s each",
+        runs, bot_name, seconds
+    );
+    println!("  deaths: {}/{}", deaths, runs);
+    println!(
+        "  score: avg {:.1}, median {}, max {}",
+        scores.iter().sum::<u32>() as f64 / count as f64,
+        scores.get(scores.len() / 2).copied().unwrap_or(0),
+        scores.last().copied().unwrap_or(0)
+    );
+    println!(
+        "  survival: avg {:.1} s",
+        survival_ticks as f64 * FIXED_DT as f64 / count as f64
+    );
+}
@@ -0,0 +1,297 @@
+use std::fs;
+use std::path::Path;
+
+use macroquad::prelude::*;
+
+pub const DEFAULT_THEME: &str = "colorful";
+const THEMES_DIR: &str = "themes";
+
+// 内置主题直接编译进可执行文件，themes/ 目录下的同名文件可以覆盖它们
+const BUILTIN_THEMES: [(&str, &str); 3] = [
+    ("colorful", include_str!("../themes/colorful.toml")),
+    ("classic", include_str!("../themes/classic.toml")),
+    (
+        "high_contrast",
+        include_str!("../themes/high_contrast.toml"),
+    ),
+];
+
+// 所有绘制代码使用的颜色都来自调色板
+#[derive(Clone)]
+pub struct Palette {
+    pub background: Color,
+    pub ground: Color,
+    pub ground_secondary: Color,
+    pub dino: Color,
+    pub dino_detail: Color,
+    pub cactus: Color,
+    pub star: Color,
+    pub star_outline: Color,
+    pub cloud: Color,
+    pub hill: Color,
+    pub stone_colors: Vec<Color>,
+    pub stone_highlight: Color,
+    pub pebble_colors: Vec<Color>,
+    pub pebble_highlight: Color,
+    pub text: Color,
+    pub text_secondary: Color,
+    pub title: Color,
+    pub input: Color,
+    pub danger: Color,
+    pub success: Color,
+    pub overlay: Color,
+    pub panel: Color,
+    pub panel_border: Color,
+    pub button: Color,
+    pub highlight: Color,
+}
+
+impl Default for Palette {
+    fn default() -> Self {
+        Palette::parse(BUILTIN_THEMES[0].1).expect("built-in theme is valid")
+    }
+}
+
+impl Palette {
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let table: toml::Table = text
+            .parse()
+            .map_err(|err: toml::de::Error| err.to_string())?;
+
+        let color = |key: &str| -> Result<Color, String> {
+            let value = table
+                .get(key)
+                .ok_or_else(|| format!("missing color '{}'", key))?;
+            let hex = value
+                .as_str()
+                .ok_or_else(|| format!("color '{}' must be a string like \"#RRGGBB\"", key))?;
+            parse_hex(hex).ok_or_else(|| format!("invalid color '{}' for '{}'", hex, key))
+        };
+        let colors = |key: &str| -> Result<Vec<Color>, String> {
+            let list = table
+                .get(key)
+                .and_then(|value| value.as_array())
+                .ok_or_else(|| format!("missing color list '{}'", key))?;
+            if list.is_empty() {
+                return Err(format!("color list '{}' must not be empty", key));
+            }
+            list.iter()
+                .map(|value| {
+                    value
+                        .as_str()
+                        .and_then(parse_hex)
+                        .ok_or_else(|| format!("invalid color {} in '{}'", value, key))
+                })
+                .collect()
+        };
+
+        Ok(Palette {
+            background: color("background")?,
+            ground: color("ground")?,
+            ground_secondary: color("ground_secondary")?,
+            dino: color("dino")?,
+            dino_detail: color("dino_detail")?,
+            cactus: color("cactus")?,
+            star: color("star")?,
+            star_outline: color("star_outline")?,
+            cloud: color("cloud")?,
+            hill: color("hill")?,
+            stone_colors: colors("stone_colors")?,
+            stone_highlight: color("stone_highlight")?,
+            pebble_colors: colors("pebble_colors")?,
+            pebble_highlight: color("pebble_highlight")?,
+            text: color("text")?,
+            text_secondary: color("text_secondary")?,
+            title: color("title")?,
+            input: color("input")?,
+            danger: color("danger")?,
+            success: color("success")?,
+            overlay: color("overlay")?,
+            panel: color("panel")?,
+            panel_border: color("panel_border")?,
+            button: color("button")?,
+            highlight: color("highlight")?,
+        })
+    }
+
+    // 夜晚的调色板：把每个颜色向反色渐变，night 为 0 表示白天，1 表示完全进入夜晚
+    pub fn at_night(&self, night: f32) -> Palette {
+        if night <= 0.0 {
+            return self.clone();
+        }
+        self.map(|c| invert(c, night))
+    }
+
+    // 不属于调色板的固定颜色（恐龙外观等）按浅色背景设计，画在这套调色板上时背景越暗越接近反色：
+    // 白色背景的主题在夜晚与 at_night 的处理完全一致，黑色背景的主题上直接使用反色
+    pub fn adapt(&self, color: Color) -> Color {
+        let background = self.background;
+        let brightness = 0.299 * background.r + 0.587 * background.g + 0.114 * background.b;
+        invert(color, 1.0 - brightness)
+    }
+
+    // 所有颜色的不透明度乘以 alpha，用于淡入的界面
+    pub fn faded(&self, alpha: f32) -> Palette {
+        self.map(|c| Color {
+            a: c.a * alpha,
+            ..c
+        })
+    }
+
+    fn map(&self, f: impl Fn(Color) -> Color) -> Palette {
+        Palette {
+            background: f(self.background),
+            ground: f(self.ground),
+            ground_secondary: f(self.ground_secondary),
+            dino: f(self.dino),
+            dino_detail: f(self.dino_detail),
+            cactus: f(self.cactus),
+            star: f(self.star),
+            star_outline: f(self.star_outline),
+            cloud: f(self.cloud),
+            hill: f(self.hill),
+            stone_colors: self.stone_colors.iter().map(|c| f(*c)).collect(),
+            stone_highlight: f(self.stone_highlight),
+            pebble_colors: self.pebble_colors.iter().map(|c| f(*c)).collect(),
+            pebble_highlight: f(self.pebble_highlight),
+            text: f(self.text),
+            text_secondary: f(self.text_secondary),
+            title: f(self.title),
+            input: f(self.input),
+            danger: f(self.danger),
+            success: f(self.success),
+            overlay: f(self.overlay),
+            panel: f(self.panel),
+            panel_border: f(self.panel_border),
+            button: f(self.button),
+            highlight: f(self.highlight),
+        }
+    }
+}
+
+// 把颜色向反色渐变，amount 为 1 时完全反色
+fn invert(c: Color, amount: f32) -> Color {
+    Color::new(
+        c.r + (1.0 - 2.0 * c.r) * amount,
+        c.g + (1.0 - 2.0 * c.g) * amount,
+        c.b + (1.0 - 2.0 * c.b) * amount,
+        c.a,
+    )
+}
+
+// 解析 "#RRGGBB" 或 "#RRGGBBAA"
+fn parse_hex(hex: &str) -> Option<Color> {
+    let digits = hex.strip_prefix('#')?;
+    if digits.len() != 6 && digits.len() != 8 {
+        return None;
+    }
+    let channel = |i: usize| u8::from_str_radix(digits.get(i..i + 2)?, 16).ok();
+    let alpha = if digits.len() == 8 { channel(6)? } else { 255 };
+    Some(Color::from_rgba(
+        channel(0)?,
+        channel(2)?,
+        channel(4)?,
+        alpha,
+    ))
+}
+
+pub struct Theme {
+    pub name: String,
+    pub palette: Palette,
+}
+
+// 加载内置主题和 themes/ 目录下的主题文件，文件名即主题名
+pub fn load_themes() -> Vec<Theme> {
+    let mut themes: Vec<Theme> = BUILTIN_THEMES
+        .iter()
+        .map(|(name, text)| Theme {
+            name: name.to_string(),
+            palette: Palette::parse(text).expect("built-in theme is valid"),
+        })
+        .collect();
+
+    let Ok(entries) = fs::read_dir(THEMES_DIR) else {
+        return themes;
+    };
+    let mut paths: Vec<_> = entries
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.extension().is_some_and(|ext| ext == "toml"))
+        .collect();
+    paths.sort();
+
+    for path in paths {
+        let Some(name) = path.file_stem().and_then(|stem| stem.to_str()) else {
+            continue;
+        };
+        match load_theme_file(&path) {
+            Ok(palette) => {
+                if let Some(theme) = themes.iter_mut().find(|theme| theme.name == name) {
+                    theme.palette = palette;
+                } else {
+                    themes.push(Theme {
+                        name: name.to_string(),
+                        palette,
+                    });
+                }
+            }
+            Err(err) => println!("Warning: invalid theme {}: {}", path.display(), err),
+        }
+    }
+
+    themes
+}
+
+fn load_theme_file(path: &Path) -> Result<Palette, String> {
+    let text = fs::read_to_string(path).map_err(|err| err.to_string())?;
+    Palette::parse(&text)
+}
+
+pub fn find_theme<'a>(themes: &'a [Theme], name: &str) -> &'a Theme {
+    themes
+        .iter()
+        .find(|theme| theme.name == name)
+        .unwrap_or(&themes[0])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn builtin_themes_parse_and_bad_files_report_the_key() {
+        for (name, text) in BUILTIN_THEMES {
+            assert!(Palette::parse(text).is_ok(), "{}", name);
+        }
+        let classic = Palette::parse(BUILTIN_THEMES[1].1).unwrap();
+        assert_eq!(classic.background, Color::from_rgba(0xF7, 0xF7, 0xF7, 255));
+        assert_eq!(
+            parse_hex("#11223380"),
+            Some(Color::from_rgba(0x11, 0x22, 0x33, 0x80))
+        );
+        assert_eq!(parse_hex("112233"), None);
+        assert_eq!(parse_hex("#1122GG"), None);
+
+        let broken = BUILTIN_THEMES[1].1.replace("#535353", "#5353");
+        assert!(Palette::parse(&broken).err().unwrap().contains("ground"));
+        let missing = BUILTIN_THEMES[1].1.replace("hill", "mountain");
+        assert_eq!(
+            Palette::parse(&missing).err().unwrap(),
+            "missing color 'hill'"
+        );
+    }
+
+    #[test]
+    fn night_inverts_gradually_and_keeps_alpha() {
+        let day = Palette::default();
+        assert_eq!(day.at_night(0.0).background, day.background);
+
+        // 白色背景在完全进入夜晚时变黑，半途为灰色；透明度不变
+        let night = day.at_night(1.0);
+        assert_eq!(night.background, Color::new(0.0, 0.0, 0.0, 1.0));
+        assert_eq!(day.at_night(0.5).background, Color::new(0.5, 0.5, 0.5, 1.0));
+        assert_eq!(night.overlay.a, day.overlay.a);
+        for (a, b) in day.stone_colors.iter().zip(&night.stone_colors) {
+            assert!((a.r + b.r - 1.0).abs() < 1e-5);
+        }
+    }
+}
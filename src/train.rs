@@ -0,0 +1,179 @@
+use std::thread;
+
+use ::rand::rngs::StdRng;
+use ::rand::{Rng, SeedableRng};
+
+use crate::defs::EntityDefs;
+use crate::input::ActionState;
+use crate::neuro::{Genome, NeuroBot};
+use crate::{GameState, FIXED_DT};
+
+// 神经进化训练：固定拓扑的遗传算法。每一代的每个基因组都用同一组种子各跑若干局，
+// 适应度为平均分数加平均存活秒数；保留最好的几个，其余由锦标赛选出的父母交叉、变异产生。
+// 每一代换一组种子，避免只记住某几局的障碍物布局
+
+// 每代原样保留的最优基因组数量
+const ELITES: usize = 4;
+// 锦标赛选择时每次比较的基因组数量
+const TOURNAMENT_SIZE: usize = 3;
+// 每个权重发生变异的概率和变异幅度（正态分布的标准差）
+const MUTATION_RATE: f32 = 0.1;
+const MUTATION_SIZE: f32 = 0.3;
+// 训练随机数的种子，同样的参数训练结果完全相同
+const TRAIN_SEED: u64 = 2024;
+
+pub struct TrainConfig {
+    pub generations: u32,
+    pub population: usize,
+    // 每个基因组每代跑的局数
+    pub seeds: u64,
+    // 每局的最长时间
+    pub seconds: f32,
+    // 最优基因组的保存路径，每代结束后覆盖
+    pub output: String,
+}
+
+pub fn run(config: &TrainConfig) {
+    let defs = EntityDefs::load();
+    let max_ticks = (config.seconds / FIXED_DT) as u64;
+    let threads = thread::available_parallelism().map_or(1, |n| n.get());
+    let mut rng = StdRng::seed_from_u64(TRAIN_SEED);
+    let mut population: Vec<Genome> = (0..config.population.max(ELITES + 1))
+        .map(|_| Genome::random(&mut rng))
+        .collect();
+    println!(
+        "Training: {} generations, population {}, {} runs each, {} threads",
+        config.generations,
+        population.len(),
+        config.seeds,
+        threads
+    );
+
+    for generation in 0..config.generations {
+        let first_seed = generation as u64 * config.seeds;
+        let seeds = first_seed..first_seed + config.seeds;
+        evaluate_all(&mut population, &defs, seeds, max_ticks, threads);
+        population.sort_by(|a, b| b.fitness.total_cmp(&a.fitness));
+
+        let mean =
+            population.iter().map(|genome| genome.fitness).sum::<f32>() / population.len() as f32;
+        println!(
+            "  generation {}/{}: best {:.1}, mean {:.1}",
+            generation + 1,
+            config.generations,
+            population[0].fitness,
+            mean
+        );
+        if let Err(err) = population[0].save(&config.output) {
+            eprintln!("Warning: failed to save {}: {}", config.output, err);
+        }
+
+        population = next_generation(&population, &mut rng);
+    }
+    println!("Best genome saved to {}", config.output);
+}
+
+// 把种群分给多个线程并行评估，每个线程各自创建游戏
+fn evaluate_all(
+    population: &mut [Genome],
+    defs: &EntityDefs,
+    seeds: std::ops::Range<u64>,
+    max_ticks: u64,
+    threads: usize,
+) {
+    let chunk_size = population.len().div_ceil(threads);
+    thread::scope(|scope| {
+        for chunk in population.chunks_mut(chunk_size) {
+            let seeds = seeds.clone();
+            scope.spawn(move || {
+                for genome in chunk {
+                    genome.fitness = evaluate(genome, defs, seeds.clone(), max_ticks);
+                }
+            });
+        }
+    });
+}
+
+fn evaluate(
+    genome: &Genome,
+    defs: &EntityDefs,
+    seeds: std::ops::Range<u64>,
+    max_ticks: u64,
+) -> f32 {
+    let runs = seeds.end - seeds.start;
+    let actions = ActionState::default();
+    let mut total = 0.0;
+    for seed in seeds {
+        let mut game = GameState::new(defs.clone());
+        game.reseed(seed);
+        game.bot = Some(Box::new(NeuroBot::new(genome.clone())));
+        while !game.game_over && game.ticks < max_ticks {
+            game.update(&actions);
+        }
+        total += game.score as f32 + game.ticks as f32 * FIXED_DT;
+    }
+    total / runs.max(1) as f32
+}
+
+// population 已按适应度从高到低排序
+fn next_generation(population: &[Genome], rng: &mut StdRng) -> Vec<Genome> {
+    let mut next: Vec<Genome> = population[..ELITES].to_vec();
+    while next.len() < population.len() {
+        let mother = tournament(population, rng);
+        let father = tournament(population, rng);
+        let mut child = mother.clone();
+        for (weight, other) in child.weights.iter_mut().zip(&father.weights) {
+            if rng.gen_bool(0.5) {
+                *weight = *other;
+            }
+            if rng.gen::<f32>() < MUTATION_RATE {
+                *weight += gaussian(rng) * MUTATION_SIZE;
+            }
+        }
+        next.push(child);
+    }
+    next
+}
+
+fn tournament<'a>(population: &'a [Genome], rng: &mut StdRng) -> &'a Genome {
+    (0..TOURNAMENT_SIZE)
+        .map(|_| &population[rng.gen_range(0..population.len())])
+        .max_by(|a, b| a.fitness.total_cmp(&b.fitness))
+        .unwrap_or(&population[0])
+}
+
+// 标准正态分布（Box-Muller 变换）
+fn gaussian(rng: &mut StdRng) -> f32 {
+    let u1: f32 = rng.gen_range(f32::EPSILON..1.0);
+    let u2: f32 = rng.gen();
+    (-2.0 * u1.ln()).sqrt() * (std::f32::consts::TAU * u2).cos()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn elites_survive_into_next_generation() {
+        let mut rng = StdRng::seed_from_u64(1);
+        let mut population: Vec<Genome> = (0..10).map(|_| Genome::random(&mut rng)).collect();
+        for (i, genome) in population.iter_mut().enumerate() {
+            genome.fitness = 10.0 - i as f32;
+        }
+        let next = next_generation(&population, &mut rng);
+        assert_eq!(next.len(), population.len());
+        assert_eq!(next[..ELITES], population[..ELITES]);
+    }
+
+    #[test]
+    fn parallel_evaluation_matches_serial() {
+        let defs = EntityDefs::default();
+        let mut rng = StdRng::seed_from_u64(2);
+        let mut population: Vec<Genome> = (0..6).map(|_| Genome::random(&mut rng)).collect();
+        let max_ticks = (20.0 / FIXED_DT) as u64;
+        evaluate_all(&mut population, &defs, 0..3, max_ticks, 4);
+        for genome in &population {
+            assert_eq!(genome.fitness, evaluate(genome, &defs, 0..3, max_ticks));
+        }
+    }
+}
@@ -0,0 +1,24 @@
+use macroquad::prelude::*;
+
+// 有中文字体时用字体绘制，否则退回默认字体
+pub fn draw_label(font: Option<&Font>, text: &str, x: f32, y: f32, font_size: u16, color: Color) {
+    if let Some(font) = font {
+        draw_text_ex(
+            text,
+            x,
+            y,
+            TextParams {
+                font: Some(font),
+                font_size,
+                color,
+                ..Default::default()
+            },
+        );
+    } else {
+        draw_text(text, x, y, font_size as f32, color);
+    }
+}
+
+pub fn label_width(font: Option<&Font>, text: &str, font_size: u16) -> f32 {
+    measure_text(text, font, font_size, 1.0).width
+}
@@ -0,0 +1,469 @@
+use macroquad::prelude::*;
+
+use crate::audio::SoundQueue;
+use crate::camera::Camera;
+use crate::defs::EntityDefs;
+use crate::input::{Action, ActionState, Bindings, Controls};
+use crate::particles::ParticleToggle;
+use crate::sfx::SoundEffect;
+use crate::theme::Palette;
+use crate::ui::{draw_label, label_width};
+use crate::{GameState, FIXED_DT, GROUND_Y2, MAX_STEPS_PER_FRAME};
+
+// 本地双人赛跑：两名玩家共用一个键盘，屏幕上下分屏，各自有独立的碰撞、分数和复活题。
+// 两名玩家使用同一个种子的共用赛道（见 course.rs），障碍物完全相同
+
+const PLAYERS: usize = 2;
+// 每个半屏中地面以下留出的高度
+const GROUND_MARGIN: f32 = 30.0;
+
+struct Player {
+    game: GameState,
+    bindings: Bindings,
+    // 还没有被任何一步处理的按键事件
+    carried: ActionState,
+    // 每名玩家各自推进：慢动作只放慢拾取它的一方，赛道按各自的步数复制，两边仍然相同
+    accumulator: f32,
+}
+
+impl Player {
+    fn new(game: GameState, index: usize) -> Self {
+        Self {
+            game,
+            bindings: Bindings::versus(index),
+            carried: ActionState::default(),
+            accumulator: 0.0,
+        }
+    }
+
+    // 按经过的时间推进固定步长，按键事件只交给第一步，没有推进时留到下一帧
+    fn advance(&mut self, dt: f32, mut actions: ActionState) {
+        actions.merge_pressed(&self.carried);
+        self.accumulator = (self.accumulator + dt * self.game.time_scale())
+            .min(FIXED_DT * MAX_STEPS_PER_FRAME as f32);
+        let mut stepped = false;
+        while self.accumulator >= FIXED_DT {
+            self.game.update(&actions);
+            actions.clear_pressed();
+            self.accumulator -= FIXED_DT;
+            stepped = true;
+        }
+        self.carried = if stepped {
+            ActionState::default()
+        } else {
+            actions
+        };
+    }
+
+    // 撞到障碍物且不再答题复活
+    fn finished(&self) -> bool {
+        self.game.game_over && !self.game.showing_math_question
+    }
+}
+
+pub struct Versus {
+    players: Vec<Player>,
+    paused: bool,
+    // 两名玩家的音效
+    sounds: SoundQueue,
+}
+
+impl Versus {
+    pub fn new(
+        defs: &EntityDefs,
+        view_width: f32,
+        font: Option<Font>,
+        palette: &Palette,
+        particles: &ParticleToggle,
+    ) -> Self {
+        let sounds = SoundQueue::default();
+        let players = (0..PLAYERS)
+            .map(|index| {
+                let mut game = GameState::new(defs.clone());
+                game.font = font.clone();
+                game.palette = palette.clone();
+                game.window_width = view_width;
+                game.subscribe_feedback(&sounds, particles);
+                Player::new(game, index)
+            })
+            .collect();
+        let mut versus = Self {
+            players,
+            paused: false,
+            sounds,
+        };
+        versus.restart();
+        versus
+    }
+
+    // 用新的种子重新开始，两名玩家的赛道和复活题完全相同
+    pub fn restart(&mut self) {
+        let seed = ::rand::random();
+        for player in &mut self.players {
+            player.game.reset();
+            player.game.reseed(seed);
+            player.game.share_course();
+            player.carried = ActionState::default();
+            player.accumulator = 0.0;
+        }
+        self.paused = false;
+    }
+
+    // 热重载的定义从下一局开始生效
+    pub fn set_defs(&mut self, defs: &EntityDefs) {
+        for player in &mut self.players {
+            player.game.reload_defs(defs.clone());
+        }
+    }
+
+    pub fn set_palette(&mut self, palette: &Palette) {
+        for player in &mut self.players {
+            player.game.palette = palette.clone();
+        }
+    }
+
+    pub fn set_shake(&mut self, intensity: f32) {
+        for player in &mut self.players {
+            player.game.camera.intensity = intensity;
+        }
+    }
+
+    pub fn finished(&self) -> bool {
+        self.players.iter().all(Player::finished)
+    }
+
+    // 每帧调用一次：shared 为共用的按键（暂停、重新开始），每名玩家的按键按各自的绑定读取
+    pub fn update(&mut self, dt: f32, controls: &Controls, shared: &ActionState) {
+        if self.finished() {
+            if shared.pressed(Action::Restart) {
+                self.restart();
+            }
+            return;
+        }
+        if shared.pressed(Action::Pause) {
+            self.paused = !self.paused;
+        }
+        if self.paused {
+            return;
+        }
+
+        for player in &mut self.players {
+            let actions = controls.actions_for(&player.bindings);
+            player.advance(dt, actions);
+        }
+    }
+
+    pub fn take_sounds(&mut self) -> Vec<SoundEffect> {
+        self.sounds.borrow_mut().drain(..).collect()
+    }
+
+    // 分数高者获胜，同分时坚持更久者获胜，仍相同则为平局
+    fn winner(&self) -> Option<usize> {
+        let key = |player: &Player| (player.game.score, player.game.ticks);
+        let (first, second) = (key(&self.players[0]), key(&self.players[1]));
+        match first.cmp(&second) {
+            std::cmp::Ordering::Greater => Some(0),
+            std::cmp::Ordering::Less => Some(1),
+            std::cmp::Ordering::Equal => None,
+        }
+    }
+
+    pub fn draw(&self) {
+        let font = self.players[0].game.font.as_ref();
+        clear_background(self.players[0].game.current_palette().background);
+
+        // 玩家 1 在上半屏，玩家 2 在下半屏，每个半屏以 1:1 的比例显示地面附近的区域
+        let (width, half) = (screen_width(), screen_height() / 2.0);
+        let top = GROUND_Y2 + GROUND_MARGIN - half;
+        for (index, player) in self.players.iter().enumerate() {
+            let palette = player.game.current_palette();
+            let viewport_y = if index == 0 { half as i32 } else { 0 };
+            let view = Rect::new(0.0, top, width, half);
+            let viewport = Some((0, viewport_y, width as i32, half as i32));
+            // 场景随这名玩家的屏幕震动晃动，分数和复活题不晃动
+            Camera::steady(view, viewport);
+            draw_rectangle(0.0, top, width, half, palette.background);
+            player.game.camera.set(view, viewport);
+            player.game.draw_world(&palette);
+            Camera::steady(view, viewport);
+            self.draw_player_hud(index, player, top, &palette);
+        }
+        set_default_camera();
+
+        let palette = self.players[0].game.current_palette();
+        draw_line(0.0, half, width, half, 3.0, palette.panel_border);
+        if self.finished() {
+            self.draw_results(&palette);
+        } else if self.paused {
+            let paused_text = if font.is_some() {
+                "已暂停 - 按 P 继续"
+            } else {
+                "Paused - press P to resume"
+            };
+            draw_label(
+                font,
+                paused_text,
+                width / 2.0 - label_width(font, paused_text, 30) / 2.0,
+                half - 10.0,
+                30,
+                palette.input,
+            );
+        }
+    }
+
+    // 半屏左上角的分数和按键提示，以及这名玩家的复活题
+    fn draw_player_hud(&self, index: usize, player: &Player, top: f32, palette: &Palette) {
+        let font = player.game.font.as_ref();
+        let chinese = font.is_some();
+        let game = &player.game;
+        let score_text = if chinese {
+            format!("玩家 {}  分数: {}", index + 1, game.score)
+        } else {
+            format!("Player {}  Score: {}", index + 1, game.score)
+        };
+        draw_label(font, &score_text, 20.0, top + 30.0, 26, palette.text);
+        let keys_text = match (index, chinese) {
+            (0, true) => "W 跳跃  S 下蹲",
+            (0, false) => "W jump  S duck",
+            (_, true) => "↑ 跳跃  ↓ 下蹲",
+            (_, false) => "UP jump  DOWN duck",
+        };
+        draw_label(
+            font,
+            keys_text,
+            20.0,
+            top + 52.0,
+            18,
+            palette.text_secondary,
+        );
+
+        let center_x = screen_width() / 2.0;
+        let center_y = top + screen_height() / 4.0;
+        if let (true, Some(question)) = (game.showing_math_question, &game.math_question) {
+            let palette = &palette.faded(game.quiz_alpha());
+            // 半屏放不下完整的答题界面，只显示题目、答案和这名玩家的按键
+            draw_rectangle(
+                center_x - 220.0,
+                center_y - 60.0,
+                440.0,
+                120.0,
+                palette.panel,
+            );
+            draw_rectangle_lines(
+                center_x - 220.0,
+                center_y - 60.0,
+                440.0,
+                120.0,
+                3.0,
+                palette.panel_border,
+            );
+            let question_text = if chinese {
+                format!("答题复活！ {}", question.question)
+            } else {
+                format!("Revive! {}", question.question)
+            };
+            draw_label(
+                font,
+                &question_text,
+                center_x - label_width(font, &question_text, 30) / 2.0,
+                center_y - 20.0,
+                30,
+                palette.title,
+            );
+            let answer_text = if chinese {
+                format!("答案: {}", game.input_buffer)
+            } else {
+                format!("Answer: {}", game.input_buffer)
+            };
+            draw_label(
+                font,
+                &answer_text,
+                center_x - 100.0,
+                center_y + 15.0,
+                26,
+                palette.input,
+            );
+            let hint_text = match (index, chinese) {
+                (0, true) => "主键盘数字作答，E 确认，Q 退格，X 放弃",
+                (0, false) => "Top-row digits, E submit, Q erase, X give up",
+                (_, true) => "小键盘数字作答，回车确认，退格删除，右 Shift 放弃",
+                (_, false) => "Numpad digits, ENTER submit, BACKSPACE erase, RIGHT SHIFT give up",
+            };
+            draw_label(
+                font,
+                hint_text,
+                center_x - label_width(font, hint_text, 16) / 2.0,
+                center_y + 45.0,
+                16,
+                palette.text_secondary,
+            );
+        } else if player.finished() && !self.finished() {
+            let waiting_text = if chinese {
+                "已结束，等待对手"
+            } else {
+                "Finished - waiting for the other player"
+            };
+            draw_label(
+                font,
+                waiting_text,
+                center_x - label_width(font, waiting_text, 30) / 2.0,
+                center_y,
+                30,
+                palette.danger,
+            );
+        }
+    }
+
+    // 两人都结束后的结果：胜者和双方的分数、星星、坚持时间、复活次数
+    fn draw_results(&self, palette: &Palette) {
+        let font = self.players[0].game.font.as_ref();
+        let chinese = font.is_some();
+        let (center_x, center_y) = (screen_width() / 2.0, screen_height() / 2.0);
+        draw_rectangle(0.0, 0.0, screen_width(), screen_height(), palette.overlay);
+        draw_rectangle(
+            center_x - 220.0,
+            center_y - 130.0,
+            440.0,
+            260.0,
+            palette.panel,
+        );
+        draw_rectangle_lines(
+            center_x - 220.0,
+            center_y - 130.0,
+            440.0,
+            260.0,
+            3.0,
+            palette.panel_border,
+        );
+
+        let title = match (self.winner(), chinese) {
+            (Some(index), true) => format!("玩家 {} 获胜！", index + 1),
+            (Some(index), false) => format!("Player {} wins!", index + 1),
+            (None, true) => "平局！".to_string(),
+            (None, false) => "It's a draw!".to_string(),
+        };
+        draw_label(
+            font,
+            &title,
+            center_x - label_width(font, &title, 36) / 2.0,
+            center_y - 85.0,
+            36,
+            palette.title,
+        );
+
+        let labels = if chinese {
+            ["分数", "星星", "时间", "复活"]
+        } else {
+            ["Score", "Stars", "Time", "Revives"]
+        };
+        let column_x = [center_x - 180.0, center_x - 20.0, center_x + 100.0];
+        for index in 0..self.players.len() {
+            let name = if chinese {
+                format!("玩家 {}", index + 1)
+            } else {
+                format!("Player {}", index + 1)
+            };
+            let color = if self.winner() == Some(index) {
+                palette.success
+            } else {
+                palette.text
+            };
+            draw_label(font, &name, column_x[index + 1], center_y - 45.0, 22, color);
+        }
+        for (row, label) in labels.iter().enumerate() {
+            let y = center_y - 15.0 + row as f32 * 28.0;
+            draw_label(font, label, column_x[0], y, 22, palette.text_secondary);
+            for (index, player) in self.players.iter().enumerate() {
+                let game = &player.game;
+                let value = match row {
+                    0 => game.score.to_string(),
+                    1 => game.stars.to_string(),
+                    2 => format!("{:.1}s", game.ticks as f32 * FIXED_DT),
+                    _ => game.recording.revives.len().to_string(),
+                };
+                draw_label(font, &value, column_x[index + 1], y, 22, palette.text);
+            }
+        }
+
+        let hint_text = if chinese {
+            "按 R 再来一局，ESC 返回标题画面"
+        } else {
+            "R to race again, ESC for title screen"
+        };
+        draw_label(
+            font,
+            hint_text,
+            center_x - label_width(font, hint_text, 20) / 2.0,
+            center_y + 110.0,
+            20,
+            palette.text_secondary,
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bot::ReflexBot;
+
+    #[test]
+    fn players_race_on_identical_courses() {
+        // 一名玩家由机器人操作，另一名一直不动但无敌，两边的障碍物位置应完全相同
+        let mut players: Vec<Player> = (0..PLAYERS)
+            .map(|index| {
+                let mut game = GameState::new(EntityDefs::default());
+                game.reseed(9);
+                game.share_course();
+                Player::new(game, index)
+            })
+            .collect();
+        players[0].game.bot = Some(Box::new(ReflexBot));
+        players[1].game.invincible = true;
+
+        let idle = ActionState::default();
+        for _ in 0..60 * 30 {
+            for player in &mut players {
+                player.game.update(&idle);
+            }
+            if players[0].game.game_over {
+                break;
+            }
+        }
+        let obstacles = |player: &Player| -> Vec<Vec2> {
+            player
+                .game
+                .world
+                .iter()
+                .filter(|entity| entity.is_damaging())
+                .map(|entity| entity.pos)
+                .collect()
+        };
+        assert!(players[0].game.ticks > 60);
+        assert_eq!(players[0].game.ticks, players[1].game.ticks);
+        assert!(!obstacles(&players[0]).is_empty());
+        assert_eq!(obstacles(&players[0]), obstacles(&players[1]));
+    }
+
+    #[test]
+    fn slow_motion_only_slows_the_player_who_picked_it_up() {
+        let mut players: Vec<Player> = (0..PLAYERS)
+            .map(|index| {
+                let mut game = GameState::new(EntityDefs::default());
+                game.reseed(4);
+                game.share_course();
+                game.invincible = true;
+                Player::new(game, index)
+            })
+            .collect();
+        players[1].game.activate_power_up("slow_motion");
+
+        // 一秒内不动的玩家走满 60 步，慢动作的玩家只走 36 步左右
+        for _ in 0..60 {
+            for player in &mut players {
+                player.advance(FIXED_DT, ActionState::default());
+            }
+        }
+        assert_eq!(players[0].game.ticks, 60);
+        assert!((35..=37).contains(&players[1].game.ticks));
+    }
+}